@@ -1,3 +1,21 @@
+// Recursive aggregation of the `unif` proofs — NOT IMPLEMENTED, the sketch below is kept for reference.
+//
+// It cannot be made to work on top of the current proving stack:
+// - the unification proofs are zcash `halo2_proofs` 0.3 proofs over Pasta with the IPA commitment
+//   scheme (`Params<EqAffine>`, `Blake2bWrite<_, EqAffine, Challenge255<_>>`);
+// - `snark-verifier` 0.2.3 is built on the axiom `halo2-base` / `halo2-ecc` fork and only verifies
+//   BN254 + KZG (SHPLONK / GWC) proofs, its `PlonkProtocol` / `Halo2Loader` cannot be fed a
+//   zcash `VerifyingKey<EqAffine>` or an IPA transcript.
+//
+// Folding the proof tree into one artifact needs one of:
+// - moving UnificationCircuit to halo2-axiom with KZG over BN254, then an aggregation circuit with
+//   `PlonkSuccinctVerifier` + `KzgAs` whose public inputs are the root goal commitment, the rules
+//   hash and the fact hashes (the per-node goal commitment chaining of `verify` moves in-circuit);
+// - or a Pasta-cycle IPA accumulation verifier (Halo style) in zcash halo2, which is not available
+//   as a gadget in halo2_gadgets 0.3.
+//
+// Until then `verify` checks every proof and the goal commitment chain off-circuit.
+
 /*use halo2_proofs::{
     circuit::{AssignedCell, Chip, Layouter, SimpleFloorPlanner, Value},
     pasta::Fp,
@@ -1,19 +1,55 @@
 use halo2_proofs::{
     circuit::{AssignedCell, Chip, Layouter, Value},
     pasta::Fp,
-    plonk::{Advice, Column, ConstraintSystem, Error},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
 };
 
 use halo2curves::ff::Field;
-use crate::utils_2::common_helpers::to_fp_value;
+use crate::{
+    chips::{
+        fact_check::{
+            compare_chip::{CompareChip, CompareConfig},
+            div_rem_chip::{DivRemChip, DivRemConfig},
+        },
+        range_check_chip::RangeCheckChip,
+    },
+    fixed::{Rounding, ROUNDING, SCALE},
+    utils_2::{common_helpers::{RANGE_CHECK_BITS, RANGE_CHECK_LIMB_BITS}, term_encoding::name_value},
+};
+
+/// Az osztó gadget (acc, dvs) bemenete
+type DivOperands = (AssignedCell<Fp,Fp>, AssignedCell<Fp,Fp>);
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Konfig
 // ─────────────────────────────────────────────────────────────────────────────
 #[derive(Clone, Debug)]
 pub struct BuiltinExprConfig {
-    /// Egyetlen "work" advice oszlop, ebben számolunk mindent (egyszerű, robusztus)
+    /// Egyetlen "work" advice oszlop, minden kapu ennek egymás utáni soraira hivatkozik
     pub work: Column<Advice>,
+    /// Range-checked a > b (>, <, >=, =< ágak)
+    pub cmp_cfg: CompareConfig,
+    /// cmp_ok = b_gt*[l>r] + b_lt*[r>l] + b_ge*(1-[r>l]) + b_le*(1-[l>r]) a work oszlop 7 során
+    pub q_cmp_ok: Selector,
+    /// Előjeles hányados/maradék gadget (div, //, mod, rem, és a *, / kerekítése)
+    pub div_rem_cfg: DivRemConfig,
+    /// az osztó gadget bemenete: [x, y, s_int, s_mul, s_fdiv, acc, dvs]
+    pub q_div_in: Selector,
+    /// out = a + b + c + d a work oszlop 5 során
+    pub q_sum4: Selector,
+    /// is-zero gadget: [x, y, inv, b] → b = [x == y]
+    pub q_is_eq: Selector,
+    /// b*(1-b) = 0
+    pub q_bool: Selector,
+    /// a*(1-b) = 0, azaz a → b
+    pub q_implies: Selector,
+    /// egy kifejezésfa-csúcs: [x, y, s_add, s_sub, s_mul, s_fdiv, s_div, s_idiv, s_mod, s_rem, q, r, res]
+    pub q_arith: Selector,
+    /// s*(a - b) = 0, azaz s → a == b (a csúcs eredménye = a tanúsított eredmény)
+    pub q_eq_if: Selector,
+    /// ok = b_ie*eq_ok + b_ne*(1-eq_ok) + b_cmp*cmp_ok a work oszlop 6 során
+    pub q_ok: Selector,
 }
 
 #[derive(Clone, Debug)]
@@ -29,605 +65,659 @@ impl Chip<Fp> for BuiltinExprChip {
     fn loaded(&self) -> &Self::Loaded { &() }
 }
 
+// is-zero gadget jobb oldala: konstans (név-egyezés) vagy másik cella
+#[derive(Clone, Copy)]
+enum EqRhs<'a> {
+    Const(Fp),
+    Cell(&'a AssignedCell<Fp, Fp>),
+}
+
 impl BuiltinExprChip {
     pub fn configure(meta: &mut ConstraintSystem<Fp>) -> BuiltinExprConfig {
         let work = meta.advice_column();
         meta.enable_equality(work);
-        BuiltinExprConfig { work }
+
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+
+        let range_cfg = RangeCheckChip::configure(meta, RANGE_CHECK_LIMB_BITS);
+        let cmp_cfg = CompareChip::configure(meta, range_cfg.clone(), RANGE_CHECK_BITS);
+        let div_rem_cfg = DivRemChip::configure(meta, range_cfg, RANGE_CHECK_BITS);
+
+        let q_sum4 = meta.selector();
+        meta.create_gate("sum of four", |meta| {
+            let q = meta.query_selector(q_sum4);
+            let [a, b, c, d, out] = [0, 1, 2, 3, 4].map(|i| meta.query_advice(work, Rotation(i)));
+            vec![q * (out - (a + b + c + d))]
+        });
+
+        let q_is_eq = meta.selector();
+        meta.create_gate("is equal (is-zero of x - y)", |meta| {
+            let q = meta.query_selector(q_is_eq);
+            let [x, y, inv, b] = [0, 1, 2, 3].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            let d = x - y;
+            vec![
+                // b = 1 - d*inv
+                q.clone() * (b.clone() - (one - d.clone() * inv)),
+                // d != 0 → b = 0
+                q * d * b,
+            ]
+        });
+
+        let q_bool = meta.selector();
+        meta.create_gate("boolean", |meta| {
+            let q = meta.query_selector(q_bool);
+            let b = meta.query_advice(work, Rotation::cur());
+            let one = Expression::Constant(Fp::ONE);
+            vec![q * b.clone() * (one - b)]
+        });
+
+        let q_implies = meta.selector();
+        meta.create_gate("implication", |meta| {
+            let q = meta.query_selector(q_implies);
+            let a = meta.query_advice(work, Rotation::cur());
+            let b = meta.query_advice(work, Rotation::next());
+            let one = Expression::Constant(Fp::ONE);
+            vec![q * a * (one - b)]
+        });
+
+        // fixpontos számok: az érték SCALE-szerese; h = 1 a fele felfelé kerekítéshez
+        let scale = Expression::Constant(Fp::from(SCALE as u64));
+        let half = Expression::Constant(if ROUNDING == Rounding::HalfUp { Fp::ONE } else { Fp::ZERO });
+
+        let q_div_in = meta.selector();
+        meta.create_gate("divider operands", |meta| {
+            let q = meta.query_selector(q_div_in);
+            let [x, y, s_int, s_mul, s_fdiv, acc, dvs] =
+                [0, 1, 2, 3, 4, 5, 6].map(|i| meta.query_advice(work, Rotation(i)));
+            let two = Expression::Constant(Fp::from(2));
+
+            // egész osztás: x / y; x*y: (2xy + h*S) / 2S; x/y: (2Sx + h*y) / 2y
+            vec![
+                q.clone() * (acc - (s_int.clone() * x.clone()
+                    + s_mul.clone() * (two.clone() * x.clone() * y.clone() + half.clone() * scale.clone())
+                    + s_fdiv.clone() * (two.clone() * scale.clone() * x + half.clone() * y.clone()))),
+                q * (dvs - (s_int * y.clone() + s_mul * two.clone() * scale.clone() + s_fdiv * two * y)),
+            ]
+        });
+
+        let q_arith = meta.selector();
+        meta.create_gate("arith node", |meta| {
+            let q = meta.query_selector(q_arith);
+            let [x, y, s_add, s_sub, s_mul, s_fdiv, s_div, s_idiv, s_mod, s_rem, qv, rv, res] =
+                [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12].map(|i| meta.query_advice(work, Rotation(i)));
+
+            // res = x + Σ s_i * (res_i - x); *, / → q (már kerekítve), div, // → S*q, mod, rem → r
+            let step = s_add * y.clone()
+                - s_sub * y
+                + (s_mul + s_fdiv) * (qv.clone() - x.clone())
+                + (s_div + s_idiv) * (scale * qv - x.clone())
+                + (s_mod + s_rem) * (rv - x.clone());
+            vec![q * (res - (x + step))]
+        });
+
+        let q_eq_if = meta.selector();
+        meta.create_gate("gated equality", |meta| {
+            let q = meta.query_selector(q_eq_if);
+            let [s, a, b] = [0, 1, 2].map(|i| meta.query_advice(work, Rotation(i)));
+            vec![q * s * (a - b)]
+        });
+
+        let q_cmp_ok = meta.selector();
+        meta.create_gate("comparison result", |meta| {
+            let q = meta.query_selector(q_cmp_ok);
+            let [b_gt, b_lt, b_ge, b_le, g_lr, g_rl, cmp_ok] =
+                [0, 1, 2, 3, 4, 5, 6].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            vec![
+                q * (cmp_ok
+                    - (b_gt * g_lr.clone()
+                        + b_lt * g_rl.clone()
+                        + b_ge * (one.clone() - g_rl)
+                        + b_le * (one - g_lr)))
+            ]
+        });
+
+        let q_ok = meta.selector();
+        meta.create_gate("final ok", |meta| {
+            let q = meta.query_selector(q_ok);
+            let [b_ie, b_ne, eq_ok, b_cmp, cmp_ok, ok] =
+                [0, 1, 2, 3, 4, 5].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            vec![q * (ok - (b_ie * eq_ok.clone() + b_ne * (one - eq_ok) + b_cmp * cmp_ok))]
+        });
+
+        BuiltinExprConfig {
+            work, cmp_cfg, q_cmp_ok, div_rem_cfg, q_div_in, q_sum4,
+            q_is_eq, q_bool, q_implies, q_arith, q_eq_if, q_ok,
+        }
     }
 
     pub fn construct(cfg: BuiltinExprConfig) -> Self {
         Self { cfg }
     }
 
+    /// A range check lookup tábla feltöltése (szintézisenként pontosan egyszer)
+    pub fn load_range_table(&self, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        RangeCheckChip::construct(self.cfg.cmp_cfg.range_cfg.clone()).load_table(layouter)
+    }
+
     #[inline]
-    fn fp_op(s: &str) -> Fp { to_fp_value(s) }
+    fn fp_op(s: &str) -> Fp { name_value(s) }
 
-    /// b = [x == c] tanú + booleanitás
-    fn eq_const_bool(
+    /// Konstans cella (a konstans oszlophoz kötve)
+    fn constant_cell(
         &self,
         mut layouter: impl Layouter<Fp>,
-        x: &AssignedCell<Fp,Fp>,
         c: Fp,
-        row0: usize,
+    ) -> Result<AssignedCell<Fp,Fp>, Error> {
+        layouter.assign_region(
+            || "constant",
+            |mut region| region.assign_advice_from_constant(|| "const", self.cfg.work, 0, c),
+        )
+    }
+
+    /// out = a + b + c + d (kapuval)
+    fn sum4(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        cells: [&AssignedCell<Fp,Fp>; 4],
     ) -> Result<AssignedCell<Fp,Fp>, Error> {
         let col = self.cfg.work;
         layouter.assign_region(
-            || "eq_const_bool",
+            || "sum4",
             |mut region| {
-                let b = region.assign_advice(
-                    || "b",
-                    col, row0,
-                    || x.value().map(|vx| if *vx == c { Fp::ONE } else { Fp::ZERO })
-                )?;
-                /*let be = region.assign_advice(
-                    || "b*(1-b)",
-                    col, row0+1,
-                    || b.value().map(|vb| *vb * (Fp::ONE - *vb))
-                )?;
-                let z = region.assign_advice(|| "z", col, row0+2, || Value::known(Fp::ZERO))?;*/
-                //region.constrain_equal(be.cell(), z.cell())?;
-                Ok(b)
+                self.cfg.q_sum4.enable(&mut region, 0)?;
+                let mut sum = Value::known(Fp::ZERO);
+                for (i, c) in cells.iter().enumerate() {
+                    c.copy_advice(|| format!("in[{i}]"), &mut region, col, i)?;
+                    sum = sum.zip(c.value()).map(|(s, v)| s + *v);
+                }
+                region.assign_advice(|| "sum", col, 4, || sum)
             }
         )
     }
 
-    /// s_add + s_sub + s_mul + s_div = 1  és mind boolean
-    /*fn enforce_onehot4(
+    /// b*(1-b) = 0 (kapuval)
+    fn assert_bool(
         &self,
         mut layouter: impl Layouter<Fp>,
-        s: [&AssignedCell<Fp,Fp>;4],
-        row: usize,
+        b: &AssignedCell<Fp,Fp>,
     ) -> Result<(), Error> {
-        let col = self.cfg.work;
         layouter.assign_region(
-            || "onehot4",
+            || "boolean",
             |mut region| {
-                for (i,si) in s.iter().enumerate() {
-                    let be = region.assign_advice(
-                        || format!("b*(1-b)[{i}]"),
-                        col, row+i,
-                        || si.value().map(|v| *v * (Fp::ONE - *v))
-                    )?;
-                    let z = region.assign_advice(|| "z", col, row+10+i, || Value::known(Fp::ZERO))?;
-                    region.constrain_equal(be.cell(), z.cell())?;
-                }
-                let sum = region.assign_advice(
-                    || "sum selectors",
-                    col, row+20,
-                    || s[0].value()
-                        .zip(s[1].value()).zip(s[2].value()).zip(s[3].value())
-                        .map(|(((a,b),c),d)| *a + *b + *c + *d)
-                )?;
-                let diff = region.assign_advice(
-                    || "sum-1",
-                    col, row+21,
-                    || sum.value().map(|v| *v - Fp::ONE)
-                )?;
-                let z = region.assign_advice(|| "z", col, row+22, || Value::known(Fp::ZERO))?;
-                region.constrain_equal(diff.cell(), z.cell())
+                self.cfg.q_bool.enable(&mut region, 0)?;
+                b.copy_advice(|| "b", &mut region, self.cfg.work, 0)?;
+                Ok(())
             }
         )
-    }*/
+    }
 
-    /// Fő API: kiértékeli a láncot és visszaad egy boolean `ok` cellát.
-    ///
-    /// Bemenet:
-    ///  - `names[p]`  : a predikátumok/builtinek nevei; p=0: "is" vagy "="; p>0: +,-,*,div
-    ///  - `args[p][a][l]`: argument mátrixok (operand mindig args[p][0][0]),
-    ///     * p=0-nál: LHS=args[0][0][0], RHS0=args[0][1][0]
-    ///
-    /// Viselkedés:
-    ///  - Ellenőrzi, hogy p=0 neve is/=
-    ///  - Akkumulátor: acc = RHS0
-    ///  - p>=1: unáris műveletek acc-ra operand=arg0[0]
-    ///  - Végül ok = [LHS == acc] * [name0 ∈ {is,=}]
-    pub fn eval_chain_equal(
+    /// a = 1 → b = 1 (kapuval)
+    fn assert_implies(
         &self,
         mut layouter: impl Layouter<Fp>,
-        names: &[AssignedCell<Fp,Fp>],
-        args: &[Vec<Vec<AssignedCell<Fp,Fp>>>],
-        must_be_true: bool,
+        a: &AssignedCell<Fp,Fp>,
+        b: &AssignedCell<Fp,Fp>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "implication",
+            |mut region| {
+                self.cfg.q_implies.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, self.cfg.work, 0)?;
+                b.copy_advice(|| "b", &mut region, self.cfg.work, 1)?;
+                Ok(())
+            }
+        )
+    }
+
+    /// b = [x == rhs], is-zero gadget: b = 1 - (x-rhs)*inv, (x-rhs)*b = 0
+    fn is_eq(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        x: &AssignedCell<Fp,Fp>,
+        rhs: EqRhs<'_>,
     ) -> Result<AssignedCell<Fp,Fp>, Error> {
-        assert!(!names.is_empty(), "names must be non-empty");
         let col = self.cfg.work;
-
-        // 0. term: is / =
-        let name0 = &names[0];
-        let lhs   = args[0][0][0].clone();
-        let rhs0  = args[0][1][0].clone();
-
-        // comparison path számára KÜLÖN clone-ok
-        let lhs_cmp = lhs.clone();
-        let rhs_cmp = rhs0.clone();
-        // név-egyezés booleánok (is vagy =)
-        let b_is = self.eq_const_bool(layouter.namespace(|| "name==is"), name0, Self::fp_op("is"), 0)?;
-        let b_eq = self.eq_const_bool(layouter.namespace(|| "name==="),  name0, Self::fp_op("="),  5)?;
-        let b_gt = self.eq_const_bool(layouter.namespace(|| "name >"), name0, Self::fp_op(">"), 10)?;
-        let b_lt = self.eq_const_bool(layouter.namespace(|| "name <"), name0, Self::fp_op("<"), 20)?;
-        let b_ge = self.eq_const_bool(layouter.namespace(|| "name >="), name0, Self::fp_op(">="), 30)?;
-        let b_le = self.eq_const_bool(layouter.namespace(|| "name <="), name0, Self::fp_op("<="), 40)?;
-
-
-        // legalább az egyik igaz: b_ie = 1 - (1-b_is)*(1-b_eq)
-        // legalább az egyik igaz: b_ie = 1 - (1-b_is)*(1-b_eq)  (EZZEL TÉRJÜNK VISSZA AZ EREDETI EQ-ÁGHOZ)
-        let b_ie = layouter.assign_region(
-            || "is_or_eq = 1 - (1-bis)*(1=)",
+        layouter.assign_region(
+            || "is_eq",
             |mut region| {
-                let col = self.cfg.work;
-                let prod_not = region.assign_advice(
-                    || "(1-bis)*(1=)",
-                    col, 20,
-                    || b_is.value().zip(b_eq.value()).map(|(bi,be)| (Fp::ONE - *bi) * (Fp::ONE - *be))
+                self.cfg.q_is_eq.enable(&mut region, 0)?;
+                let x_l = x.copy_advice(|| "x", &mut region, col, 0)?;
+                let y_l = match rhs {
+                    EqRhs::Const(c) => region.assign_advice_from_constant(|| "c", col, 1, c)?,
+                    EqRhs::Cell(y) => y.copy_advice(|| "y", &mut region, col, 1)?,
+                };
+                let d = x_l.value().zip(y_l.value()).map(|(a, b)| *a - *b);
+                region.assign_advice(
+                    || "inv",
+                    col, 2,
+                    || d.map(|dv| dv.invert().unwrap_or(Fp::ZERO))
                 )?;
                 region.assign_advice(
-                    || "is_or_eq",
-                    col, 21,
-                    || prod_not.value().map(|v| Fp::ONE - *v)
+                    || "b",
+                    col, 3,
+                    || d.map(|dv| if dv == Fp::ZERO { Fp::ONE } else { Fp::ZERO })
                 )
             }
-        )?;
+        )
+    }
 
-        // ÚJ: b_cmp = OR(>, <, >=, <=) = 1 - Π(1 - b_op)
-        let b_cmp = layouter.assign_region(
-            || "cmp_ops_or",
+    /// b = [x == c] (operátornév-egyezés)
+    fn eq_const_bool(
+        &self,
+        layouter: impl Layouter<Fp>,
+        x: &AssignedCell<Fp,Fp>,
+        c: Fp,
+    ) -> Result<AssignedCell<Fp,Fp>, Error> {
+        self.is_eq(layouter, x, EqRhs::Const(c))
+    }
+
+    /// s = 1 → a == b (kapuval)
+    fn assert_eq_if(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        s: &AssignedCell<Fp,Fp>,
+        a: &AssignedCell<Fp,Fp>,
+        b: &AssignedCell<Fp,Fp>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "gated equality",
             |mut region| {
-                let col = self.cfg.work;
-                let prod_not = region.assign_advice(
-                    || "Π(1-bop)",
-                    col, 30,
-                    || b_gt.value()
-                        .zip(b_lt.value())
-                        .zip(b_ge.value())
-                        .zip(b_le.value())
-                        .map(|(((gt,lt),ge),le)| {
-                            let one = Fp::ONE;
-                            (one - *gt) * (one - *lt) * (one - *ge) * (one - *le)
-                        })
-                )?;
-                region.assign_advice(
-                    || "b_cmp",
-                    col, 31,
-                    || prod_not.value().map(|v| Fp::ONE - *v)
-                )
+                self.cfg.q_eq_if.enable(&mut region, 0)?;
+                s.copy_advice(|| "s", &mut region, self.cfg.work, 0)?;
+                a.copy_advice(|| "a", &mut region, self.cfg.work, 1)?;
+                b.copy_advice(|| "b", &mut region, self.cfg.work, 2)?;
+                Ok(())
             }
-        )?;
+        )
+    }
 
-        // ONE-HOT(2): b_ie + b_cmp == 1  (pont egy ág legyen aktív)
-        let b_known = layouter.assign_region(
-            || "known op",
+    /// Az osztó gadget bemenete: (acc, dvs) az egész osztáshoz, a szorzás és a `/` kerekítéséhez
+    fn div_operands(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        x: &AssignedCell<Fp,Fp>,
+        y: &AssignedCell<Fp,Fp>,
+        selectors: [&AssignedCell<Fp,Fp>; 3], // int, mul, fdiv
+    ) -> Result<DivOperands, Error> {
+        let col = self.cfg.work;
+        layouter.assign_region(
+            || "divider operands",
             |mut region| {
-                let col = self.cfg.work;
-                let prod_not = region.assign_advice(
-                    || "(1-b_ie)*(1-b_cmp)",
-                    col, 50,
-                    || b_ie.value().zip(b_cmp.value()).map(|(ie,cmp)| {
-                        let one = Fp::ONE;
-                        (one - *ie) * (one - *cmp)
-                    })
-                )?;
-                region.assign_advice(
-                    || "b_known",
-                    col, 51,
-                    || prod_not.value().map(|v| Fp::ONE - v)
-                )
+                self.cfg.q_div_in.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, col, 0)?;
+                y.copy_advice(|| "y", &mut region, col, 1)?;
+                for (i, s) in selectors.iter().enumerate() {
+                    s.copy_advice(|| format!("s[{i}]"), &mut region, col, 2 + i)?;
+                }
+
+                let scale = Fp::from(SCALE as u64);
+                let half = if ROUNDING == Rounding::HalfUp { Fp::ONE } else { Fp::ZERO };
+                let [s_int, s_mul, s_fdiv] = selectors.map(|s| s.value().copied());
+                let sel = s_int.zip(s_mul).zip(s_fdiv);
+                let acc = x.value().zip(y.value()).zip(sel).map(|((xv, yv), ((si, sm), sf))| {
+                    si * *xv + sm * (xv.double() * *yv + half * scale) + sf * (scale.double() * *xv + half * *yv)
+                });
+                let dvs = y.value().zip(sel).map(|(yv, ((si, sm), sf))| {
+                    si * *yv + sm * scale.double() + sf * yv.double()
+                });
+                let acc = region.assign_advice(|| "acc", col, 5, || acc)?;
+                let dvs = region.assign_advice(|| "dvs", col, 6, || dvs)?;
+                Ok((acc, dvs))
             }
-        )?;
+        )
+    }
 
-        // ✅ csak akkor kényszerítjük az egy-szor-aktiv-at, ha ismert ág
+    /// Egy csúcs: res = x + Σ s_i * (res_i - x), ahol a szelektorok one-hot-ok
+    fn arith_node(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        x: &AssignedCell<Fp,Fp>,
+        y: &AssignedCell<Fp,Fp>,
+        selectors: [&AssignedCell<Fp,Fp>; 8], // add, sub, mul, /, div, //, mod, rem
+        q: &AssignedCell<Fp,Fp>,
+        r: &AssignedCell<Fp,Fp>,
+    ) -> Result<AssignedCell<Fp,Fp>, Error> {
+        let col = self.cfg.work;
         layouter.assign_region(
-            || "gate onehot2",
+            || "arith node",
             |mut region| {
-                let col = self.cfg.work;
-                let sum = region.assign_advice(
-                    || "sum",
-                    col, 52,
-                    || b_ie.value().zip(b_cmp.value()).map(|(ie,cmp)| *ie + *cmp)
-                )?;
-                let diff = region.assign_advice(
-                    || "b_known*(sum-1)",
-                    col, 53,
-                    || b_known.value().zip(sum.value()).map(|(k,s)| *k * (*s - Fp::ONE))
-                )?;
-                let z = region.assign_advice(|| "z", col, 54, || Value::known(Fp::ZERO))?;
-                region.constrain_equal(diff.cell(), z.cell())
+                self.cfg.q_arith.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, col, 0)?;
+                y.copy_advice(|| "y", &mut region, col, 1)?;
+                for (i, s) in selectors.iter().enumerate() {
+                    s.copy_advice(|| format!("s[{i}]"), &mut region, col, 2 + i)?;
+                }
+                q.copy_advice(|| "q", &mut region, col, 10)?;
+                r.copy_advice(|| "r", &mut region, col, 11)?;
+
+                let scale = Fp::from(SCALE as u64);
+                let [sa, ss, sm, sf, sd, si, smo, sr] = selectors.map(|s| s.value().copied());
+                let sels = sa.zip(ss).zip(sm).zip(sf).zip(sd).zip(si).zip(smo).zip(sr);
+                let res = x.value().zip(y.value()).zip(q.value()).zip(r.value()).zip(sels)
+                    .map(|((((xv, yv), qv), rv), (((((((sa, ss), sm), sf), sd), si), smo), sr))| {
+                        *xv + sa * *yv - ss * *yv
+                            + (sm + sf) * (*qv - *xv)
+                            + (sd + si) * (scale * *qv - *xv)
+                            + (smo + sr) * (*rv - *xv)
+                    });
+                region.assign_advice(|| "res", col, 12, || res)
             }
-        )?;
+        )
+    }
 
-        // induló acc
-        let mut acc = rhs0;
+    /// Fő API: ellenőrzi a builtin goal kifejezésfáját és visszaad egy boolean `ok` cellát.
+    ///
+    /// Bemenet (`term_encoding::encode_builtin` elrendezése, `max_pred_list` hosszan, üres taggal kitöltve):
+    ///  - p=0: a reláció, `names[0]` ∈ is, =:=, =, ==, =\=, \=, \==, >, <, >=, =<;
+    ///    bal oldal = args[0][0][0], jobb oldal = args[0][1][0]
+    ///  - p>=1: kifejezésfa-csúcsok, `names[p]` ∈ +, -, *, /, div, //, mod, rem;
+    ///    x = args[p][0][0], y = args[p][1][0], eredmény = args[p][2][0]
+    ///
+    /// A csúcsok közti élek (egy operandus egy korábbi csúcs eredménye) a szabály egyenlőségei
+    /// a szülő bizonyításában, itt minden csúcsot külön ellenőrzünk.
+    ///
+    /// Viselkedés:
+    ///  - Minden névszelektor is-zero gadgetből jön, így boolean és legfeljebb egy aktív
+    ///  - p>=1: eredmény = x op y; builtin goal esetén minden tag ismert op vagy üres (név = 0)
+    ///  - A számok fixpontosak (`fixed::Fixed`: az érték SCALE-szerese, előjelesen), a * és a /
+    ///    eredménye `fixed::ROUNDING` szerint kerekít, a div, //, mod, rem egészekre a Prolog szerint számol
+    ///  - Végül ok = b_ie*[lhs == rhs] + b_ne*[lhs != rhs] + b_cmp*cmp_ok
+    ///
+    /// Az `ok` nincs 1-re kényszerítve: a hívó (`FactChip` döntési kapuja) köti a goal többi
+    /// ágához, egy builtin goal csak ok = 1 mellett fogadható el.
+    pub fn eval_builtin(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        names: &[AssignedCell<Fp,Fp>],
+        args: &[Vec<Vec<AssignedCell<Fp,Fp>>>],
+    ) -> Result<AssignedCell<Fp,Fp>, Error> {
+        assert!(!names.is_empty(), "names must be non-empty");
 
-        // p>=1: unáris műveletek
+        // 0. term: a reláció
+        let name0 = &names[0];
+        let lhs   = args[0][0][0].clone();
+        let rhs   = args[0][1][0].clone();
+
+        let zero = self.constant_cell(layouter.namespace(|| "zero"), Fp::ZERO)?;
+
+        // név-egyezés booleánok
+        let b_is   = self.eq_const_bool(layouter.namespace(|| "name==is"),  name0, Self::fp_op("is"))?;
+        let b_aeq  = self.eq_const_bool(layouter.namespace(|| "name==:="), name0, Self::fp_op("=:="))?;
+        let b_unif = self.eq_const_bool(layouter.namespace(|| "name==="),   name0, Self::fp_op("="))?;
+        let b_same = self.eq_const_bool(layouter.namespace(|| "name===="),  name0, Self::fp_op("=="))?;
+        let b_ane  = self.eq_const_bool(layouter.namespace(|| "name==\\="), name0, Self::fp_op("=\\="))?;
+        let b_nunif = self.eq_const_bool(layouter.namespace(|| "name\\="),  name0, Self::fp_op("\\="))?;
+        let b_nsame = self.eq_const_bool(layouter.namespace(|| "name\\=="), name0, Self::fp_op("\\=="))?;
+        let b_gt = self.eq_const_bool(layouter.namespace(|| "name >"),   name0, Self::fp_op(">"))?;
+        let b_lt = self.eq_const_bool(layouter.namespace(|| "name <"),   name0, Self::fp_op("<"))?;
+        let b_ge = self.eq_const_bool(layouter.namespace(|| "name >="),  name0, Self::fp_op(">="))?;
+        let b_le = self.eq_const_bool(layouter.namespace(|| "name =<"),  name0, Self::fp_op("=<"))?;
+
+        // ágak: b_ie = egyenlőség, b_ne = különbözőség, b_cmp = rendezés; a szelektorok
+        // kölcsönösen kizárók, így az összeg maga az OR
+        let b_ie  = self.sum4(layouter.namespace(|| "b_ie"),  [&b_is, &b_aeq, &b_unif, &b_same])?;
+        let b_ne  = self.sum4(layouter.namespace(|| "b_ne"),  [&b_ane, &b_nunif, &b_nsame, &zero])?;
+        let b_cmp = self.sum4(layouter.namespace(|| "b_cmp"), [&b_gt, &b_lt, &b_ge, &b_le])?;
+
+        // ONE-HOT: b_known = b_ie + b_ne + b_cmp ∈ {0,1} (legfeljebb egy ág aktív)
+        let b_known = self.sum4(layouter.namespace(|| "b_known"), [&b_ie, &b_ne, &b_cmp, &zero])?;
+        self.assert_bool(layouter.namespace(|| "b_known boolean"), &b_known)?;
+
+        // p>=1: kifejezésfa-csúcsok
         for (p, name_cell) in names.iter().enumerate().skip(1) {
             let x = args[p][0][0].clone();
+            let y = args[p][1][0].clone();
+            let z = args[p][2][0].clone();
 
             // szelektor booleánok
-            let s_add = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==+")),   name_cell, Self::fp_op("+"),   100 + 40*p)?;
-            let s_sub = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==-")),  name_cell, Self::fp_op("-"),   110 + 40*p)?;
-            let s_mul = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==*")),  name_cell, Self::fp_op("*"),   120 + 40*p)?;
-            let s_div = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==div")),name_cell, Self::fp_op("div"), 130 + 40*p)?;
-
-            // arithmetic selector = OR of add/sub/mul/div
-            let s_arith = layouter.assign_region(
-                || format!("s_arith p{p}"),
-                |mut region| {
-                    let col = self.cfg.work;
-                    region.assign_advice(
-                        || "s_add + s_sub + s_mul + s_div",
-                        col, 140 + 40*p,
-                        || s_add.value()
-                            .zip(s_sub.value()).zip(s_mul.value()).zip(s_div.value())
-                            .map(|(((a,b),c),d)| *a + *b + *c + *d)
-                    )
-                }
-            )?;
-
-            // soft check: s_arith*(1 - s_arith) == 0  (boolean)
-            layouter.assign_region(
-                || format!("s_arith boolean p{p}"),
-                |mut region| {
-                    let col = self.cfg.work;
-                    let be = region.assign_advice(
-                        || "s_arith*(1-s_arith)",
-                        col, 141 + 40*p,
-                        || s_arith.value().map(|v| *v * (Fp::ONE - *v))
-                    )?;
-                    let z = region.assign_advice(|| "z", col, 142 + 40*p, || Value::known(Fp::ZERO))?;
-                    region.constrain_equal(be.cell(), z.cell())
-                }
-            )?;
-
-
-            // res-ek
-            let res_add = layouter.assign_region(
-                || format!("res_add p{p}"),
-                |mut region| {
-                    region.assign_advice(
-                        || "acc+x",
-                        col, 200 + 10*p,
-                        || acc.value().zip(x.value()).map(|(a,b)| *a + *b)
-                    )
-                }
-            )?;
-            let res_sub = layouter.assign_region(
-                || format!("res_sub p{p}"),
-                |mut region| {
-                    region.assign_advice(
-                        || "acc-x",
-                        col, 201 + 10*p,
-                        || acc.value().zip(x.value()).map(|(a,b)| *a - *b)
-                    )
-                }
+            let s_add  = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==+")),   name_cell, Self::fp_op("+"))?;
+            let s_sub  = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==-")),   name_cell, Self::fp_op("-"))?;
+            let s_mul  = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==*")),   name_cell, Self::fp_op("*"))?;
+            let s_fdiv = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==/")),   name_cell, Self::fp_op("/"))?;
+            let s_div  = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==div")), name_cell, Self::fp_op("div"))?;
+            let s_idiv = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==//")),  name_cell, Self::fp_op("//"))?;
+            let s_mod  = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==mod")), name_cell, Self::fp_op("mod"))?;
+            let s_rem  = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==rem")), name_cell, Self::fp_op("rem"))?;
+            let s_pad  = self.eq_const_bool(layouter.namespace(|| format!("p{p} name==0")),   name_cell, Fp::ZERO)?;
+
+            // egész osztások: s_int = s_div + s_idiv + s_mod + s_rem
+            let s_int = self.sum4(layouter.namespace(|| format!("s_int p{p}")), [&s_div, &s_idiv, &s_mod, &s_rem])?;
+
+            // az osztó gadget engedélyezése: egész osztás, vagy * és / kerekítése
+            let s_divrem = self.sum4(layouter.namespace(|| format!("s_divrem p{p}")), [&s_int, &s_mul, &s_fdiv, &zero])?;
+
+            // arithmetic selector = OR of add/sub/divrem, boolean
+            let s_arith = self.sum4(layouter.namespace(|| format!("s_arith p{p}")), [&s_add, &s_sub, &s_divrem, &zero])?;
+            self.assert_bool(layouter.namespace(|| format!("s_arith boolean p{p}")), &s_arith)?;
+
+            // builtin goal-ban minden további tag ismert aritmetikai op vagy üres
+            let s_node = self.sum4(layouter.namespace(|| format!("s_node p{p}")), [&s_arith, &s_pad, &zero, &zero])?;
+            self.assert_implies(layouter.namespace(|| format!("builtin → node p{p}")), &b_known, &s_node)?;
+
+            // csonkoló osztás: // és rem (a maradék x előjelét kapja), div és mod lefelé kerekít;
+            // a * és / a ROUNDING szerint
+            let s_trunc = if ROUNDING == Rounding::TowardZero {
+                self.sum4(layouter.namespace(|| format!("s_trunc p{p}")), [&s_idiv, &s_rem, &s_mul, &s_fdiv])?
+            } else {
+                self.sum4(layouter.namespace(|| format!("s_trunc p{p}")), [&s_idiv, &s_rem, &zero, &zero])?
+            };
+
+            // acc = q*dvs + r, |r| < |dvs|, dvs != 0, előjeles (kényszerítve, ha s_divrem = 1)
+            let (acc, dvs) = self.div_operands(
+                layouter.namespace(|| format!("divider operands p{p}")),
+                &x,
+                &y,
+                [&s_int, &s_mul, &s_fdiv],
             )?;
-            let res_mul = layouter.assign_region(
-                || format!("res_mul p{p}"),
-                |mut region| {
-                    region.assign_advice(
-                        || "acc*x",
-                        col, 202 + 10*p,
-                        || acc.value().zip(x.value()).map(|(a,b)| *a * *b)
-                    )
-                }
+            let div_rem_chip = DivRemChip::construct(self.cfg.div_rem_cfg.clone());
+            let (res_quot, res_rem) = div_rem_chip.div_rem(
+                layouter.namespace(|| format!("div/rem p{p}")),
+                &acc,
+                &dvs,
+                &s_divrem,
+                &s_trunc,
             )?;
-            // div: tanú inv(x), és kapu s_div*(x*inv - 1) == 0
-            // div: egész osztás q = floor(acc / x), r = acc - q*x, 0 <= r < x
-            // div: integer division acc = q*x + r, 0 <= r < x
-            let (res_div, _q, _r) = layouter.assign_region(
-            || format!("res_div integer p{p}"),
-            |mut region| {
-                // q = floor(acc/x)
-                let q_val = acc.value().zip(x.value()).map(|(a, xv)| {
-                    if *xv == Fp::ZERO {
-                        // osztás 0-val: adunk q=0-t; (ha akarod, külön tiltó kaput is tehetsz s_div & (x==0) == 0)
-                        Fp::ZERO
-                    } else {
-                        let a128 = fp_to_u128(*a);
-                        let x128 = fp_to_u128(*xv);
-                        Fp::from((a128 / x128) as u64)
-                    }
-                });
-                let q = region.assign_advice(|| "q", col, 203 + 10*p, || q_val)?;
-
-                // r = acc - q*x
-                let r = region.assign_advice(
-                    || "r",
-                    col, 204 + 10*p,
-                    || acc.value().zip(q.value()).zip(x.value()).map(|((a,qv), xv)| {
-                        *a - (*qv * *xv)
-                    })
-                )?;
-
-                // acc = q*x + r  ✔
-                // ✅ csak ha s_div=1, akkor kötelező: acc = q*x + r
-                let gated = region.assign_advice(
-                    || "s_div * (acc - q*x - r)",
-                    col, 205 + 10*p,
-                    || s_div.value()
-                        .zip(acc.value()).zip(q.value()).zip(x.value()).zip(r.value())
-                        .map(|((((sd,a),qv),xv),rv)| *sd * (*a - (*qv * *xv) - *rv))
-                )?;
-                let zero = region.assign_advice(|| "0", col, 206 + 10*p, || Value::known(Fp::ZERO))?;
-                region.constrain_equal(gated.cell(), zero.cell())?;
-
-
-                // r < x  → opcionális “soft” check (range tábla nélkül ez csak rekonstrukció)
-                let _y = region.assign_advice(
-                    || "y = x-r-1",
-                    col, 207 + 10*p,
-                    || x.value().zip(r.value()).map(|(xv,rv)| *xv - *rv - Fp::ONE)
-                )?;
-
-                // (opcionális) "range_check_u32_in_region" hívások maradhatnak, de valódi 16 bites bound-ot
-                // nem kényszerítenek lookup tábla nélkül. Ha kell erős bound, külön range-chip kell.
-
-                // integer div eredménye → accumulator kandidát
-                let rd = region.assign_advice(
-                    || "acc=q",
-                    col, 270 + 20*p,
-                    || q.value().copied()
-                )?;
 
-                Ok((rd, q, r))
-            }
+            // mux: res = x + Σ s_i * (res_i - x)
+            let res = self.arith_node(
+                layouter.namespace(|| format!("res p{p}")),
+                &x,
+                &y,
+                [&s_add, &s_sub, &s_mul, &s_fdiv, &s_div, &s_idiv, &s_mod, &s_rem],
+                &res_quot,
+                &res_rem,
             )?;
 
-            // mux: acc_next = Σ s_i * res_i
-            acc = layouter.assign_region(
-            || format!("acc_next p{p}"),
-            |mut region| {
-                region.assign_advice(
-                    || "acc + Σ s_i*(res_i-acc)",
-                    col, 207 + 10*p,
-                    || s_add.value().zip(res_add.value())
-                        .zip(s_sub.value()).zip(res_sub.value())
-                        .zip(s_mul.value()).zip(res_mul.value())
-                        .zip(s_div.value()).zip(res_div.value())
-                        .zip(acc.value())
-                        .map(|((((((((sa,ra), ss), rs), sm), rm), sd), rd), accv)| {
-                            let term = *sa * (*ra - *accv)
-                                    + *ss * (*rs - *accv)
-                                    + *sm * (*rm - *accv)
-                                    + *sd * (*rd - *accv);
-                            *accv + term
-                        })
-                )
-            }
-        )?;
+            // a tanúsított eredmény (args[p][2][0]) a kiszámolt
+            self.assert_eq_if(layouter.namespace(|| format!("result p{p}")), &s_arith, &z, &res)?;
         }
 
-        // végső összehasonlítás + AND az is/=-szel
+        // végső összehasonlítás
         //---------------------------------------------
-        // EDDIGI eq művelet eredménye acc-ban van
-
-        // delta = lhs - rhs0
-        let delta = layouter.assign_region(
-            || "delta",
+        // sign of delta = lhs - rhs via range-checked comparisons:
+        // g_lr = [lhs > rhs], g_rl = [rhs > lhs], both enforced only when b_cmp = 1
+        let cmp_chip = CompareChip::construct(self.cfg.cmp_cfg.clone());
+        let g_lr = cmp_chip.greater_than(layouter.namespace(|| "lhs > rhs"), &lhs, &rhs, &b_cmp)?;
+        let g_rl = cmp_chip.greater_than(layouter.namespace(|| "rhs > lhs"), &rhs, &lhs, &b_cmp)?;
+
+        // cmp_ok = b_gt*g_lr + b_lt*g_rl + b_ge*(1-g_rl) + b_le*(1-g_lr)
+        let cmp_ok = layouter.assign_region(
+            || "cmp_ok",
             |mut region| {
                 let col = self.cfg.work;
+                self.cfg.q_cmp_ok.enable(&mut region, 0)?;
+                for (i, (name, c)) in [("b_gt", &b_gt), ("b_lt", &b_lt), ("b_ge", &b_ge), ("b_le", &b_le), ("g_lr", &g_lr), ("g_rl", &g_rl)]
+                    .into_iter()
+                    .enumerate()
+                {
+                    c.copy_advice(|| name, &mut region, col, i)?;
+                }
                 region.assign_advice(
-                    || "lhs-rhs",
-                    col, 700,
-                    || lhs_cmp.value().zip(rhs_cmp.value()).map(|(l,r)| *l - *r)
+                    || "cmp_ok",
+                    col, 6,
+                    || b_gt.value().zip(b_lt.value()).zip(b_ge.value()).zip(b_le.value())
+                        .zip(g_lr.value()).zip(g_rl.value())
+                        .map(|(((((gt, lt), ge), le), glr), grl)| {
+                            *gt * *glr + *lt * *grl + *ge * (Fp::ONE - *grl) + *le * (Fp::ONE - *glr)
+                        })
                 )
             }
         )?;
 
-        // (delta > 0) gated by b_cmp
-        let is_positive = layouter.assign_region(
-            || "is_positive_gated",
+        // eq_ok: [lhs == rhs]
+        let eq_ok = self.is_eq(layouter.namespace(|| "[lhs==rhs]"), &lhs, EqRhs::Cell(&rhs))?;
+
+        // final ok = b_ie*eq_ok + b_ne*(1-eq_ok) + b_cmp*cmp_ok (boolean, mivel b_ie + b_ne + b_cmp <= 1)
+        let ok = layouter.assign_region(
+            || "final ok (mux eq / ne / cmp)",
             |mut region| {
                 let col = self.cfg.work;
+                self.cfg.q_ok.enable(&mut region, 0)?;
+                b_ie.copy_advice(|| "b_ie", &mut region, col, 0)?;
+                b_ne.copy_advice(|| "b_ne", &mut region, col, 1)?;
+                eq_ok.copy_advice(|| "eq_ok", &mut region, col, 2)?;
+                b_cmp.copy_advice(|| "b_cmp", &mut region, col, 3)?;
+                cmp_ok.copy_advice(|| "cmp_ok", &mut region, col, 4)?;
                 region.assign_advice(
-                    || "pos(d)*b_cmp",
-                    col, 701,
-                    || delta.value().zip(b_cmp.value())
-                        .map(|(d,bc)| if *bc == Fp::ZERO { Fp::ZERO }
-                                    else if *d > Fp::ZERO { Fp::ONE } else { Fp::ZERO })
+                    || "ok",
+                    col, 5,
+                    || b_ie.value().zip(b_ne.value()).zip(eq_ok.value()).zip(b_cmp.value()).zip(cmp_ok.value())
+                        .map(|((((ie, ne), eqv), cmp), cmpv)| *ie * *eqv + *ne * (Fp::ONE - *eqv) + *cmp * *cmpv)
                 )
             }
         )?;
 
+        Ok(ok)
+    }
+}
 
-        // Soft: delta < 0
-        let is_negative = layouter.assign_region(
-            || "is_negative",
-            |mut region| {
-                let col = self.cfg.work;
-                region.assign_advice(
-                    || "neg(d)",
-                    col, 702,
-                    || delta.value().map(|d| if *d < Fp::ZERO { Fp::ONE } else { Fp::ZERO })
-                )
-            }
-        )?;
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
 
-        // d == 0 → eq result
-        let is_zero = layouter.assign_region(
-            || "is_zero(d)",
-            |mut region| {
-                let col = self.cfg.work;
-                region.assign_advice(
-                    || "zero(d)",
-                    col, 703,
-                    || delta.value().map(|d| if *d == Fp::ZERO { Fp::ONE } else { Fp::ZERO })
-                )
-            }
-        )?;
+    use super::*;
+    use crate::{circuit_shape::CircuitShape, data::TermFp, term::Term, utils_2::term_encoding::encode_builtin};
 
-        // Comparison op logic
-        let ok_gt = layouter.assign_region(
-            || "ok_gt",
-            |mut region| {
-                let col = self.cfg.work;
-                region.assign_advice(
-                    || "",
-                    col, 704,
-                    || is_positive.value().zip(b_gt.value()).map(|(p,g)| *p * *g)
-                )
-            }
-        )?;
-        let ok_lt = layouter.assign_region(
-            || "ok_lt",
-            |mut region| {
-                let col = self.cfg.work;
-                region.assign_advice(
-                    || "",
-                    col, 705,
-                    || is_negative.value().zip(b_lt.value()).map(|(n,l)| *n * *l)
-                )
-            }
-        )?;
-        let ok_ge = layouter.assign_region(
-            || "ok_ge",
-            |mut region| {
-                let col = self.cfg.work;
-                region.assign_advice(
-                    || "",
-                    col, 706,
-                    || is_positive.value().zip(is_zero.value()).zip(b_ge.value())
-                        .map(|((p,z),ge)| (*p + *z) * *ge)
-                )
-            }
-        )?;
-        let ok_le = layouter.assign_region(
-            || "ok_le",
-            |mut region| {
-                let col = self.cfg.work;
-                region.assign_advice(
-                    || "",
-                    col, 707,
-                    || is_negative.value().zip(is_zero.value()).zip(b_le.value())
-                        .map(|((n,z),le)| (*n + *z) * *le)
-                )
-            }
-        )?;
+    const K: u32 = 14;
+    const SHAPE: CircuitShape = CircuitShape::mnb();
 
-// OR of all comparison results
-let cmp_ok = layouter.assign_region(
-    || "cmp_ok",
-    |mut region| {
-        let col = self.cfg.work;
-        region.assign_advice(
-            || "",
-            col, 708,
-            || ok_gt.value()
-                 .zip(ok_lt.value())
-                 .zip(ok_ge.value())
-                 .zip(ok_le.value())
-                 .map(|(((a,b),c),d)| {
-                     let one = Fp::ONE;
-                     one - ((one-*a)*(one-*b)*(one-*c)*(one-*d))
-                 })
-        )
+    // eval_builtin on the encoded goal, its ok must be `expected`
+    #[derive(Clone)]
+    struct BuiltinCircuit {
+        terms: Vec<TermFp>,
+        expected: bool,
     }
-)?;
 
-// eq_ok: [lhs == acc]
-let eq_ok = layouter.assign_region(
-    || "[lhs==acc]",
-    |mut region| {
-        let col = self.cfg.work;
-        region.assign_advice(
-            || "eq_ok",
-            col, 709,
-            || lhs.value().zip(acc.value()).map(|(l,a)| if *l == *a { Fp::ONE } else { Fp::ZERO })
-        )
+    impl Circuit<Fp> for BuiltinCircuit {
+        type Config = (BuiltinExprConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (BuiltinExprChip::configure(meta), input)
+        }
+
+        fn synthesize(&self, (cfg, input): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = BuiltinExprChip::construct(cfg);
+            chip.load_range_table(layouter.namespace(|| "table"))?;
+            let (names, args) = layouter.assign_region(
+                || "goal",
+                |mut region| {
+                    let mut row = 0;
+                    let mut cell = |v: Fp| {
+                        row += 1;
+                        region.assign_advice(|| "input", input, row - 1, || Value::known(v))
+                    };
+                    let mut names = Vec::new();
+                    let mut args = Vec::new();
+                    for t in &self.terms {
+                        names.push(cell(t.name)?);
+                        args.push(
+                            t.args.iter()
+                                .map(|a| a.iter().map(|v| cell(*v)).collect::<Result<Vec<_>, _>>())
+                                .collect::<Result<Vec<_>, _>>()?,
+                        );
+                    }
+                    Ok((names, args))
+                },
+            )?;
+            let ok = chip.eval_builtin(layouter.namespace(|| "builtin"), &names, &args)?;
+            layouter.assign_region(|| "expected", |mut region| region.constrain_constant(ok.cell(), Fp::from(self.expected)))
+        }
     }
-)?;
 
-// final ok = b_ie*eq_ok + b_cmp*cmp_ok
-let ok = layouter.assign_region(
-    || "final ok (mux eq vs cmp)",
-    |mut region| {
-        let col = self.cfg.work;
-        region.assign_advice(
-            || "ok",
-            col, 710,
-            || b_ie.value().zip(eq_ok.value()).zip(b_cmp.value()).zip(cmp_ok.value())
-                .map(|(((ie,eqv),cmp),cmpv)| *ie * *eqv + *cmp * *cmpv)
-        )
+    fn terms(goal: &str) -> Vec<TermFp> {
+        let mut terms = encode_builtin(&Term::read(goal).unwrap(), &SHAPE);
+        terms.resize(SHAPE.max_pred_list, TermFp::empty(&SHAPE));
+        terms
     }
-)?;
 
-// booleanitás: ok*(1-ok) == 0
-layouter.assign_region(
-    || "ok boolean",
-    |mut region| {
-        let col = self.cfg.work;
-        let be = region.assign_advice(
-            || "ok*(1-ok)",
-            col, 711,
-            || ok.value().map(|v| *v * (Fp::ONE - *v))
-        )?;
-        let z = region.assign_advice(|| "z", col, 712, || Value::known(Fp::ZERO))?;
-        region.constrain_equal(be.cell(), z.cell())
+    fn verify(terms: Vec<TermFp>, expected: bool) -> bool {
+        MockProver::run(K, &BuiltinCircuit { terms, expected }, vec![]).unwrap().verify().is_ok()
     }
-)?;
 
+    // ok of the goal, None if the circuit can not be satisfied at all
+    fn ok(goal: &str) -> Option<bool> {
+        [true, false].into_iter().find(|expected| verify(terms(goal), *expected))
+    }
 
-        // ha kell: ok == 1 kényszer
-        if must_be_true {
-            layouter.assign_region(
-                || "ok==1",
-                |mut region| {
-                    let diff = region.assign_advice(
-                        || "ok-1",
-                        col, 904,
-                        || ok.value().map(|v| *v - Fp::ONE)
-                    )?;
-                    let z = region.assign_advice(|| "z", col, 905, || Value::known(Fp::ZERO))?;
-                    region.constrain_equal(diff.cell(), z.cell())
-                }
-            )?;
-        }
+    #[test]
+    fn relations_select_their_branch() {
+        assert_eq!(ok("3 =:= 1 + 2"), Some(true));
+        assert_eq!(ok("3 =\\= 1 + 2"), Some(false));
+        assert_eq!(ok("4 =\\= 1 + 2"), Some(true));
+        assert_eq!(ok("2 < 3"), Some(true));
+        assert_eq!(ok("3 =< 2"), Some(false));
+        assert_eq!(ok("3 >= 3"), Some(true));
+    }
 
-        Ok(ok)
+    #[test]
+    fn a_goal_that_is_not_built_in_is_not_ok() {
+        assert_eq!(ok("price(3, 4)"), Some(false));
+    }
+
+    #[test]
+    fn unknown_operator_in_a_built_in_fails() {
+        let mut t = terms("3 is 1 + 2");
+        assert!(verify(t.clone(), true));
+        t[1].name = name_value("foo");
+        assert!(!verify(t.clone(), true));
+        assert!(!verify(t, false));
+    }
+
+    #[test]
+    fn integer_division() {
+        assert_eq!(ok("3 is 7 // 2"), Some(true));
+        assert_eq!(ok("1 is 7 mod 2"), Some(true));
+        assert_eq!(ok("1 is 7 rem 2"), Some(true));
+        assert_eq!(ok("4 is 7 div 2"), Some(false));
+        // divisor 0 can not be proven at all
+        assert_eq!(ok("0 is 7 div 0"), None);
+    }
+
+    #[test]
+    fn wrong_node_result_fails() {
+        let mut t = terms("3 is 7 // 2");
+        assert!(verify(t.clone(), true));
+        t[1].args[2][0] = t[1].args[2][0] + Fp::from(SCALE as u64);
+        assert!(!verify(t.clone(), true));
+        assert!(!verify(t, false));
+    }
+
+    #[test]
+    fn fixed_point_decimals() {
+        assert_eq!(ok("149.5 is 747.5 * 0.2"), Some(true));
+        assert_eq!(ok("149.4 is 747.5 * 0.2"), Some(false));
+        assert_eq!(ok("2.0 =:= 2"), Some(true));
+        assert_eq!(ok("0.5 < 0.75"), Some(true));
+        assert_eq!(ok("-0.5 > 0.25"), Some(false));
+        assert_eq!(ok("0.25 is 1 / 4"), Some(true));
     }
 }
-use halo2curves::ff::PrimeField;
-
-// extract lower 64-bit integer safely from field
-fn fp_to_u128(x: Fp) -> u128 {
-    let repr = x.to_repr();
-    let mut b = [0u8; 16];
-    b.copy_from_slice(&repr.as_ref()[0..16]);
-    u128::from_le_bytes(b)
-}
-/*
-/// Soft 32-bit range-check (u32)
-fn range_check_u32_in_region(
-    region: &mut halo2_proofs::circuit::Region<'_, Fp>,
-    col: Column<Advice>,
-    row: usize,
-    v: &AssignedCell<Fp, Fp>,
-) -> Result<(), Error> {
-    let lo = region.assign_advice(
-        || "lo",
-        col, row,
-        || v.value().map(|val| {
-            let vv = fp_to_u128(*val);
-            Fp::from((vv & 0xFFFF) as u64)
-        })
-    )?;
-    let hi = region.assign_advice(
-        || "hi",
-        col, row+1,
-        || v.value().map(|val| {
-            let vv = fp_to_u128(*val);
-            Fp::from(((vv >> 16) & 0xFFFF) as u64)
-        })
-    )?;
-
-    // v == lo + hi * 2^16
-    let recon = region.assign_advice(
-        || "recon",
-        col, row+2,
-        || lo.value().zip(hi.value()).map(|(l,h)| {
-            *l + (*h * Fp::from(1u64 << 16))
-        })
-    )?;
-
-    region.constrain_equal(v.cell(), recon.cell())?;
-
-    Ok(())
-}*/
@@ -0,0 +1,279 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use halo2curves::ff::{Field, PrimeField};
+use num_bigint::BigUint;
+
+use crate::chips::range_check_chip::{RangeCheckChip, RangeCheckConfig};
+
+/// Range-checked `a > b` for signed `num_bits` wide integers, a negative `n` is the field
+/// element `p - |n|`.
+///
+/// Layout (two rows, `q_cmp` on the first, `off = 2^(num_bits-1)`):
+///
+/// | a           | b           | en | gt | diff |
+/// |-------------|-------------|----|----|------|
+/// | a           | b           | en | gt | diff |
+/// | en*(a+off)  | en*(b+off)  |    |    |      |
+///
+/// diff = en * (gt*(a-b-1) + (1-gt)*(b-a)), and diff, en*(a+off), en*(b+off) are range
+/// checked, so with en = 1 both sides are in [-off, off) and the boolean gt is forced to be
+/// [a > b] (the offset keeps the order and cancels in diff). With en = 0 everything is 0.
+#[derive(Clone, Debug)]
+pub struct CompareConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub en: Column<Advice>,
+    pub gt: Column<Advice>,
+    pub diff: Column<Advice>,
+    pub q_cmp: Selector,
+    pub range_cfg: RangeCheckConfig,
+    pub num_bits: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct CompareChip {
+    cfg: CompareConfig,
+}
+
+impl Chip<Fp> for CompareChip {
+    type Config = CompareConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl CompareChip {
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        range_cfg: RangeCheckConfig,
+        num_bits: usize,
+    ) -> CompareConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let en = meta.advice_column();
+        let gt = meta.advice_column();
+        let diff = meta.advice_column();
+        let q_cmp = meta.selector();
+
+        for col in [a, b, en, gt, diff] {
+            meta.enable_equality(col);
+        }
+
+        meta.create_gate("range-checked a > b", |meta| {
+            let q = meta.query_selector(q_cmp);
+            let av = meta.query_advice(a, Rotation::cur());
+            let bv = meta.query_advice(b, Rotation::cur());
+            let env = meta.query_advice(en, Rotation::cur());
+            let g = meta.query_advice(gt, Rotation::cur());
+            let d = meta.query_advice(diff, Rotation::cur());
+            let a_en = meta.query_advice(a, Rotation::next());
+            let b_en = meta.query_advice(b, Rotation::next());
+            let one = Expression::Constant(Fp::ONE);
+            let off = Expression::Constant(signed_offset(num_bits));
+
+            let signed = g.clone() * (av.clone() - bv.clone() - one.clone())
+                + (one.clone() - g.clone()) * (bv.clone() - av.clone());
+
+            vec![
+                q.clone() * g.clone() * (one.clone() - g),
+                q.clone() * env.clone() * (one - env.clone()),
+                q.clone() * (d - env.clone() * signed),
+                q.clone() * (a_en - env.clone() * (av + off.clone())),
+                q * (b_en - env * (bv + off)),
+            ]
+        });
+
+        CompareConfig { a, b, en, gt, diff, q_cmp, range_cfg, num_bits }
+    }
+
+    pub fn construct(cfg: CompareConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// Returns a boolean cell `[a > b]` (signed), enforced whenever `en == 1`.
+    pub fn greater_than(
+        &self,
+        layouter: impl Layouter<Fp>,
+        a: &AssignedCell<Fp, Fp>,
+        b: &AssignedCell<Fp, Fp>,
+        en: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let gt = a.value().zip(b.value()).map(|(x, y)| {
+            if fp_to_i128(x) > fp_to_i128(y) { Fp::ONE } else { Fp::ZERO }
+        });
+        self.assign_greater_than(layouter, a, b, en, gt)
+    }
+
+    // `gt` is only the witness, the gate and the range checks decide if it is right
+    fn assign_greater_than(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        a: &AssignedCell<Fp, Fp>,
+        b: &AssignedCell<Fp, Fp>,
+        en: &AssignedCell<Fp, Fp>,
+        gt_val: Value<Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let cfg = &self.cfg;
+        let off = signed_offset(cfg.num_bits);
+
+        let (gt, diff, a_en, b_en) = layouter.assign_region(
+            || "a > b",
+            |mut region| {
+                cfg.q_cmp.enable(&mut region, 0)?;
+
+                let a_l = a.copy_advice(|| "a", &mut region, cfg.a, 0)?;
+                let b_l = b.copy_advice(|| "b", &mut region, cfg.b, 0)?;
+                let en_l = en.copy_advice(|| "en", &mut region, cfg.en, 0)?;
+
+                let gt = region.assign_advice(|| "gt", cfg.gt, 0, || gt_val)?;
+
+                let diff_val = a_l.value().zip(b_l.value()).zip(en_l.value()).zip(gt_val)
+                    .map(|(((x, y), e), g)| *e * (g * (*x - *y - Fp::ONE) + (Fp::ONE - g) * (*y - *x)));
+                let diff = region.assign_advice(|| "diff", cfg.diff, 0, || diff_val)?;
+
+                let a_en = region.assign_advice(
+                    || "en*(a+off)", cfg.a, 1,
+                    || a_l.value().zip(en_l.value()).map(|(x, e)| (*x + off) * *e),
+                )?;
+                let b_en = region.assign_advice(
+                    || "en*(b+off)", cfg.b, 1,
+                    || b_l.value().zip(en_l.value()).map(|(y, e)| (*y + off) * *e),
+                )?;
+
+                Ok((gt, diff, a_en, b_en))
+            },
+        )?;
+
+        let range = RangeCheckChip::construct(cfg.range_cfg.clone());
+        range.range_check(layouter.namespace(|| "range(en*(a+off))"), &a_en, cfg.num_bits)?;
+        range.range_check(layouter.namespace(|| "range(en*(b+off))"), &b_en, cfg.num_bits)?;
+        range.range_check(layouter.namespace(|| "range(diff)"), &diff, cfg.num_bits)?;
+
+        Ok(gt)
+    }
+}
+
+pub fn fp_to_biguint(x: &Fp) -> BigUint {
+    BigUint::from_bytes_le(x.to_repr().as_ref())
+}
+
+/// `2^(num_bits-1)`, added to a signed value to make it an unsigned `num_bits` wide one.
+pub fn signed_offset(num_bits: usize) -> Fp {
+    Fp::from(2).pow_vartime([num_bits as u64 - 1])
+}
+
+/// Signed reading of a field element: `x` if it is below `2^127`, `-(p - x)` if `p - x` is,
+/// `None` for anything else (not an integer the chips could accept).
+pub fn fp_to_i128(x: &Fp) -> Option<i128> {
+    let small = |v: &Fp| {
+        let repr = v.to_repr();
+        let bytes = repr.as_ref();
+        (bytes[16..].iter().all(|b| *b == 0) && bytes[15] < 0x80)
+            .then(|| i128::from_le_bytes(bytes[..16].try_into().unwrap()))
+    };
+    small(x).or_else(|| small(&-*x).map(|n| -n))
+}
+
+pub fn i128_to_fp(n: i128) -> Fp {
+    let abs = Fp::from_u128(n.unsigned_abs());
+    if n < 0 { -abs } else { abs }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+
+    use super::*;
+    use crate::utils_2::common_helpers::{RANGE_CHECK_BITS, RANGE_CHECK_LIMB_BITS};
+
+    const K: u32 = 10;
+
+    // [a > b] with en, `gt` overrides the witness of the chip, `expected` is the output it must give
+    #[derive(Clone, Default)]
+    struct GtCircuit {
+        a: Fp,
+        b: Fp,
+        en: Fp,
+        gt: Option<Fp>,
+        expected: Fp,
+    }
+
+    impl Circuit<Fp> for GtCircuit {
+        type Config = (CompareConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let range_cfg = RangeCheckChip::configure(meta, RANGE_CHECK_LIMB_BITS);
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (CompareChip::configure(meta, range_cfg, RANGE_CHECK_BITS), input)
+        }
+
+        fn synthesize(&self, (cfg, input): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            RangeCheckChip::construct(cfg.range_cfg.clone()).load_table(layouter.namespace(|| "table"))?;
+            let [a, b, en] = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let mut cell = |i: usize, v: Fp| region.assign_advice(|| "input", input, i, || Value::known(v));
+                    Ok([cell(0, self.a)?, cell(1, self.b)?, cell(2, self.en)?])
+                },
+            )?;
+            let chip = CompareChip::construct(cfg);
+            let gt = match self.gt {
+                None => chip.greater_than(layouter.namespace(|| "a > b"), &a, &b, &en)?,
+                Some(gt) => chip.assign_greater_than(layouter.namespace(|| "a > b"), &a, &b, &en, Value::known(gt))?,
+            };
+            layouter.assign_region(|| "expected", |mut region| region.constrain_constant(gt.cell(), self.expected))
+        }
+    }
+
+    fn gt(a: i128, b: i128) -> GtCircuit {
+        GtCircuit { a: i128_to_fp(a), b: i128_to_fp(b), en: Fp::ONE, ..Default::default() }
+    }
+
+    fn verify(c: &GtCircuit) -> bool {
+        MockProver::run(K, c, vec![]).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn greater_than_in_range() {
+        for (a, b) in [(5, 3), (3, 5), (4, 4), (0, 1), (i64::MAX as i128, 0)] {
+            let expected = Fp::from(a > b);
+            assert!(verify(&GtCircuit { expected, ..gt(a, b) }), "{a} > {b}");
+            assert!(!verify(&GtCircuit { expected: Fp::ONE - expected, ..gt(a, b) }), "{a} > {b} inverted");
+        }
+    }
+
+    #[test]
+    fn signed_greater_than() {
+        let (min, max) = (i64::MIN as i128, i64::MAX as i128);
+        for (a, b) in [(0, -1), (-1, 0), (-5, -7), (-7, -5), (-3, 3), (max, min), (min, max), (min, min)] {
+            assert!(verify(&GtCircuit { expected: Fp::from(a > b), ..gt(a, b) }), "{a} > {b}");
+            assert!(!verify(&GtCircuit { gt: Some(Fp::from(a <= b)), expected: Fp::from(a <= b), ..gt(a, b) }), "{a} > {b} wrong");
+        }
+        // below the signed range
+        assert!(!verify(&gt(min - 1, 0)));
+    }
+
+    #[test]
+    fn wrong_gt_fails() {
+        for (a, b, wrong) in [(5, 3, Fp::ZERO), (3, 5, Fp::ONE), (4, 4, Fp::ONE)] {
+            assert!(!verify(&GtCircuit { gt: Some(wrong), expected: wrong, ..gt(a, b) }), "{a} > {b}");
+        }
+    }
+
+    #[test]
+    fn out_of_range_fails_only_when_enabled() {
+        let big = i64::MAX as i128 + 1;
+        assert!(!verify(&GtCircuit { expected: Fp::ONE, ..gt(big, 0) }));
+        assert!(!verify(&gt(0, big)));
+        let disabled = GtCircuit { en: Fp::ZERO, expected: Fp::ONE, ..gt(big, 0) };
+        assert!(verify(&disabled));
+    }
+}
@@ -0,0 +1,342 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use halo2curves::ff::Field;
+
+use crate::chips::{
+    fact_check::compare_chip::{fp_to_i128, i128_to_fp, signed_offset},
+    range_check_chip::{RangeCheckChip, RangeCheckConfig},
+};
+
+/// `(q, r)` cells of a division.
+pub type QuotRem = (AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>);
+
+/// Signed integer quotient / remainder shared by `div`, `//`, `mod` and `rem`, a negative `n`
+/// is the field element `p - |n|`.
+///
+/// Layout (three rows, `q_div` on the first, `off = 2^(num_bits-1)`, `abs(v) = (1-2s)*v` with
+/// the sign bit `s` of `v`):
+///
+/// | acc           | x               | q          | r      | en | trunc | sa | sx |
+/// |---------------|-----------------|------------|--------|----|-------|----|----|
+/// | acc           | x               | q          | r      | en | trunc | sa | sx |
+/// | en*abs(acc)   | en*(abs(x)-1)   | en*(q+off) | en*r'  |    |       |    |    |
+/// |               | en*(abs(x)-r'-1)|            |        |    |       |    |    |
+///
+/// sa, sx are the sign bits of acc and x. The remainder takes the sign of
+/// x (`div`, `mod`: floor) or, with trunc = 1, of acc (`//`, `rem`: towards zero); `t` is
+/// that sign bit and `r' = (1-2t)*r`.
+///
+/// With en = 1: acc = q*x + r and all the second / third row cells are range checked (abs(acc)
+/// on `2*num_bits`, so it can be a product), so the sign bits are the real ones, x != 0, q is a
+/// `num_bits` wide signed integer (no wrap around in q*x) and 0 <= r' < abs(x), which makes q
+/// and r the unique Prolog results.
+#[derive(Clone, Debug)]
+pub struct DivRemConfig {
+    pub acc: Column<Advice>,
+    pub x: Column<Advice>,
+    pub q: Column<Advice>,
+    pub r: Column<Advice>,
+    pub en: Column<Advice>,
+    pub trunc: Column<Advice>,
+    pub sa: Column<Advice>,
+    pub sx: Column<Advice>,
+    pub q_div: Selector,
+    pub range_cfg: RangeCheckConfig,
+    pub num_bits: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct DivRemChip {
+    cfg: DivRemConfig,
+}
+
+impl Chip<Fp> for DivRemChip {
+    type Config = DivRemConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl DivRemChip {
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        range_cfg: RangeCheckConfig,
+        num_bits: usize,
+    ) -> DivRemConfig {
+        let acc = meta.advice_column();
+        let x = meta.advice_column();
+        let q = meta.advice_column();
+        let r = meta.advice_column();
+        let en = meta.advice_column();
+        let trunc = meta.advice_column();
+        let sa = meta.advice_column();
+        let sx = meta.advice_column();
+        let q_div = meta.selector();
+
+        for col in [acc, x, q, r, en, trunc, sa, sx] {
+            meta.enable_equality(col);
+        }
+
+        meta.create_gate("signed integer div/rem", |meta| {
+            let s = meta.query_selector(q_div);
+            let accv = meta.query_advice(acc, Rotation::cur());
+            let xv = meta.query_advice(x, Rotation::cur());
+            let qv = meta.query_advice(q, Rotation::cur());
+            let rv = meta.query_advice(r, Rotation::cur());
+            let env = meta.query_advice(en, Rotation::cur());
+            let tr = meta.query_advice(trunc, Rotation::cur());
+            let sav = meta.query_advice(sa, Rotation::cur());
+            let sxv = meta.query_advice(sx, Rotation::cur());
+            let acc_abs_en = meta.query_advice(acc, Rotation::next());
+            let x_abs_en = meta.query_advice(x, Rotation::next());
+            let q_en = meta.query_advice(q, Rotation::next());
+            let r_abs_en = meta.query_advice(r, Rotation::next());
+            let gap_en = meta.query_advice(x, Rotation(2));
+            let one = Expression::Constant(Fp::ONE);
+            let two = Expression::Constant(Fp::from(2));
+            let off = Expression::Constant(signed_offset(num_bits));
+
+            let bool_of = |v: Expression<Fp>| v.clone() * (one.clone() - v);
+            let abs = |sign: Expression<Fp>, v: Expression<Fp>| (one.clone() - two.clone() * sign) * v;
+            // sign bit of the remainder
+            let t = sxv.clone() + tr.clone() * (sav.clone() - sxv.clone());
+            let x_abs = abs(sxv.clone(), xv.clone());
+            let r_abs = abs(t, rv.clone());
+
+            vec![
+                s.clone() * bool_of(env.clone()),
+                s.clone() * bool_of(tr),
+                s.clone() * bool_of(sav.clone()),
+                s.clone() * bool_of(sxv),
+                // acc = q*x + r
+                s.clone() * env.clone() * (accv.clone() - qv.clone() * xv - rv),
+                // range checked copies
+                s.clone() * (acc_abs_en - env.clone() * abs(sav, accv)),
+                s.clone() * (x_abs_en - env.clone() * (x_abs.clone() - one.clone())),
+                s.clone() * (q_en - env.clone() * (qv + off)),
+                s.clone() * (r_abs_en - env.clone() * r_abs.clone()),
+                s * (gap_en - env * (x_abs - r_abs - one)),
+            ]
+        });
+
+        DivRemConfig { acc, x, q, r, en, trunc, sa, sx, q_div, range_cfg, num_bits }
+    }
+
+    pub fn construct(cfg: DivRemConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// Returns `(q, r)` with `acc = q*x + r`, enforced whenever `en == 1`. `r` has the sign of
+    /// `x` (floor: `div`, `mod`), with `trunc == 1` the sign of `acc` (towards zero: `//`, `rem`).
+    pub fn div_rem(
+        &self,
+        layouter: impl Layouter<Fp>,
+        acc: &AssignedCell<Fp, Fp>,
+        x: &AssignedCell<Fp, Fp>,
+        en: &AssignedCell<Fp, Fp>,
+        trunc: &AssignedCell<Fp, Fp>,
+    ) -> Result<QuotRem, Error> {
+        // a divisor that is 0 or not an integer fails the range checks anyway when en = 1
+        let qr = acc.value().zip(x.value()).zip(trunc.value()).map(|((a, xv), t)| {
+            match (fp_to_i128(a), fp_to_i128(xv)) {
+                (Some(a), Some(xv)) if xv != 0 => {
+                    let (mut q, mut r) = (a / xv, a % xv);
+                    if *t == Fp::ZERO && r != 0 && (r < 0) != (xv < 0) {
+                        q -= 1;
+                        r += xv;
+                    }
+                    (i128_to_fp(q), i128_to_fp(r))
+                }
+                _ => (Fp::ZERO, *a),
+            }
+        });
+        self.assign_div_rem(layouter, acc, x, en, trunc, qr)
+    }
+
+    // `qr` is only the witness, the gate and the range checks decide if it is right
+    fn assign_div_rem(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        acc: &AssignedCell<Fp, Fp>,
+        x: &AssignedCell<Fp, Fp>,
+        en: &AssignedCell<Fp, Fp>,
+        trunc: &AssignedCell<Fp, Fp>,
+        qr: Value<(Fp, Fp)>,
+    ) -> Result<QuotRem, Error> {
+        let cfg = &self.cfg;
+        let off = signed_offset(cfg.num_bits);
+
+        let (q, r, checked) = layouter.assign_region(
+            || "div/rem",
+            |mut region| {
+                cfg.q_div.enable(&mut region, 0)?;
+
+                let acc_l = acc.copy_advice(|| "acc", &mut region, cfg.acc, 0)?;
+                let x_l = x.copy_advice(|| "x", &mut region, cfg.x, 0)?;
+                let en_l = en.copy_advice(|| "en", &mut region, cfg.en, 0)?;
+                let trunc_l = trunc.copy_advice(|| "trunc", &mut region, cfg.trunc, 0)?;
+
+                let sign = |v: &Fp| Fp::from(fp_to_i128(v).is_some_and(|n| n < 0));
+                let sa = region.assign_advice(|| "sa", cfg.sa, 0, || acc_l.value().map(sign))?;
+                let sx = region.assign_advice(|| "sx", cfg.sx, 0, || x_l.value().map(sign))?;
+
+                let q = region.assign_advice(|| "q", cfg.q, 0, || qr.map(|(qv, _)| qv))?;
+                let r = region.assign_advice(|| "r", cfg.r, 0, || qr.map(|(_, rv)| rv))?;
+
+                let abs = |s: Fp, v: Fp| (Fp::ONE - s.double()) * v;
+                let e = en_l.value().copied();
+                let x_abs = sx.value().zip(x_l.value()).map(|(s, v)| abs(*s, *v));
+                let t = sa.value().zip(sx.value()).zip(trunc_l.value())
+                    .map(|((a, xs), tr)| *xs + *tr * (*a - *xs));
+                let r_abs = t.zip(r.value()).map(|(t, v)| abs(t, *v));
+
+                let acc_abs_en = region.assign_advice(
+                    || "en*|acc|", cfg.acc, 1,
+                    || sa.value().zip(acc_l.value()).zip(e).map(|((s, v), e)| e * abs(*s, *v)),
+                )?;
+                let x_abs_en = region.assign_advice(
+                    || "en*(|x|-1)", cfg.x, 1,
+                    || x_abs.zip(e).map(|(v, e)| e * (v - Fp::ONE)),
+                )?;
+                let q_en = region.assign_advice(
+                    || "en*(q+off)", cfg.q, 1,
+                    || q.value().zip(e).map(|(v, e)| e * (*v + off)),
+                )?;
+                let r_abs_en = region.assign_advice(
+                    || "en*r'", cfg.r, 1,
+                    || r_abs.zip(e).map(|(v, e)| e * v),
+                )?;
+                let gap_en = region.assign_advice(
+                    || "en*(|x|-r'-1)", cfg.x, 2,
+                    || x_abs.zip(r_abs).zip(e).map(|((xa, ra), e)| e * (xa - ra - Fp::ONE)),
+                )?;
+
+                Ok((q, r, [acc_abs_en, x_abs_en, q_en, r_abs_en, gap_en]))
+            },
+        )?;
+
+        let range = RangeCheckChip::construct(cfg.range_cfg.clone());
+        let widths = [2 * cfg.num_bits, cfg.num_bits, cfg.num_bits, cfg.num_bits, cfg.num_bits];
+        for ((name, cell), bits) in ["|acc|", "|x|-1", "q+off", "r'", "|x|-r'-1"].iter().zip(&checked).zip(widths) {
+            range.range_check(layouter.namespace(|| format!("range({name})")), cell, bits)?;
+        }
+
+        Ok((q, r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+
+    use super::*;
+    use crate::utils_2::common_helpers::{RANGE_CHECK_BITS, RANGE_CHECK_LIMB_BITS};
+
+    const K: u32 = 10;
+
+    // acc = q*x + r with en and trunc, `qr` overrides the witness of the chip, `expected` is the
+    // (q, r) it must give
+    #[derive(Clone, Default)]
+    struct DivRemCircuit {
+        acc: i128,
+        x: i128,
+        en: bool,
+        trunc: bool,
+        qr: Option<(i128, i128)>,
+        expected: (i128, i128),
+    }
+
+    impl Circuit<Fp> for DivRemCircuit {
+        type Config = (DivRemConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let range_cfg = RangeCheckChip::configure(meta, RANGE_CHECK_LIMB_BITS);
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (DivRemChip::configure(meta, range_cfg, RANGE_CHECK_BITS), input)
+        }
+
+        fn synthesize(&self, (cfg, input): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            RangeCheckChip::construct(cfg.range_cfg.clone()).load_table(layouter.namespace(|| "table"))?;
+            let [acc, x, en, trunc] = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let mut cell = |i: usize, v: Fp| region.assign_advice(|| "input", input, i, || Value::known(v));
+                    Ok([
+                        cell(0, i128_to_fp(self.acc))?,
+                        cell(1, i128_to_fp(self.x))?,
+                        cell(2, Fp::from(self.en))?,
+                        cell(3, Fp::from(self.trunc))?,
+                    ])
+                },
+            )?;
+            let chip = DivRemChip::construct(cfg);
+            let (q, r) = match self.qr {
+                None => chip.div_rem(layouter.namespace(|| "div/rem"), &acc, &x, &en, &trunc)?,
+                Some((q, r)) => chip.assign_div_rem(
+                    layouter.namespace(|| "div/rem"), &acc, &x, &en, &trunc,
+                    Value::known((i128_to_fp(q), i128_to_fp(r))),
+                )?,
+            };
+            layouter.assign_region(
+                || "expected",
+                |mut region| {
+                    region.constrain_constant(q.cell(), i128_to_fp(self.expected.0))?;
+                    region.constrain_constant(r.cell(), i128_to_fp(self.expected.1))
+                },
+            )
+        }
+    }
+
+    fn div(acc: i128, x: i128, expected: (i128, i128)) -> DivRemCircuit {
+        DivRemCircuit { acc, x, en: true, expected, ..Default::default() }
+    }
+
+    fn verify(c: &DivRemCircuit) -> bool {
+        MockProver::run(K, c, vec![]).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn quotient_and_remainder() {
+        assert!(verify(&div(7, 2, (3, 1))));
+        assert!(verify(&div(6, 3, (2, 0))));
+        assert!(verify(&div(1, 5, (0, 1))));
+        assert!(!verify(&div(7, 2, (3, 0))));
+    }
+
+    #[test]
+    fn signed_floor_and_truncation() {
+        let trunc = |c: DivRemCircuit| DivRemCircuit { trunc: true, ..c };
+        // div / mod: floor, the remainder has the sign of x
+        assert!(verify(&div(-7, 2, (-4, 1))));
+        assert!(verify(&div(7, -2, (-4, -1))));
+        assert!(verify(&div(-7, -2, (3, -1))));
+        // // / rem: towards zero, the remainder has the sign of acc
+        assert!(verify(&trunc(div(-7, 2, (-3, -1)))));
+        assert!(verify(&trunc(div(7, -2, (-3, 1)))));
+        assert!(verify(&trunc(div(-7, -2, (3, -1)))));
+        // the other rounding's result has a remainder of the wrong sign
+        assert!(!verify(&DivRemCircuit { qr: Some((-3, -1)), ..div(-7, 2, (-3, -1)) }));
+        assert!(!verify(&trunc(DivRemCircuit { qr: Some((-4, 1)), ..div(-7, 2, (-4, 1)) })));
+    }
+
+    #[test]
+    fn remainder_not_below_the_divisor_fails() {
+        // 7 = 2*2 + 3 holds, but 3 >= |2|
+        assert!(!verify(&DivRemCircuit { qr: Some((2, 3)), ..div(7, 2, (2, 3)) }));
+        assert!(!verify(&DivRemCircuit { qr: Some((1, 2)), ..div(4, 2, (1, 2)) }));
+    }
+
+    #[test]
+    fn divisor_zero_fails_only_when_enabled() {
+        assert!(!verify(&div(7, 0, (0, 7))));
+        assert!(verify(&DivRemCircuit { en: false, ..div(7, 0, (0, 7)) }));
+    }
+}
@@ -1,24 +1,57 @@
 use halo2_proofs::{
     circuit::{AssignedCell, Chip, Layouter, Value},
     pasta::Fp,
-    plonk::{Advice, Column, ConstraintSystem, Error, Instance},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
 };
 use halo2curves::ff::Field;
 
-use crate::{chips::fact_check::{built_in_check_chip::{BuiltinExprChip, BuiltinExprConfig}, poseidon_hash::{PoseidonHashChip, PoseidonHashConfig}}, utils_2::common_helpers::{MAX_CANDIDATES, MAX_FACTS_HASHES}};
-
+use crate::{chips::fact_check::{built_in_check_chip::{BuiltinExprChip, BuiltinExprConfig}, compare_chip::i128_to_fp, holder_chip::{HolderChip, HolderConfig}, issuer_sig_chip::{IssuerSigChip, IssuerSigConfig}, merkle_chip::{MerkleChip, MerkleConfig}, poseidon_hash::{PoseidonHashChip, PoseidonHashConfig}, revocation_chip::{RevocationChip, RevocationConfig}, validity_chip::{ValidityChip, ValidityConfig}}, data::FactWitnessFp};
+
+/// Fact goals: the hash of the goal head (name, args, validity period, holder, salt) is signed by
+/// the issuer, in the registry, not revoked, valid now and held by the prover (`fact_ok`), or the
+/// goal is a built-in that holds (`builtin_ok`), or a rule matches it (one of the flags).
+///
+/// Flags (`q_not_flag`, `fact` column), starting from p = 1, one step per flag:
+///
+/// | p | flag | p' |
+///
+/// p' = p * (1 - flag), so the last p is Π(1 - flag_i) = 1 - any_flag.
+///
+/// Decision (`q_decision`, `fact` column):
+///
+/// | p | fact_ok | builtin_ok | is_fact | out |
+///
+/// ok = 1 - (1 - fact_ok)(1 - any_flag)(1 - builtin_ok), is_fact * (ok - 1) = 0, and
+/// out = 1 - (1 - fact_ok)(1 - builtin_ok) turns off the rule check of the goal.
 #[derive(Clone, Debug)]
 pub struct FactConfig {
     pub name: Column<Advice>,
     pub args: Column<Advice>,
     pub fact: Column<Advice>,
     pub salt: Column<Advice>,
-    pub hash_public: Column<Instance>,
     pub hash_advice: Column<Advice>,
     pub is_fact: Column<Advice>,
     pub pos_cfg: PoseidonHashConfig,
 
     pub builtin_cfg: BuiltinExprConfig,
+    pub issuer_cfg: IssuerSigConfig,
+    pub fact_root: Column<Instance>,
+    pub merkle_cfg: MerkleConfig,
+    pub revocation_cfg: RevocationConfig,
+    pub validity_cfg: ValidityConfig,
+    pub holder_cfg: HolderConfig,
+    pub q_not_flag: Selector,
+    pub q_decision: Selector,
+}
+
+/// Off-circuit part of a fact goal: the salt hashed into it, what the issuer handed out with it
+/// and the holder secret key of the prover.
+#[derive(Clone, Copy, Debug)]
+pub struct FactInput<'a> {
+    pub salt: Fp,
+    pub witness: &'a FactWitnessFp,
+    pub holder_sk: Fp,
 }
 
 #[derive(Clone, Debug)]
@@ -36,7 +69,13 @@ impl Chip<Fp> for FactChip {
 impl FactChip {
     pub fn construct (config: FactConfig) -> Self { Self { config }}
 
-    pub fn configure(meta: &mut ConstraintSystem<Fp>, hash_public: Column<Instance>) -> FactConfig {
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        issuer_pk: Column<Instance>,
+        fact_root: Column<Instance>,
+        revocation_root: Column<Instance>,
+        verification_time: Column<Instance>,
+    ) -> FactConfig {
         let fact = meta.advice_column();
         let salt = meta.advice_column();
         let hash_advice = meta.advice_column();
@@ -44,7 +83,6 @@ impl FactChip {
         let args = meta.advice_column();
         //let hash_public = meta.instance_column();
         let is_fact = meta.advice_column();
-        meta.enable_equality(hash_public); 
         meta.enable_equality(fact);
         meta.enable_equality(salt);
         meta.enable_equality(hash_advice);
@@ -55,31 +93,88 @@ impl FactChip {
 
         let pos_cfg = PoseidonHashChip::configure(meta);
         let builtin_cfg = BuiltinExprChip::configure(meta); 
-        FactConfig { name, args, fact, salt, hash_public, hash_advice, is_fact, pos_cfg, builtin_cfg, }
+        let issuer_cfg = IssuerSigChip::configure(meta, issuer_pk);
+        meta.enable_equality(fact_root);
+        let merkle_cfg = MerkleChip::configure(meta);
+        let revocation_cfg = RevocationChip::configure(
+            meta,
+            revocation_root,
+            merkle_cfg.clone(),
+            builtin_cfg.cmp_cfg.range_cfg.clone(),
+        );
+        let validity_cfg = ValidityChip::configure(meta, verification_time, builtin_cfg.cmp_cfg.clone());
+        let holder_cfg = HolderChip::configure(meta, issuer_cfg.ecc.clone());
+
+        let q_not_flag = meta.selector();
+        meta.create_gate("prod of (1 - flag)", |meta| {
+            let q = meta.query_selector(q_not_flag);
+            let [p, flag, p_next] = [0, 1, 2].map(|i| meta.query_advice(fact, Rotation(i)));
+            vec![q * (p_next - p * (Expression::Constant(Fp::ONE) - flag))]
+        });
+
+        let q_decision = meta.selector();
+        meta.create_gate("final decision gated by is_fact", |meta| {
+            let q = meta.query_selector(q_decision);
+            let [not_any_flag, fact_ok, builtin_ok, is_fact, out] =
+                [0, 1, 2, 3, 4].map(|i| meta.query_advice(fact, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            let neither = (one.clone() - fact_ok) * (one.clone() - builtin_ok);
+            vec![
+                // is_fact * (ok - 1) = -is_fact * (1 - ok)
+                q.clone() * is_fact * neither.clone() * not_any_flag,
+                q * (out - (one - neither)),
+            ]
+        });
+
+        FactConfig {
+            name, args, fact, salt, hash_advice, is_fact, pos_cfg, builtin_cfg,
+            issuer_cfg, fact_root, merkle_cfg, revocation_cfg, validity_cfg, holder_cfg,
+            q_not_flag, q_decision,
+        }
     }
 pub fn assign(
     &self,
     mut layouter: impl Layouter<Fp>,
     goal_name_cell: &[AssignedCell<Fp, Fp>],
     goal_name_args_cells: &[Vec<Vec<AssignedCell<Fp, Fp>>>],
-    goal_name_salt: &AssignedCell<Fp, Fp>,
     is_fact: &AssignedCell<Fp, Fp>,
-    flags:  &Vec<AssignedCell<Fp,Fp>>,
+    flags: &[AssignedCell<Fp, Fp>],
+    input: FactInput,
 ) -> Result<AssignedCell<Fp,Fp>, Error> {
     let cfg = &self.config;
+    let witness = input.witness;
 
-    // 1) Beépített kifejezéslánc ellenőrzése (külön chip, külön namespace!)
+    // 1) Beépített kifejezésfa ellenőrzése (külön chip, külön namespace!)
     let builtin_chip = BuiltinExprChip::construct(cfg.builtin_cfg.clone());
-    let builtin_ok = builtin_chip.eval_chain_equal(
+    let builtin_ok = builtin_chip.eval_builtin(
         layouter.namespace(|| "builtin expr"),   // külön namespace
-        &goal_name_cell,                         // p lista
-        &goal_name_args_cells,                   // p -> a -> l
-        false,                                   // itt nem kényszerítjük ok==1-re
-    )?;
+        goal_name_cell,                          // p lista
+        goal_name_args_cells,                    // p -> a -> l
+    )?;                                          // ok a döntési kapuba megy (8)
 
-    // 2) Hash-elés tokenláncról (név + minden arg[0] + salt)
+    // 2) Hash-elés tokenláncról (név + minden arg[0] + érvényesség + birtokos + salt)
     let pos_chip = PoseidonHashChip::construct(cfg.pos_cfg.clone());
 
+    let (valid_from, valid_until, holder, goal_name_salt) = layouter.assign_region(
+        || "validity period, holder, salt",
+        |mut region| {
+            let from = region.assign_advice(
+                || "valid_from", cfg.hash_advice, 0,
+                || Value::known(i128_to_fp(witness.validity.from.into())),
+            )?;
+            let until = region.assign_advice(
+                || "valid_until", cfg.hash_advice, 1,
+                || Value::known(i128_to_fp(witness.validity.until.into())),
+            )?;
+            let holder = region.assign_advice(
+                || "holder", cfg.hash_advice, 2,
+                || Value::known(witness.holder),
+            )?;
+            let salt = region.assign_advice(|| "salt", cfg.hash_advice, 3, || Value::known(input.salt))?;
+            Ok((from, until, holder, salt))
+        },
+    )?;
+
     let mut tokens: Vec<AssignedCell<Fp, Fp>> = vec![goal_name_cell[0].clone()];
 
     if let Some(args_matrix) = goal_name_args_cells.get(0) {
@@ -90,181 +185,89 @@ pub fn assign(
         }
     }
     
-    tokens.push(goal_name_salt.clone());
+    tokens.push(valid_from.clone());
+    tokens.push(valid_until.clone());
+    tokens.push(holder.clone());
+    tokens.push(goal_name_salt);
     let hashed = pos_chip.hash_list(
         layouter.namespace(|| "Poseidon(fact||salt)"),
         &tokens,
     )?;
-    // 3) Membership + flags + builtin kombináció EGY régióban
-    let result_cell = layouter.assign_region(
-        || "final decision (membership OR flags OR builtin) gated by is_fact",
-        |mut region| {
-            // zero
-            let zero = region.assign_advice(
-                || "zero",
-                cfg.salt, 1,
-                || Value::known(Fp::ZERO),
-            )?;
-
-            // is_fact lokális másolat + kötés
-            let is_fact_local = region.assign_advice(
-                || "is_fact local",
-                cfg.is_fact, 1,
-                || is_fact.value().copied(),
-            )?;
-            region.constrain_equal(is_fact_local.cell(), is_fact.cell())?;
-
-            // hashed lokális másolat + opcionális equality a külső 'hashed'-hez
-            let hashed_local = region.assign_advice(
-                || "hashed local",
-                cfg.hash_advice, 0,
-                || hashed.value().copied(),
-            )?;
-            region.constrain_equal(hashed_local.cell(), hashed.cell())?;
+    // 3) Kibocsátói aláírás a hash-en (Schnorr, publikus kulcs az instance oszlopban)
+    let sig_chip = IssuerSigChip::construct(cfg.issuer_cfg.clone());
+    let sig_ok = sig_chip.verify(
+        layouter.namespace(|| "issuer signature"),
+        &pos_chip,
+        &hashed,
+        &witness.signature,
+    )?;
 
-            // publikushash-szelekció (Σ bit_i*pub_i), és Σbit_i tanúk
-            let mut pub_sel_val = Value::known(Fp::ZERO);
-            let mut pub_sel_cell = zero.clone();
+    // 4) Benne van a kibocsátó nyilvántartásában (Merkle-út a publikus gyökérig), aláírással együtt
+    let merkle_chip = MerkleChip::construct(cfg.merkle_cfg.clone());
+    let (issued_ok, _) = merkle_chip.check_root(
+        layouter.namespace(|| "fact registry path"),
+        &pos_chip,
+        &hashed,
+        &witness.path,
+        cfg.fact_root,
+        &sig_ok,
+    )?;
 
-            for i in 0..MAX_FACTS_HASHES {
-                let pub_local = region.assign_advice_from_instance(
-                    || format!("pub[{i}]"),
-                    cfg.hash_public,
-                    i,
-                    cfg.hash_advice,
-                    i + 1,
-                )?;
+    // 5) Nincs visszavonva (üres levél a visszavonási fában a hash slotján)
+    let revocation_chip = RevocationChip::construct(cfg.revocation_cfg.clone());
+    let not_revoked_ok = revocation_chip.check_not_revoked(
+        layouter.namespace(|| "not revoked"),
+        &pos_chip,
+        &hashed,
+        &witness.revocation_path,
+        &issued_ok,
+    )?;
 
-                let bit_i = region.assign_advice(
-                    || format!("bit[{i}]"),
-                    cfg.fact, i + 1,
-                    || hashed_local.value().zip(pub_local.value())
-                        .map(|(h,p)| if *h == *p { Fp::ONE } else { Fp::ZERO })
-                )?;
-                // bit_i booleanitás (kapuzva is_fact-tal)
-                let bool_expr = region.assign_advice(
-                    || format!("bit_bool[{i}]"),
-                    cfg.salt, i + 2,
-                    || is_fact_local.value().zip(bit_i.value())
-                         .map(|(f,b)| f * *b * (*b - Fp::ONE)),
-                )?;
-                region.constrain_equal(bool_expr.cell(), zero.cell())?;
+    // 6) Érvényes a publikus ellenőrzési időpontban (valid_from <= now <= valid_until)
+    let validity_chip = ValidityChip::construct(cfg.validity_cfg.clone());
+    let valid_ok = validity_chip.check_valid(
+        layouter.namespace(|| "validity period"),
+        &valid_from,
+        &valid_until,
+        &not_revoked_ok,
+    )?;
 
-                pub_sel_val = pub_sel_val.zip(bit_i.value())
-                    .zip(pub_local.value())
-                    .map(|((acc,b),p)| acc + *b * *p);
+    // 7) A bizonyító ismeri a fact birtokosának titkos kulcsát (ha a fact birtokoshoz kötött)
+    let holder_chip = HolderChip::construct(cfg.holder_cfg.clone());
+    let fact_ok = holder_chip.check_holder(
+        layouter.namespace(|| "holder binding"),
+        &pos_chip,
+        &holder,
+        input.holder_sk,
+        &valid_ok,
+    )?;
 
-                pub_sel_cell = region.assign_advice(
-                    || format!("pub_sel[{i}]"),
-                    cfg.hash_advice, MAX_FACTS_HASHES + 1 + i,
-                    || pub_sel_val,
-                )?;
+    // 8) Aláírás/nyilvántartás/visszavonás/érvényesség/birtokos + flags + builtin kombináció, is_fact-tal kapuzva
+    layouter.assign_region(
+        || "final decision (fact OR flags OR builtin) gated by is_fact",
+        |mut region| {
+            let mut not_any_flag = region.assign_advice_from_constant(|| "Π(1 - flag) = 1", cfg.fact, 0, Fp::ONE)?;
+            for (i, flag) in flags.iter().enumerate() {
+                cfg.q_not_flag.enable(&mut region, 2 * i)?;
+                let flag = flag.copy_advice(|| format!("flag {i}"), &mut region, cfg.fact, 2 * i + 1)?;
+                let next = not_any_flag.value().zip(flag.value()).map(|(p, b)| *p * (Fp::ONE - *b));
+                not_any_flag = region.assign_advice(|| format!("Π(1 - flag) to {i}"), cfg.fact, 2 * i + 2, || next)?;
             }
 
-            // flags: any_flag = 1 - Π(1 - flag_i)
-            let flag_vals: Vec<Value<Fp>> = flags.iter()
-    .map(|b| b.value().copied())
-    .collect();
-
-        // Compute prod_not_flag in memory
-        let mut prod_not_flag_val = Value::known(Fp::ONE);
-        for fv in flag_vals.iter() {
-            prod_not_flag_val = prod_not_flag_val.zip(*fv)
-                .map(|(acc, b)| acc * (Fp::ONE - b));
-        }
-
-        // Assign a single advice cell
-        let _prod_not_flag_cell = region.assign_advice(
-            || "prod_not_flag_final",
-            cfg.salt,
-            MAX_CANDIDATES + MAX_FACTS_HASHES + 1,
-            || prod_not_flag_val,
-        )?;
-
-        // any_flag = 1 - prod_not_flag
-        let any_flag_cell = region.assign_advice(
-            || "any_flag",
-            cfg.salt,
-            MAX_CANDIDATES + MAX_FACTS_HASHES + 2,
-            || prod_not_flag_val.map(|v| Fp::ONE - v),
-        )?;
-
-        // Boolean constraint: any_flag ∈ {0,1}
-        let be = region.assign_advice(
-            || "af*(1-af)",
-            cfg.salt,
-            MAX_CANDIDATES + MAX_FACTS_HASHES + 3,
-            || any_flag_cell.value().map(|v| *v * (Fp::ONE - *v)),
-        )?;
-        let z = region.assign_advice(
-            || "0",
-            cfg.salt,
-            MAX_CANDIDATES + MAX_FACTS_HASHES + 4,
-            || Value::known(Fp::ZERO),
-        )?;
-        region.constrain_equal(be.cell(), z.cell())?;
-
-        
-
-            // builtin_ok lokális másolat (hogy ebben a régióban is lásd)
-            let builtin_ok_local = region.assign_advice(
-                || "builtin_ok local",
-                cfg.fact, MAX_FACTS_HASHES*2 + 101,
-                || builtin_ok.value().copied(),
+            let row = 2 * flags.len();
+            cfg.q_decision.enable(&mut region, row)?;
+            let fact_ok = fact_ok.copy_advice(
+                || "fact_ok = signed, in the registry, not revoked, valid, held by the prover",
+                &mut region, cfg.fact, row + 1,
             )?;
-
-            //  OR logika ZKP-biztosan: OR(a,b,c) = 1 - (1-a)(1-b)(1-c)
-            let fact_ok_cell = region.assign_advice(
-                || "fact_ok = [hashed==pub_sel]",
-                cfg.fact, MAX_FACTS_HASHES*2 + 102,
-                || hashed_local.value().zip(pub_sel_cell.value())
-                     .map(|(h,ps)| if *h == *ps { Fp::ONE } else { Fp::ZERO })
-            )?;
-
-            let or_abc = region.assign_advice(
-                || "or_abc",
-                cfg.fact, MAX_FACTS_HASHES*2 + 103,
-                || fact_ok_cell.value()
-                    .zip(any_flag_cell.value())
-                    .zip(builtin_ok_local.value())
-                    .map(|((a,b),c)| {
-                        let na = Fp::ONE - *a;
-                        let nb = Fp::ONE - *b;
-                        let nc = Fp::ONE - *c;
-                        Fp::ONE - (na * nb * nc)
-                    })
-            )?;
-
-            //println!("IS FACT?: {:?}", is_fact_local.value());
-            //println!("FACT OKAY?: {:?}", fact_ok_cell.value());
-            //println!("BUILT IN?: {:?}", builtin_ok.value());
-            //println!("ANY FLAG?: {:?}", any_flag_cell.value());
-
-
-            // final_ok = is_fact * or_abc
-            let gate = region.assign_advice(
-            || "gated_final_ok",
-            cfg.salt,
-            MAX_FACTS_HASHES*2 + 105,
-            || is_fact_local.value().zip(or_abc.value())
-                .map(|(f, o)| *f * (*o - Fp::ONE))
-        )?;
-        region.constrain_equal(gate.cell(), zero.cell())?;
-
-        let final_return_cell = region.assign_advice(
-            || "final_return",
-            cfg.fact,
-            MAX_FACTS_HASHES*2 + 200,
-            || builtin_ok_local.value().zip(fact_ok_cell.value())
-                .map(|(b,f)| if *b == Fp::ONE || *f == Fp::ONE { Fp::ONE } else { Fp::ZERO })
-        )?;
-
-            Ok(final_return_cell)
-        }
-    )?;
-
-    Ok(result_cell)
+            let builtin_ok = builtin_ok.copy_advice(|| "builtin_ok", &mut region, cfg.fact, row + 2)?;
+            is_fact.copy_advice(|| "is_fact", &mut region, cfg.fact, row + 3)?;
+            let out = fact_ok.value().zip(builtin_ok.value())
+                .map(|(f, b)| Fp::ONE - (Fp::ONE - *f) * (Fp::ONE - *b));
+            region.assign_advice(|| "fact or builtin", cfg.fact, row + 4, || out)
+        },
+    )
 }
 
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,117 @@
+use halo2_gadgets::ecc::{chip::{EccChip, EccConfig}, NonIdentityPoint, ScalarVar};
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::{pallas, Fp},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use halo2curves::{ff::Field, group::prime::PrimeCurveAffine};
+
+use crate::{
+    chips::fact_check::{issuer_sig_chip::NoFixedBases, poseidon_hash::PoseidonHashChip},
+    utils_2::issuer_signature::affine_xy,
+};
+
+/// Holder binding of a fact (`utils_2::holder_binding`): the prover knows the secret key sk of
+/// the holder id hashed into the fact, id = Poseidon((sk·G).x, (sk·G).y). A fact with holder 0 is
+/// not bound, it passes with any key.
+///
+/// Check (`q_holder`, one `work` column):
+///
+/// | holder | inv_h | id | inv_d | en | ok |
+///
+/// unbound = 1 - holder*inv_h, holder * unbound = 0; same = 1 - (id-holder)*inv_d,
+/// (id-holder) * same = 0; ok = en * (unbound + (1-unbound)*same).
+#[derive(Clone, Debug)]
+pub struct HolderConfig {
+    pub ecc: EccConfig<NoFixedBases>,
+    pub work: Column<Advice>,
+    pub q_holder: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct HolderChip {
+    cfg: HolderConfig,
+}
+
+impl Chip<Fp> for HolderChip {
+    type Config = HolderConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl HolderChip {
+    pub fn construct(cfg: HolderConfig) -> Self { Self { cfg } }
+
+    /// `ecc`: the ECC chip of the issuer signature check, its table is loaded there
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, ecc: EccConfig<NoFixedBases>) -> HolderConfig {
+        let work = meta.advice_column();
+        meta.enable_equality(work);
+
+        let q_holder = meta.selector();
+        meta.create_gate("holder binding", |meta| {
+            let q = meta.query_selector(q_holder);
+            let [holder, inv_h, id, inv_d, en, ok] =
+                [0, 1, 2, 3, 4, 5].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            let unbound = one.clone() - holder.clone() * inv_h;
+            let d = id - holder.clone();
+            let same = one.clone() - d.clone() * inv_d;
+            vec![
+                q.clone() * holder * unbound.clone(),
+                q.clone() * d * same.clone(),
+                q * (ok - en * (unbound.clone() + (one - unbound) * same)),
+            ]
+        });
+
+        HolderConfig { ecc, work, q_holder }
+    }
+
+    /// `en * [holder == 0 or holder == id(sk)]`
+    pub fn check_holder(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        pos_chip: &PoseidonHashChip,
+        holder: &AssignedCell<Fp, Fp>,
+        holder_sk: Fp,
+        en: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let cfg = &self.cfg;
+        let ecc = EccChip::construct(cfg.ecc.clone());
+
+        let sk = layouter.assign_region(
+            || "holder sk",
+            |mut region| region.assign_advice(|| "sk", cfg.work, 0, || Value::known(holder_sk)),
+        )?;
+        let g = NonIdentityPoint::new(ecc.clone(), layouter.namespace(|| "G"), Value::known(pallas::Affine::generator()))?;
+        let [gx, gy] = affine_xy(&pallas::Affine::generator());
+        layouter.assign_region(
+            || "G to its constant",
+            |mut region| {
+                region.constrain_constant(g.inner().x().cell(), gx)?;
+                region.constrain_constant(g.inner().y().cell(), gy)
+            },
+        )?;
+
+        let sk = ScalarVar::from_base(ecc, layouter.namespace(|| "sk scalar"), &sk)?;
+        let (pk, _) = g.mul(layouter.namespace(|| "PK = sk·G"), sk)?;
+        let id = pos_chip.hash2(layouter.namespace(|| "id = Poseidon(PK)"), [pk.inner().x(), pk.inner().y()])?;
+
+        layouter.assign_region(
+            || "holder == 0 or holder == id",
+            |mut region| {
+                cfg.q_holder.enable(&mut region, 0)?;
+                let h = holder.copy_advice(|| "holder", &mut region, cfg.work, 0)?;
+                region.assign_advice(|| "inv_h", cfg.work, 1, || h.value().map(|h| h.invert().unwrap_or(Fp::ZERO)))?;
+                let id = id.copy_advice(|| "id", &mut region, cfg.work, 2)?;
+                let d = id.value().zip(h.value()).map(|(id, h)| *id - *h);
+                region.assign_advice(|| "inv_d", cfg.work, 3, || d.map(|d| d.invert().unwrap_or(Fp::ZERO)))?;
+                let en = en.copy_advice(|| "en", &mut region, cfg.work, 4)?;
+                let ok = en.value().zip(h.value()).zip(d)
+                    .map(|((en, h), d)| if *h == Fp::ZERO || d == Fp::ZERO { *en } else { Fp::ZERO });
+                region.assign_advice(|| "ok", cfg.work, 5, || ok)
+            },
+        )
+    }
+}
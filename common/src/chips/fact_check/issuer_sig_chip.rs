@@ -0,0 +1,218 @@
+use halo2_gadgets::{
+    ecc::{
+        chip::{BaseFieldElem, EccChip, EccConfig, FixedPoint, FullScalar, ShortScalar, H},
+        FixedPoints, NonIdentityPoint, ScalarVar,
+    },
+    sinsemilla::primitives as sinsemilla,
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::{pallas, Fp},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector, TableColumn},
+    poly::Rotation,
+};
+use halo2curves::{ff::Field, group::prime::PrimeCurveAffine};
+
+use crate::{
+    chips::fact_check::poseidon_hash::PoseidonHashChip,
+    data::FactSignatureFp,
+    utils_2::issuer_signature::{affine_xy, point_or_generator},
+};
+
+/// Only variable-base multiplications are used, the ECC chip gets no fixed bases.
+macro_rules! no_fixed_base {
+    ($name:ident, $kind:ty) => {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum $name {}
+
+        impl FixedPoint<pallas::Affine> for $name {
+            type FixedScalarKind = $kind;
+
+            fn generator(&self) -> pallas::Affine { match *self {} }
+            fn u(&self) -> Vec<[[u8; 32]; H]> { match *self {} }
+            fn z(&self) -> Vec<u64> { match *self {} }
+        }
+    };
+}
+
+no_fixed_base!(NoFullScalarBase, FullScalar);
+no_fixed_base!(NoShortScalarBase, ShortScalar);
+no_fixed_base!(NoBaseFieldBase, BaseFieldElem);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoFixedBases;
+
+impl FixedPoints<pallas::Affine> for NoFixedBases {
+    type FullScalar = NoFullScalarBase;
+    type ShortScalar = NoShortScalarBase;
+    type Base = NoBaseFieldBase;
+}
+
+/// Schnorr check of the issuer signature on a fact hash m (`utils_2::issuer_signature`):
+///
+/// PK = public input (x, y), e = Poseidon(R.x, R.y, PK.x, PK.y, m), ok = [s·G == R + e·PK]
+///
+/// The points are witnessed on the curve by the ECC chip, s and e are multiplied as base field
+/// elements. `ok` is not forced to 1, the caller gates it (only the facts need a signature).
+///
+/// Point equality (`q_point_eq`, one `work` column):
+///
+/// | x1 | y1 | x2 | y2 | inv_x | inv_y | ok |
+///
+/// ok = (1 - (x1-x2)*inv_x) * (1 - (y1-y2)*inv_y), and (x1-x2), (y1-y2) times their factor are 0.
+#[derive(Clone, Debug)]
+pub struct IssuerSigConfig {
+    pub ecc: EccConfig<NoFixedBases>,
+    pub range_check: LookupRangeCheckConfig<Fp, { sinsemilla::K }>,
+    pub table_idx: TableColumn,
+    pub issuer_pk: Column<Instance>,
+    pub work: Column<Advice>,
+    pub q_point_eq: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct IssuerSigChip {
+    cfg: IssuerSigConfig,
+}
+
+impl Chip<Fp> for IssuerSigChip {
+    type Config = IssuerSigConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl IssuerSigChip {
+    pub fn construct(cfg: IssuerSigConfig) -> Self { Self { cfg } }
+
+    /// `issuer_pk`: instance column of the issuer public key, row 0 = x, row 1 = y
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, issuer_pk: Column<Instance>) -> IssuerSigConfig {
+        let advices: [Column<Advice>; 10] = std::array::from_fn(|_| meta.advice_column());
+        for col in advices {
+            meta.enable_equality(col);
+        }
+        let lagrange_coeffs: [Column<Fixed>; 8] = std::array::from_fn(|_| meta.fixed_column());
+        meta.enable_constant(lagrange_coeffs[0]);
+
+        let table_idx = meta.lookup_table_column();
+        let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+        let ecc = EccChip::<NoFixedBases>::configure(meta, advices, lagrange_coeffs, range_check);
+
+        let work = meta.advice_column();
+        meta.enable_equality(work);
+        meta.enable_equality(issuer_pk);
+
+        let q_point_eq = meta.selector();
+        meta.create_gate("point equality", |meta| {
+            let q = meta.query_selector(q_point_eq);
+            let [x1, y1, x2, y2, inv_x, inv_y, ok] =
+                [0, 1, 2, 3, 4, 5, 6].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            let (dx, dy) = (x1 - x2, y1 - y2);
+            let eq_x = one.clone() - dx.clone() * inv_x;
+            let eq_y = one - dy.clone() * inv_y;
+            vec![
+                q.clone() * dx * eq_x.clone(),
+                q.clone() * dy * eq_y.clone(),
+                q * (ok - eq_x * eq_y),
+            ]
+        });
+
+        IssuerSigConfig { ecc, range_check, table_idx, issuer_pk, work, q_point_eq }
+    }
+
+    /// Fills the 2^10 row table of the ECC chip's range checks (`LookupRangeCheckConfig::load` is
+    /// test-only in halo2_gadgets). Must be called exactly once per synthesis.
+    pub fn load_table(&self, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let cfg = &self.cfg;
+        layouter.assign_table(
+            || "ecc range check table",
+            |mut table| {
+                for i in 0..(1usize << sinsemilla::K) {
+                    table.assign_cell(
+                        || format!("word {i}"),
+                        cfg.table_idx,
+                        i,
+                        || Value::known(Fp::from(i as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// `ok` cell of the signature check of `msg`.
+    pub fn verify(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        pos_chip: &PoseidonHashChip,
+        msg: &AssignedCell<Fp, Fp>,
+        sig: &FactSignatureFp,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let cfg = &self.cfg;
+        let ecc = EccChip::construct(cfg.ecc.clone());
+
+        let (pk_x, pk_y, s) = layouter.assign_region(
+            || "issuer pk + s",
+            |mut region| {
+                let x = region.assign_advice_from_instance(|| "pk x", cfg.issuer_pk, 0, cfg.work, 0)?;
+                let y = region.assign_advice_from_instance(|| "pk y", cfg.issuer_pk, 1, cfg.work, 1)?;
+                let s = region.assign_advice(|| "s", cfg.work, 2, || Value::known(sig.s))?;
+                Ok((x, y, s))
+            },
+        )?;
+
+        let pk_val = pk_x.value().zip(pk_y.value()).map(|(x, y)| point_or_generator([*x, *y]));
+        let pk = NonIdentityPoint::new(ecc.clone(), layouter.namespace(|| "PK"), pk_val)?;
+        let g = NonIdentityPoint::new(ecc.clone(), layouter.namespace(|| "G"), Value::known(pallas::Affine::generator()))?;
+        let r = NonIdentityPoint::new(ecc.clone(), layouter.namespace(|| "R"), Value::known(point_or_generator(sig.r)))?;
+
+        let [gx, gy] = affine_xy(&pallas::Affine::generator());
+        layouter.assign_region(
+            || "bind PK to the instance, G to its constant",
+            |mut region| {
+                region.constrain_equal(pk.inner().x().cell(), pk_x.cell())?;
+                region.constrain_equal(pk.inner().y().cell(), pk_y.cell())?;
+                region.constrain_constant(g.inner().x().cell(), gx)?;
+                region.constrain_constant(g.inner().y().cell(), gy)
+            },
+        )?;
+
+        let e = pos_chip.hash_list(
+            layouter.namespace(|| "e = Poseidon(R, PK, m)"),
+            &[r.inner().x(), r.inner().y(), pk_x, pk_y, msg.clone()],
+        )?;
+
+        let s = ScalarVar::from_base(ecc.clone(), layouter.namespace(|| "s scalar"), &s)?;
+        let (s_g, _) = g.mul(layouter.namespace(|| "s·G"), s)?;
+        let e = ScalarVar::from_base(ecc.clone(), layouter.namespace(|| "e scalar"), &e)?;
+        let (e_pk, _) = pk.mul(layouter.namespace(|| "e·PK"), e)?;
+        let rhs = r.add(layouter.namespace(|| "R + e·PK"), &e_pk)?;
+
+        layouter.assign_region(
+            || "s·G == R + e·PK",
+            |mut region| {
+                cfg.q_point_eq.enable(&mut region, 0)?;
+                let coords = [s_g.inner().x(), s_g.inner().y(), rhs.inner().x(), rhs.inner().y()];
+                for (i, c) in coords.iter().enumerate() {
+                    c.copy_advice(|| "coordinate", &mut region, cfg.work, i)?;
+                }
+                let inv = |a: &AssignedCell<Fp, Fp>, b: &AssignedCell<Fp, Fp>| {
+                    a.value().zip(b.value()).map(|(a, b)| (*a - *b).invert().unwrap_or(Fp::ZERO))
+                };
+                let eq = |a: &AssignedCell<Fp, Fp>, b: &AssignedCell<Fp, Fp>| {
+                    a.value().zip(b.value()).map(|(a, b)| if a == b { Fp::ONE } else { Fp::ZERO })
+                };
+                region.assign_advice(|| "inv_x", cfg.work, 4, || inv(&coords[0], &coords[2]))?;
+                region.assign_advice(|| "inv_y", cfg.work, 5, || inv(&coords[1], &coords[3]))?;
+                region.assign_advice(
+                    || "ok",
+                    cfg.work,
+                    6,
+                    || eq(&coords[0], &coords[2]) * eq(&coords[1], &coords[3]),
+                )
+            },
+        )
+    }
+}
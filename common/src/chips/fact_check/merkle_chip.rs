@@ -0,0 +1,140 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use halo2curves::ff::Field;
+
+use crate::{chips::fact_check::poseidon_hash::PoseidonHashChip, data::FactPathFp};
+
+/// `ok` cell of a root check and the bit cells of the leaf index (leaf first).
+pub type RootCheck = (AssignedCell<Fp, Fp>, Vec<AssignedCell<Fp, Fp>>);
+
+/// Path of a leaf in a Poseidon Merkle tree (the fact registry, `utils_2::fact_registry`, and the
+/// revocation tree, `utils_2::revocation`) up to a root that is a public input.
+///
+/// One level (`q_swap`, one `work` column), b is the bit of the level in the leaf index:
+///
+/// | cur | sib | b | left | right |
+///
+/// b is boolean, left = cur + b*(sib-cur), right = sib + b*(cur-sib), the next cur is
+/// Poseidon(left, right).
+///
+/// Root check (`q_root`):
+///
+/// | cur | root | inv | en | ok |
+///
+/// ok = en * (1 - (cur-root)*inv), and (cur-root) * (1 - (cur-root)*inv) = 0, so ok = en*[cur == root].
+#[derive(Clone, Debug)]
+pub struct MerkleConfig {
+    pub work: Column<Advice>,
+    pub q_swap: Selector,
+    pub q_root: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct MerkleChip {
+    cfg: MerkleConfig,
+}
+
+impl Chip<Fp> for MerkleChip {
+    type Config = MerkleConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl MerkleChip {
+    pub fn construct(cfg: MerkleConfig) -> Self { Self { cfg } }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> MerkleConfig {
+        let work = meta.advice_column();
+        meta.enable_equality(work);
+
+        let q_swap = meta.selector();
+        meta.create_gate("merkle level swap", |meta| {
+            let q = meta.query_selector(q_swap);
+            let [cur, sib, b, left, right] = [0, 1, 2, 3, 4].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            vec![
+                q.clone() * b.clone() * (one - b.clone()),
+                q.clone() * (left - (cur.clone() + b.clone() * (sib.clone() - cur.clone()))),
+                q * (right - (sib.clone() + b * (cur - sib))),
+            ]
+        });
+
+        let q_root = meta.selector();
+        meta.create_gate("merkle root", |meta| {
+            let q = meta.query_selector(q_root);
+            let [cur, root, inv, en, ok] = [0, 1, 2, 3, 4].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            let d = cur - root;
+            let eq = one - d.clone() * inv;
+            vec![
+                q.clone() * d * eq.clone(),
+                q * (ok - en * eq),
+            ]
+        });
+
+        MerkleConfig { work, q_swap, q_root }
+    }
+
+    /// `en * [root of leaf along path == root_col[0]]`, and the bit cells of the index (leaf first).
+    pub fn check_root(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        pos_chip: &PoseidonHashChip,
+        leaf: &AssignedCell<Fp, Fp>,
+        path: &FactPathFp,
+        root_col: Column<Instance>,
+        en: &AssignedCell<Fp, Fp>,
+    ) -> Result<RootCheck, Error> {
+        let cfg = &self.cfg;
+        let mut cur = leaf.clone();
+        let mut bits = Vec::with_capacity(path.siblings.len());
+
+        for (i, sib) in path.siblings.iter().enumerate() {
+            let right_child = (path.index >> i) & 1 == 1;
+            let (left, right, bit) = layouter.assign_region(
+                || format!("merkle level {i}"),
+                |mut region| {
+                    cfg.q_swap.enable(&mut region, 0)?;
+                    let c = cur.copy_advice(|| "cur", &mut region, cfg.work, 0)?;
+                    region.assign_advice(|| "sib", cfg.work, 1, || Value::known(*sib))?;
+                    let b = if right_child { Fp::ONE } else { Fp::ZERO };
+                    let bit = region.assign_advice(|| "b", cfg.work, 2, || Value::known(b))?;
+                    let (l, r) = if right_child {
+                        (Value::known(*sib), c.value().copied())
+                    } else {
+                        (c.value().copied(), Value::known(*sib))
+                    };
+                    let left = region.assign_advice(|| "left", cfg.work, 3, || l)?;
+                    let right = region.assign_advice(|| "right", cfg.work, 4, || r)?;
+                    Ok((left, right, bit))
+                },
+            )?;
+            bits.push(bit);
+            cur = pos_chip.hash2(layouter.namespace(|| format!("merkle node {i}")), [left, right])?;
+        }
+
+        let ok = layouter.assign_region(
+            || "merkle root == public root",
+            |mut region| {
+                cfg.q_root.enable(&mut region, 0)?;
+                let c = cur.copy_advice(|| "cur", &mut region, cfg.work, 0)?;
+                let root = region.assign_advice_from_instance(|| "root", root_col, 0, cfg.work, 1)?;
+                let d = c.value().zip(root.value()).map(|(c, r)| *c - *r);
+                region.assign_advice(|| "inv", cfg.work, 2, || d.map(|d| d.invert().unwrap_or(Fp::ZERO)))?;
+                let en = en.copy_advice(|| "en", &mut region, cfg.work, 3)?;
+                region.assign_advice(
+                    || "ok",
+                    cfg.work,
+                    4,
+                    || en.value().zip(d).map(|(en, d)| if d == Fp::ZERO { *en } else { Fp::ZERO }),
+                )
+            },
+        )?;
+        Ok((ok, bits))
+    }
+}
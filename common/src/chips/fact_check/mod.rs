@@ -1,3 +1,10 @@
 pub mod fact_hash_chip;
 pub mod poseidon_hash;
-pub mod built_in_check_chip;
\ No newline at end of file
+pub mod built_in_check_chip;
+pub mod compare_chip;
+pub mod div_rem_chip;
+pub mod issuer_sig_chip;
+pub mod merkle_chip;
+pub mod revocation_chip;
+pub mod validity_chip;
+pub mod holder_chip;
@@ -73,8 +73,9 @@ impl PoseidonHashChip {
         let inputs: [AssignedCell<Fp, Fp>; 2] = layouter.assign_region(
             || "hash2 inputs",
             |mut region| {
-                let a = region.assign_advice(|| "a", self.cfg.input_col, 0, || pair[0].value().copied())?;
-                let b = region.assign_advice(|| "b", self.cfg.input_col, 1, || pair[1].value().copied())?;
+                // copy, so the digest is bound to the caller's cells
+                let a = pair[0].copy_advice(|| "a", &mut region, self.cfg.input_col, 0)?;
+                let b = pair[1].copy_advice(|| "b", &mut region, self.cfg.input_col, 1)?;
                 Ok([a, b])
             },
         )?;
@@ -99,11 +100,11 @@ impl PoseidonHashChip {
     let mut acc = layouter.assign_region(
         || "initial acc",
         |mut region| {
-            region.assign_advice(
+            region.assign_advice_from_constant(
                 || "init acc",
                 self.cfg.input_col,
                 0,
-                || Value::known(Fp::ZERO),
+                Fp::ZERO,
             )
         },
     )?;
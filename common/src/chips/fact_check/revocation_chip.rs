@@ -0,0 +1,237 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use halo2curves::ff::Field;
+
+use crate::{
+    chips::{
+        fact_check::{merkle_chip::{MerkleChip, MerkleConfig}, poseidon_hash::PoseidonHashChip},
+        range_check_chip::{RangeCheckChip, RangeCheckConfig},
+    },
+    data::FactPathFp,
+    utils_2::{common_helpers::RANGE_CHECK_LIMB_BITS, revocation::REVOCATION_TREE_DEPTH},
+};
+
+// bits of h above the slot, range checked in whole limbs
+const HIGH_BITS: usize = 254 - REVOCATION_TREE_DEPTH;
+const _: () = assert!(HIGH_BITS.is_multiple_of(RANGE_CHECK_LIMB_BITS));
+
+/// Non-membership of a fact hash h in the revocation tree (`utils_2::revocation`): the path of
+/// its slot leads from a 0 leaf to the public root.
+///
+/// The slot is the index of the path, its bits (leaf first, from `MerkleChip::check_root`) are
+/// summed up from the top (`q_horner`, `work` column):
+///
+/// | acc | b | acc' |   acc' = 2*acc + b
+///
+/// and tied to h (`q_split`):
+///
+/// | h | slot | high | en |   en * (h - slot - 2^D*high) = 0, high < 2^(254-D)
+///
+/// so with en = 1 the slot is the low D bits of h (the sum is below p, the split is unique).
+#[derive(Clone, Debug)]
+pub struct RevocationConfig {
+    pub revocation_root: Column<Instance>,
+    pub merkle_cfg: MerkleConfig,
+    pub range_cfg: RangeCheckConfig,
+    pub work: Column<Advice>,
+    pub q_horner: Selector,
+    pub q_split: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct RevocationChip {
+    cfg: RevocationConfig,
+}
+
+impl Chip<Fp> for RevocationChip {
+    type Config = RevocationConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl RevocationChip {
+    pub fn construct(cfg: RevocationConfig) -> Self { Self { cfg } }
+
+    /// `revocation_root`: instance column of the revocation tree root (row 0)
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        revocation_root: Column<Instance>,
+        merkle_cfg: MerkleConfig,
+        range_cfg: RangeCheckConfig,
+    ) -> RevocationConfig {
+        let work = meta.advice_column();
+        meta.enable_equality(work);
+        meta.enable_equality(revocation_root);
+
+        let q_horner = meta.selector();
+        meta.create_gate("slot bits", |meta| {
+            let q = meta.query_selector(q_horner);
+            let [acc, b, next] = [0, 1, 2].map(|i| meta.query_advice(work, Rotation(i)));
+            vec![q * (next - (acc * Expression::Constant(Fp::from(2)) + b))]
+        });
+
+        let q_split = meta.selector();
+        meta.create_gate("slot = low bits of h", |meta| {
+            let q = meta.query_selector(q_split);
+            let [h, slot, high, en] = [0, 1, 2, 3].map(|i| meta.query_advice(work, Rotation(i)));
+            let shift = Expression::Constant(Fp::from(2).pow_vartime([REVOCATION_TREE_DEPTH as u64]));
+            vec![q * en * (h - slot - shift * high)]
+        });
+
+        RevocationConfig { revocation_root, merkle_cfg, range_cfg, work, q_horner, q_split }
+    }
+
+    /// `en * [h is not revoked]`
+    pub fn check_not_revoked(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        pos_chip: &PoseidonHashChip,
+        fact_hash: &AssignedCell<Fp, Fp>,
+        path: &FactPathFp,
+        en: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let cfg = &self.cfg;
+        let merkle_chip = MerkleChip::construct(cfg.merkle_cfg.clone());
+        let range_chip = RangeCheckChip::construct(cfg.range_cfg.clone());
+
+        let empty_leaf = layouter.assign_region(
+            || "empty leaf",
+            |mut region| region.assign_advice_from_constant(|| "0", cfg.work, 0, Fp::ZERO),
+        )?;
+        let (ok, bits) = merkle_chip.check_root(
+            layouter.namespace(|| "revocation path"),
+            pos_chip,
+            &empty_leaf,
+            path,
+            cfg.revocation_root,
+            en,
+        )?;
+
+        let slot = layouter.assign_region(
+            || "slot from the path bits",
+            |mut region| {
+                let mut acc = region.assign_advice_from_constant(|| "acc 0", cfg.work, 0, Fp::ZERO)?;
+                for (j, bit) in bits.iter().rev().enumerate() {
+                    cfg.q_horner.enable(&mut region, 2 * j)?;
+                    let b = bit.copy_advice(|| "b", &mut region, cfg.work, 2 * j + 1)?;
+                    let next = acc.value().zip(b.value()).map(|(a, b)| *a + *a + *b);
+                    acc = region.assign_advice(|| "acc", cfg.work, 2 * j + 2, || next)?;
+                }
+                Ok(acc)
+            },
+        )?;
+
+        let high = layouter.assign_region(
+            || "split h",
+            |mut region| {
+                cfg.q_split.enable(&mut region, 0)?;
+                let h = fact_hash.copy_advice(|| "h", &mut region, cfg.work, 0)?;
+                let s = slot.copy_advice(|| "slot", &mut region, cfg.work, 1)?;
+                let shift_inv = Fp::from(2).pow_vartime([REVOCATION_TREE_DEPTH as u64]).invert().unwrap();
+                // high is only meaningful when it is enforced, 0 passes the range check otherwise
+                let high = h.value().zip(s.value()).zip(en.value())
+                    .map(|((h, s), en)| *en * (*h - *s) * shift_inv);
+                let high = region.assign_advice(|| "high", cfg.work, 2, || high)?;
+                en.copy_advice(|| "en", &mut region, cfg.work, 3)?;
+                Ok(high)
+            },
+        )?;
+        range_chip.range_check(layouter.namespace(|| "range(high)"), &high, HIGH_BITS)?;
+
+        Ok(ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::{SimpleFloorPlanner, Value}, dev::MockProver, plonk::Circuit};
+
+    use super::*;
+    use crate::{chips::fact_check::poseidon_hash::PoseidonHashConfig, utils_2::revocation::RevocationRegistry};
+
+    const K: u32 = 14;
+
+    // en * [h is not revoked] against the root of `path`'s tree, `expected` is the output it must give
+    #[derive(Clone)]
+    struct RevocationCircuit {
+        h: Fp,
+        en: Fp,
+        path: FactPathFp,
+        expected: Fp,
+    }
+
+    impl Circuit<Fp> for RevocationCircuit {
+        type Config = (RevocationConfig, PoseidonHashConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let range_cfg = RangeCheckChip::configure(meta, RANGE_CHECK_LIMB_BITS);
+            let pos_cfg = PoseidonHashChip::configure(meta);
+            let merkle_cfg = MerkleChip::configure(meta);
+            let root = meta.instance_column();
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (RevocationChip::configure(meta, root, merkle_cfg, range_cfg), pos_cfg, input)
+        }
+
+        fn synthesize(&self, (cfg, pos_cfg, input): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            RangeCheckChip::construct(cfg.range_cfg.clone()).load_table(layouter.namespace(|| "table"))?;
+            let [h, en] = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let mut cell = |i: usize, v: Fp| region.assign_advice(|| "input", input, i, || Value::known(v));
+                    Ok([cell(0, self.h)?, cell(1, self.en)?])
+                },
+            )?;
+            let pos_chip = PoseidonHashChip::construct(pos_cfg);
+            let ok = RevocationChip::construct(cfg)
+                .check_not_revoked(layouter.namespace(|| "not revoked"), &pos_chip, &h, &self.path, &en)?;
+            layouter.assign_region(|| "expected", |mut region| region.constrain_constant(ok.cell(), self.expected))
+        }
+    }
+
+    fn verify(c: &RevocationCircuit, root: Fp) -> bool {
+        MockProver::run(K, c, vec![vec![root]]).unwrap().verify().is_ok()
+    }
+
+    fn registry() -> (RevocationRegistry, Fp, Fp) {
+        let (live, revoked) = (Fp::from(0x1234_5678_9abc), Fp::from(0xdead_beef_0042));
+        (RevocationRegistry::new(&[revoked]), live, revoked)
+    }
+
+    #[test]
+    fn live_fact_is_not_revoked() {
+        let (reg, live, _) = registry();
+        let c = RevocationCircuit { h: live, en: Fp::ONE, path: reg.path(&live), expected: Fp::ONE };
+        assert!(verify(&c, reg.root()));
+        assert!(!verify(&RevocationCircuit { expected: Fp::ZERO, ..c.clone() }, reg.root()));
+        // the root of another list
+        assert!(!verify(&c, RevocationRegistry::new(&[live]).root()));
+    }
+
+    #[test]
+    fn revoked_fact_is_not_ok() {
+        let (reg, _, revoked) = registry();
+        let c = RevocationCircuit { h: revoked, en: Fp::ONE, path: reg.path(&revoked), expected: Fp::ZERO };
+        assert!(verify(&c, reg.root()));
+        assert!(!verify(&RevocationCircuit { expected: Fp::ONE, ..c }, reg.root()));
+    }
+
+    #[test]
+    fn path_of_another_slot_fails() {
+        let (reg, live, revoked) = registry();
+        // an empty slot that is not the slot of the revoked hash
+        let other = RevocationCircuit { h: revoked, en: Fp::ONE, path: reg.path(&live), expected: Fp::ONE };
+        assert!(!verify(&other, reg.root()));
+        assert!(!verify(&RevocationCircuit { expected: Fp::ZERO, ..other.clone() }, reg.root()));
+        // nothing is enforced without en
+        assert!(verify(&RevocationCircuit { en: Fp::ZERO, expected: Fp::ZERO, ..other }, reg.root()));
+    }
+}
@@ -0,0 +1,95 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use halo2curves::ff::Field;
+
+use crate::chips::fact_check::compare_chip::{CompareChip, CompareConfig};
+
+/// Validity period of a fact against the public verification time (`now`, row 0 of the
+/// instance column): from <= now <= until on signed 64-bit integers, as two range-checked
+/// `CompareChip::greater_than`s enforced with `en`.
+///
+/// Combination (`q_valid`, one `work` column):
+///
+/// | en | from > now | now > until | ok |
+///
+/// ok = en * (1 - (from > now)) * (1 - (now > until)), so ok = en*[from <= now <= until].
+#[derive(Clone, Debug)]
+pub struct ValidityConfig {
+    pub verification_time: Column<Instance>,
+    pub cmp_cfg: CompareConfig,
+    pub work: Column<Advice>,
+    pub q_valid: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct ValidityChip {
+    cfg: ValidityConfig,
+}
+
+impl Chip<Fp> for ValidityChip {
+    type Config = ValidityConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl ValidityChip {
+    pub fn construct(cfg: ValidityConfig) -> Self { Self { cfg } }
+
+    /// `verification_time`: instance column of the verification time in unix seconds (row 0)
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        verification_time: Column<Instance>,
+        cmp_cfg: CompareConfig,
+    ) -> ValidityConfig {
+        let work = meta.advice_column();
+        meta.enable_equality(work);
+        meta.enable_equality(verification_time);
+
+        let q_valid = meta.selector();
+        meta.create_gate("valid at the verification time", |meta| {
+            let q = meta.query_selector(q_valid);
+            let [en, early, late, ok] = [0, 1, 2, 3].map(|i| meta.query_advice(work, Rotation(i)));
+            let one = Expression::Constant(Fp::ONE);
+            vec![q * (ok - en * (one.clone() - early) * (one - late))]
+        });
+
+        ValidityConfig { verification_time, cmp_cfg, work, q_valid }
+    }
+
+    /// `en * [from <= now <= until]`
+    pub fn check_valid(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        from: &AssignedCell<Fp, Fp>,
+        until: &AssignedCell<Fp, Fp>,
+        en: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let cfg = &self.cfg;
+        let cmp_chip = CompareChip::construct(cfg.cmp_cfg.clone());
+
+        let now = layouter.assign_region(
+            || "verification time",
+            |mut region| region.assign_advice_from_instance(|| "now", cfg.verification_time, 0, cfg.work, 0),
+        )?;
+        let early = cmp_chip.greater_than(layouter.namespace(|| "from > now"), from, &now, en)?;
+        let late = cmp_chip.greater_than(layouter.namespace(|| "now > until"), &now, until, en)?;
+
+        layouter.assign_region(
+            || "validity ok",
+            |mut region| {
+                cfg.q_valid.enable(&mut region, 0)?;
+                let en = en.copy_advice(|| "en", &mut region, cfg.work, 0)?;
+                let early = early.copy_advice(|| "from > now", &mut region, cfg.work, 1)?;
+                let late = late.copy_advice(|| "now > until", &mut region, cfg.work, 2)?;
+                let ok = en.value().zip(early.value()).zip(late.value())
+                    .map(|((en, e), l)| *en * (Fp::ONE - *e) * (Fp::ONE - *l));
+                region.assign_advice(|| "ok", cfg.work, 3, || ok)
+            },
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use halo2_proofs::{
     circuit::{ Layouter, AssignedCell},
     pasta::Fp,
-    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed},
 };
 
 use crate::chips::{
@@ -9,7 +9,7 @@ use crate::chips::{
     finding_rule::sig_rlc_chip::SigRlcChip,
     finding_rule::sig_or_compare_chip::SigOrCompareChip,
 };
-use crate::utils_2::common_helpers::MAX_CANDIDATES;
+use crate::circuit_shape::CircuitShape;
 
 /// CONFIG
 #[derive(Clone, Debug)]
@@ -18,7 +18,6 @@ pub struct SigCheckConfig {
     pub sig_arity: Column<Advice>,
     pub flag: Column<Advice>,
     pub q_bool: Column<Fixed>,
-    pub q_sum: Selector,
     pub rlc_cfg: RlcFixedConfig,
 }
 
@@ -33,7 +32,6 @@ impl SigCheckChip {
         let sig_arity = meta.advice_column();
         let flag      = meta.advice_column();
         let q_bool    = meta.fixed_column();
-        let q_sum     = meta.selector();
 
         // Equality permisson
         meta.enable_equality(sig_name);
@@ -47,20 +45,10 @@ impl SigCheckChip {
             vec![ q * b.clone() * (b - halo2_proofs::plonk::Expression::Constant(Fp::one())) ]
         });
 
-        // Sum flags
-        meta.create_gate("sum flags == 1", |meta| {
-            let q  = meta.query_selector(q_sum);
-            let mut sum_expr = meta.query_advice(flag, halo2_proofs::poly::Rotation::cur());
-            for i in 1..MAX_CANDIDATES {
-                sum_expr = sum_expr + meta.query_advice(flag, halo2_proofs::poly::Rotation(i as i32));
-            }
-            vec![ q * (sum_expr - halo2_proofs::plonk::Expression::Constant(Fp::one())) ]
-        });
-
 
         let rlc_cfg = RlcFixedChip::configure(meta, alpha);
 
-        SigCheckConfig { sig_name, sig_arity, flag, q_bool, q_sum, rlc_cfg }
+        SigCheckConfig { sig_name, sig_arity, flag, q_bool, rlc_cfg }
     }
 
     pub fn construct(cfg: SigCheckConfig) -> Self {
@@ -73,6 +61,7 @@ impl SigCheckChip {
         proof_pairs: &[Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>],
         candidate_pairs_all: &[Vec<Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>>],
         is_fact: &AssignedCell<Fp, Fp>,
+        shape: &CircuitShape,
     ) -> Result<Vec<AssignedCell<Fp, Fp>>, Error> { 
         let sig_rlc_chip = SigRlcChip::construct(self.cfg.rlc_cfg.clone());
         let or_chip = SigOrCompareChip::construct(self.cfg.clone(), sig_rlc_chip);
@@ -82,6 +71,7 @@ impl SigCheckChip {
             proof_pairs,
             candidate_pairs_all,
             is_fact,
+            shape.max_sig_tokens(),
         )?;
 
         Ok(b_flags)
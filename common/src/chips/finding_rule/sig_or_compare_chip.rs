@@ -25,6 +25,7 @@ impl SigOrCompareChip {
     // NESTED candidates: Vec<candidate>[ Vec<row>[ (name,arity), ... ] ]
     candidate_pairs_nested: &[Vec<Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>>],
     is_fact: &AssignedCell<Fp, Fp>,
+    max_sig_tokens: usize,
 ) -> Result<Vec<AssignedCell<Fp, Fp>>, Error> {
     let cfg = &self.cfg;
     //println!("LIST: \n {:?}", proof_pairs_nested);
@@ -49,6 +50,7 @@ impl SigOrCompareChip {
     let proof_rlc = self.sig_rlc_chip.fold_sig_list(
         layouter.namespace(|| "sig RLC(proof)"),
         &proof_flat,
+        max_sig_tokens,
     )?;
     //println!("proof val: {:?}", proof_rlc.value());
     // candidate RLC-k és match flag-ek (b_i)
@@ -58,6 +60,7 @@ impl SigOrCompareChip {
         let cand_rlc = self.sig_rlc_chip.fold_sig_list(
             layouter.namespace(|| format!("sig RLC(cand {i})")),
             cand,
+            max_sig_tokens,
         )?;
         //println!("cand val: {:?}", cand_rlc.value());
         // Boolean match flag = 1 if proof_rlc == cand_rlc else 0
@@ -1,6 +1,5 @@
 use halo2_proofs::{circuit::{AssignedCell, Layouter}, pasta::Fp, plonk::Error};
 use crate::chips::rlc_chip::{RlcFixedChip, RlcFixedConfig};
-use crate::utils_2::common_helpers::MAX_SIG_TOKENS;
 
 #[derive(Clone, Debug)]
 pub struct SigRlcChip {
@@ -14,6 +13,7 @@ impl SigRlcChip {
         &self,
         mut layouter: impl Layouter<Fp>,
         pairs: &[(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)],
+        max_sig_tokens: usize, // padding, == shape.max_sig_tokens()
     ) -> Result<AssignedCell<Fp, Fp>, Error> {
         use halo2_proofs::circuit::Value;
         let rlc = RlcFixedChip::construct(self.cfg.clone());
@@ -40,7 +40,7 @@ impl SigRlcChip {
                     toks.push(a_tok);
                 }
 
-                while toks.len() < MAX_SIG_TOKENS {
+                while toks.len() < max_sig_tokens {
                     let pad = region.assign_advice(|| "pad", self.cfg.token, row, || Value::known(Fp::zero()))?;
                     toks.push(pad);
                     row += 1;
@@ -0,0 +1,77 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Instance},
+};
+
+use crate::chips::fact_check::poseidon_hash::{PoseidonHashChip, PoseidonHashConfig};
+
+/// Salted Poseidon commitment to a proof node's goal, exposed in `public_goal_commitments`
+/// (row 0: head goal, row 1 + i: i-th child) so the per-node proofs chain into one tree.
+///
+/// commitment = Poseidon fold of [salt, name_0, args_0.., name_1, args_1.., ...]
+///
+/// A goal is encoded the same way as the head of its own proof and as a child of its parent's
+/// (`term_encoding::encode_args`), so the names and args are hashed as they are.
+#[derive(Clone, Debug)]
+pub struct GoalCommitConfig {
+    pub v: Column<Advice>,
+    pub pos_cfg: PoseidonHashConfig,
+    pub public_goal_commitments: Column<Instance>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GoalCommitChip {
+    config: GoalCommitConfig,
+}
+
+impl Chip<Fp> for GoalCommitChip {
+    type Config = GoalCommitConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.config }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl GoalCommitChip {
+    pub fn construct(config: GoalCommitConfig) -> Self { Self { config } }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, public_goal_commitments: Column<Instance>) -> GoalCommitConfig {
+        let v = meta.advice_column();
+        meta.enable_equality(v);
+        meta.enable_equality(public_goal_commitments);
+
+        let pos_cfg = PoseidonHashChip::configure(meta);
+
+        GoalCommitConfig { v, pos_cfg, public_goal_commitments }
+    }
+
+    /// Commits to one node, `names[p]` is the p-th predicate of the node and `args[p]` its
+    /// flattened `shape.per_term()` args, the result is bound to instance `row`.
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        salt: Value<Fp>,
+        names: &[AssignedCell<Fp, Fp>],
+        args: &[&[AssignedCell<Fp, Fp>]],
+        row: usize,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let cfg = &self.config;
+
+        let salt = layouter.assign_region(
+            || format!("goal commitment {row} salt"),
+            |mut region| region.assign_advice(|| "salt", cfg.v, 0, || salt),
+        )?;
+
+        let mut tokens = vec![salt];
+        for (name, term_args) in names.iter().zip(args.iter()) {
+            tokens.push(name.clone());
+            tokens.extend(term_args.iter().cloned());
+        }
+
+        let pos_chip = PoseidonHashChip::construct(cfg.pos_cfg.clone());
+        let commitment = pos_chip.hash_list(layouter.namespace(|| format!("Poseidon(goal {row})")), &tokens)?;
+        layouter.constrain_instance(commitment.cell(), cfg.public_goal_commitments, row)?;
+
+        Ok(commitment)
+    }
+}
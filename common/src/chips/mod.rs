@@ -1,8 +1,10 @@
 pub mod rlc_chip;
+pub mod range_check_chip;
 
 
 pub mod fact_check;
 pub mod value_check;
 pub mod finding_rule;
 pub mod acc;
-pub mod rules_check_chip;
\ No newline at end of file
+pub mod rules_check_chip;
+pub mod goal_commit_chip;
\ No newline at end of file
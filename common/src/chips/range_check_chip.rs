@@ -0,0 +1,193 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, TableColumn},
+    poly::Rotation,
+};
+use halo2curves::ff::{Field, PrimeField};
+
+/// Lookup-table based range check.
+///
+/// A value `v` is decomposed into `num_bits / limb_bits` limbs with a running sum
+/// z_0 = v, z_{i+1} = (z_i - limb_i) / 2^limb_bits, every limb_i = z_i - z_{i+1} * 2^limb_bits
+/// is looked up in a `[0, 2^limb_bits)` table and the last z_n is constrained to 0.
+#[derive(Clone, Debug)]
+pub struct RangeCheckConfig {
+    pub z: Column<Advice>,
+    pub q_lookup: Selector,
+    pub table: TableColumn,
+    pub limb_bits: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct RangeCheckChip {
+    cfg: RangeCheckConfig,
+}
+
+impl Chip<Fp> for RangeCheckChip {
+    type Config = RangeCheckConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl RangeCheckChip {
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, limb_bits: usize) -> RangeCheckConfig {
+        assert!(limb_bits > 0 && limb_bits < 64, "limb_bits must be in 1..64");
+
+        let z = meta.advice_column();
+        let q_lookup = meta.complex_selector();
+        let table = meta.lookup_table_column();
+        meta.enable_equality(z);
+
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+
+        // limb_i = z_i - z_{i+1} * 2^B  ∈ [0, 2^B)
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let shift = Expression::Constant(Fp::from(1u64 << limb_bits));
+            vec![(q * (z_cur - z_next * shift), table)]
+        });
+
+        RangeCheckConfig { z, q_lookup, table, limb_bits }
+    }
+
+    pub fn construct(cfg: RangeCheckConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// Fills the `[0, 2^limb_bits)` table. Must be called exactly once per synthesis.
+    pub fn load_table(&self, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let cfg = &self.cfg;
+        layouter.assign_table(
+            || "range check table",
+            |mut table| {
+                for i in 0..(1usize << cfg.limb_bits) {
+                    table.assign_cell(
+                        || format!("limb {i}"),
+                        cfg.table,
+                        i,
+                        || Value::known(Fp::from(i as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Enforces `0 <= v < 2^num_bits`. `num_bits` must be a multiple of `limb_bits`.
+    pub fn range_check(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        v: &AssignedCell<Fp, Fp>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        let cfg = &self.cfg;
+        assert!(num_bits.is_multiple_of(cfg.limb_bits), "num_bits must be a multiple of limb_bits");
+        let num_limbs = num_bits / cfg.limb_bits;
+
+        let shift_inv = Fp::from(1u64 << cfg.limb_bits).invert().unwrap();
+
+        layouter.assign_region(
+            || format!("range check {num_bits} bits"),
+            |mut region| {
+                let mut z = v.copy_advice(|| "z_0", &mut region, cfg.z, 0)?;
+
+                for i in 0..num_limbs {
+                    cfg.q_lookup.enable(&mut region, i)?;
+
+                    let limb = v.value().map(|x| Fp::from(limb_of(x, i, cfg.limb_bits)));
+                    let z_next = z
+                        .value()
+                        .zip(limb)
+                        .map(|(zv, l)| (*zv - l) * shift_inv);
+
+                    z = region.assign_advice(|| format!("z_{}", i + 1), cfg.z, i + 1, || z_next)?;
+                }
+
+                // Everything has been consumed by the limbs → v < 2^num_bits
+                region.constrain_constant(z.cell(), Fp::ZERO)
+            },
+        )
+    }
+}
+
+// i-th `limb_bits` wide limb of the canonical little-endian representation
+fn limb_of(x: &Fp, i: usize, limb_bits: usize) -> u64 {
+    let repr = x.to_repr();
+    let bytes = repr.as_ref();
+    let mut out = 0u64;
+    for b in 0..limb_bits {
+        let bit = i * limb_bits + b;
+        if bit >= 256 {
+            break;
+        }
+        if (bytes[bit / 8] >> (bit % 8)) & 1 == 1 {
+            out |= 1 << b;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+
+    use super::*;
+
+    const K: u32 = 9;
+    const LIMB_BITS: usize = 8;
+
+    #[derive(Clone, Default)]
+    struct RangeCircuit {
+        v: Fp,
+        num_bits: usize,
+    }
+
+    impl Circuit<Fp> for RangeCircuit {
+        type Config = (RangeCheckConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (RangeCheckChip::configure(meta, LIMB_BITS), input)
+        }
+
+        fn synthesize(&self, (cfg, input): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(cfg);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let v = layouter.assign_region(
+                || "v",
+                |mut region| region.assign_advice(|| "v", input, 0, || Value::known(self.v)),
+            )?;
+            chip.range_check(layouter.namespace(|| "range"), &v, self.num_bits)
+        }
+    }
+
+    fn in_range(v: Fp, num_bits: usize) -> bool {
+        MockProver::run(K, &RangeCircuit { v, num_bits }, vec![]).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn values_below_the_bound_pass() {
+        for v in [0u64, 1, 255, 256, 0xffff] {
+            assert!(in_range(Fp::from(v), 16), "{v}");
+        }
+        assert!(in_range(Fp::from(u64::MAX), 64));
+    }
+
+    #[test]
+    fn out_of_range_values_fail() {
+        assert!(!in_range(Fp::from(1 << 16), 16));
+        assert!(!in_range(Fp::from(256), 8));
+        assert!(!in_range(Fp::from(u64::MAX) + Fp::ONE, 64));
+        // a negative number is p - |n|, far above any bound
+        assert!(!in_range(-Fp::ONE, 64));
+    }
+}
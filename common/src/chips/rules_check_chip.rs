@@ -1,11 +1,10 @@
 use halo2_proofs::{
-    circuit::{AssignedCell, Chip, Layouter, Value},
+    circuit::{AssignedCell, Chip, Layouter},
     pasta::Fp,
     plonk::{Advice, Column, ConstraintSystem, Error, Instance},
 };
-use halo2curves::ff::Field;
 
-use crate::{chips::fact_check::poseidon_hash::{PoseidonHashChip, PoseidonHashConfig}, utils_2::common_helpers::MAX_RULE_COMPONENTS};
+use crate::chips::fact_check::poseidon_hash::{PoseidonHashChip, PoseidonHashConfig};
 
 #[derive(Clone, Debug)]
 pub struct RulesConfig {
@@ -48,50 +47,10 @@ impl RulesChip {
         let pos_chip = PoseidonHashChip::construct(cfg.pos_cfg.clone());
 
 
-        let hashed = pos_chip.hash_list(layouter.namespace(|| "Poseidon(rules_flatten)"), &flatten_rules)?;
+        let hashed = pos_chip.hash_list(layouter.namespace(|| "Poseidon(rules_flatten)"), flatten_rules)?;
 
-        // Membership check (gated by is_fact)
-        layouter.assign_region(
-            || "membership check (is_fact-gated)",
-            |mut region| {
-                // zero cell for equality constraints
-                let zero = region.assign_advice(
-                    || "zero",
-                    cfg.rules,
-                    MAX_RULE_COMPONENTS+1,
-                    || Value::known(Fp::ZERO),
-                )?;
-
-                let pub_rules_local = region.assign_advice_from_instance(
-                    || format!("pub_hash]"),
-                    cfg.public_rules_hash,
-                    0,
-                    cfg.rules,
-                    MAX_RULE_COMPONENTS+2,
-                )?;
-
-                let hashed_local = region.assign_advice(
-                    || "hashed local",
-                    cfg.rules,
-                    MAX_RULE_COMPONENTS+3,
-                    || hashed.value().copied(),
-                )?;
-
-                // Equals?
-                let equal = region.assign_advice(
-                    || format!("check"),
-                    cfg.rules,
-                    MAX_RULE_COMPONENTS+4,
-                    || hashed_local.value()
-                            .zip(pub_rules_local.value())
-                            .map(|(h, p)| {
-                                if *h == *p { Fp::ZERO } else { Fp::ONE }
-                    }),
-                )?;
-                region.constrain_equal(equal.cell(), zero.cell())?;
-                Ok(())
-            },
-        )?;
+        // Poseidon(rules_flatten) == public rules hash
+        layouter.constrain_instance(hashed.cell(), cfg.public_rules_hash, 0)?;
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,131 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use halo2curves::ff::Field;
+
+/// „Létezik i: ok_i = 1 és b_i = 1”, ha a goal nem fact; ok_i a RuleRowsChip-ből jön
+/// (a klóz minden egyenlősége teljesül), b_i a SigCheck-ből.
+///
+/// Klózonként egy sor, q_step: prod_{i+1} = prod_i * (1 - ok_i*b_i), prod_0 = 1.
+/// Az utolsó sorban q_final: final = (1 - fact) * prod_n, és final == 0.
+#[derive(Clone, Debug)]
+pub struct ClauseExistsConfig {
+    pub adv_ok:   Column<Advice>,
+    pub adv_b:    Column<Advice>,
+    pub adv_fact: Column<Advice>,
+
+    pub adv_prod: Column<Advice>,
+    pub adv_final: Column<Advice>,    // (1 - fact) * prod  MUST be 0
+
+    pub q_step:  Selector,
+    pub q_final: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClauseExistsChip {
+    pub cfg: ClauseExistsConfig,
+}
+
+impl Chip<Fp> for ClauseExistsChip {
+    type Config = ClauseExistsConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
+impl ClauseExistsChip {
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> ClauseExistsConfig {
+        let adv_ok    = meta.advice_column();
+        let adv_b     = meta.advice_column();
+        let adv_fact  = meta.advice_column();
+
+        let adv_prod  = meta.advice_column();
+        let adv_final = meta.advice_column();
+
+        for col in [adv_ok, adv_b, adv_fact, adv_prod, adv_final] {
+            meta.enable_equality(col);
+        }
+
+        let q_step = meta.selector();
+        let q_final = meta.selector();
+
+        meta.create_gate("product step", |meta| {
+            let q = meta.query_selector(q_step);
+            let ok = meta.query_advice(adv_ok, Rotation::cur());
+            let b = meta.query_advice(adv_b, Rotation::cur());
+            let prod = meta.query_advice(adv_prod, Rotation::cur());
+            let prod_next = meta.query_advice(adv_prod, Rotation::next());
+            vec![q * (prod_next - prod * (Expression::Constant(Fp::ONE) - ok * b))]
+        });
+
+        meta.create_gate("final check", |meta| {
+            let q = meta.query_selector(q_final);
+            let fact = meta.query_advice(adv_fact, Rotation::cur());
+            let prod = meta.query_advice(adv_prod, Rotation::cur());
+            let fin = meta.query_advice(adv_final, Rotation::cur());
+            vec![q * (fin - (Expression::Constant(Fp::ONE) - fact) * prod)]
+        });
+
+        ClauseExistsConfig {
+            adv_ok, adv_b, adv_fact, adv_prod, adv_final,
+            q_step, q_final,
+        }
+    }
+
+    pub fn construct(cfg: ClauseExistsConfig) -> Self { Self { cfg } }
+
+    pub fn assign_exists_clause(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        ok_cells: &[AssignedCell<Fp,Fp>],
+        b_flags: &[AssignedCell<Fp,Fp>],
+        fact_cell: &AssignedCell<Fp,Fp>,
+    ) -> Result<(), Error> {
+
+        let cfg = self.cfg.clone();
+
+        layouter.assign_region(
+            || "exists clause (ok*b) with final enforcement",
+            |mut region| {
+                // 1) a szorzat lánc
+                let mut prod = region.assign_advice_from_constant(|| "prod_0", cfg.adv_prod, 0, Fp::ONE)?;
+
+                for (i, ok) in ok_cells.iter().enumerate() {
+                    cfg.q_step.enable(&mut region, i)?;
+
+                    let ok_local = ok.copy_advice(|| format!("ok[{i}]"), &mut region, cfg.adv_ok, i)?;
+                    let b_local = b_flags[i].copy_advice(|| format!("b[{i}]"), &mut region, cfg.adv_b, i)?;
+
+                    // prod *= (1 - ok*b)
+                    let prod_val = prod.value().zip(ok_local.value()).zip(b_local.value())
+                        .map(|((p, ok), b)| *p * (Fp::one() - *ok * *b));
+                    prod = region.assign_advice(|| format!("prod_{}", i + 1), cfg.adv_prod, i + 1, || prod_val)?;
+                }
+
+                // 2) final_check = (1 - fact) * prod
+                let row = ok_cells.len();
+                cfg.q_final.enable(&mut region, row)?;
+                let fact_local = fact_cell.copy_advice(|| "fact", &mut region, cfg.adv_fact, row)?;
+
+                let final_check_val = prod.value().zip(fact_local.value())
+                    .map(|(p,f)| (Fp::one() - *f) * *p);
+
+                let final_cell = region.assign_advice(
+                    || "final_check",
+                    cfg.adv_final,
+                    row,
+                    || final_check_val
+                )?;
+                // ENFORCEMENT HERE:
+                region.constrain_constant(final_cell.cell(), Fp::zero())?;
+
+                Ok(())
+            }
+        )?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,2 @@
-pub mod rows_compress_config;
 pub mod rule_rows_chip;
-pub mod dot_chip;
\ No newline at end of file
+pub mod clause_exists_chip;
\ No newline at end of file
@@ -1,15 +1,40 @@
 use halo2_proofs::{
-    circuit::{Layouter, AssignedCell, Value},
-    plonk::{Advice, Column, ConstraintSystem, Error},
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
     pasta::Fp,
+    poly::Rotation,
 };
-use halo2curves::ff::PrimeField;
+use halo2curves::ff::{Field, PrimeField};
 
-use crate::{unification_checker_circuit::{MAX_NODES, PER_NODE, PER_TERM}, utils_2::common_helpers::{MAX_ARITY, MAX_PRED_LIST}};
+use crate::circuit_shape::CircuitShape;
 
+/// Egy klóz-egyenlőség egyik oldala: a w egy slotja (node, predikátum, arg, lista index),
+/// vagy konstans (a szabályban szereplő érték).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EqSide {
+    Slot(Fp, Fp, Fp, Fp),
+    Const(Fp),
+}
+
+/// A klózok egyenlőségei soronként külön ellenőrizve, egy sor egy egyenlőség:
+///
+/// | left | right | inv | ok |
+///
+/// q_eq: (left-right) * (1 - (left-right)*inv) = 0, ok_next = ok * (1 - (left-right)*inv), ok_0 = 1,
+/// így a klóz utolsó ok-ja = [minden egyenlősége teljesül]. (A ±1 sorok összege nem elég, két
+/// rossz egyenlőség kiolthatja egymást.)
+///
+/// left / right a w cellák másolata vagy konstans: a permutáció és a konstansok keygen-kor
+/// rögzülnek, így a verifying key maga köti a policy-t, más klóz → más vk.
 #[derive(Clone, Debug)]
 pub struct RuleRowsConfig {
-    pub val: Column<Advice>,
+    /// a w vektor (az összes node argumentumai + a homogén 1)
+    pub w: Column<Advice>,
+    pub left: Column<Advice>,
+    pub right: Column<Advice>,
+    pub inv: Column<Advice>,
+    pub ok: Column<Advice>,
+    pub q_eq: Selector,
 }
 
 #[derive(Clone, Debug)]
@@ -17,78 +42,142 @@ pub struct RuleRowsChip {
     pub cfg: RuleRowsConfig,
 }
 
+impl Chip<Fp> for RuleRowsChip {
+    type Config = RuleRowsConfig;
+    type Loaded = ();
+    fn config(&self) -> &Self::Config { &self.cfg }
+    fn loaded(&self) -> &Self::Loaded { &() }
+}
+
 impl RuleRowsChip {
     pub fn configure(meta: &mut ConstraintSystem<Fp>) -> RuleRowsConfig {
-        let val = meta.advice_column();
-        meta.enable_equality(val);
-        RuleRowsConfig { val }
+        let w = meta.advice_column();
+        let left = meta.advice_column();
+        let right = meta.advice_column();
+        let inv = meta.advice_column();
+        let ok = meta.advice_column();
+        for col in [w, left, right, ok] {
+            meta.enable_equality(col);
+        }
+        // ok_0, az 1 a w végén és az értékek konstansai
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let q_eq = meta.selector();
+
+        meta.create_gate("clause equality row", |meta| {
+            let q = meta.query_selector(q_eq);
+            let d = meta.query_advice(left, Rotation::cur()) - meta.query_advice(right, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let ok_cur = meta.query_advice(ok, Rotation::cur());
+            let ok_next = meta.query_advice(ok, Rotation::next());
+            // [left == right]
+            let z = Expression::Constant(Fp::ONE) - d.clone() * inv;
+
+            vec![
+                q.clone() * d * z.clone(),
+                q * (ok_next - ok_cur * z),
+            ]
+        });
+
+        RuleRowsConfig { w, left, right, inv, ok, q_eq }
     }
 
     pub fn construct(cfg: RuleRowsConfig) -> Self { Self { cfg } }
 
-     pub fn assign_rule_rows_fp_4d(
+    /// A w vektor cellái, az utolsó (homogén) elem 1-hez kötve.
+    pub fn assign_w(
         &self,
         mut layouter: impl Layouter<Fp>,
-        clause_eqs_4d: &[(Fp,Fp,Fp,Fp,Fp,Fp,Fp,Fp)], // (n,p,a,l, n',p',a',l')
-        max_dim: usize, // == MAX_DOT_DIM
-    ) -> Result<Vec<Vec<AssignedCell<Fp,Fp>>>, Error> {
-        let cfg = self.cfg.clone();
+        w: &[Fp],
+    ) -> Result<Vec<AssignedCell<Fp, Fp>>, Error> {
+        let cfg = &self.cfg;
+        layouter.assign_region(
+            || "bind w vector",
+            |mut region| {
+                let out = w
+                    .iter()
+                    .enumerate()
+                    .map(|(i, val)| region.assign_advice(|| format!("w[{i}]"), cfg.w, i, || Value::known(*val)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                if let Some(one) = out.last() {
+                    region.constrain_constant(one.cell(), Fp::ONE)?;
+                }
+                Ok(out)
+            },
+        )
+    }
 
-        let clause_eqs_global: Vec<Option<(usize,usize)>> = clause_eqs_4d.iter().map(|t| {
-            let (ln, lp, la, ll, rn, rp, ra, rl) = t;
+    /// `[w teljesíti a klóz minden egyenlőségét]`, egy klóz egyenlőségei: (left, right) párok.
+    pub fn assign_clause_ok(
+        &self,
+        layouter: impl Layouter<Fp>,
+        clause_eqs: &[(EqSide, EqSide)],
+        w_cells: &[AssignedCell<Fp, Fp>],
+        shape: &CircuitShape,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let eqs = clause_eqs
+            .iter()
+            .map(|(l, r)| Ok((resolve_side(shape, l)?, resolve_side(shape, r)?)))
+            .filter(|eq| !matches!(eq, Ok((Side::W(l), Side::W(r))) if l == r)) // (0,0)==(0,0) padding equality → nincs sor
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.assign_eq_rows(layouter, &eqs, w_cells, None)
+    }
 
-            // Fp -> usize
-            let ln = fp_to_usize(ln);
-            let lp = fp_to_usize(lp);
-            let la = fp_to_usize(la);
-            let ll = fp_to_usize(ll);
+    // `z` felülírja a [left == right] witnesst minden sorban, a kapuk döntik el, jó-e
+    fn assign_eq_rows(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        eqs: &[(Side, Side)],
+        w_cells: &[AssignedCell<Fp, Fp>],
+        z: Option<Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let cfg = &self.cfg;
 
-            let rn = fp_to_usize(rn);
-            let rp = fp_to_usize(rp);
-            let ra = fp_to_usize(ra);
-            let rl = fp_to_usize(rl);
+        layouter.assign_region(
+            || "clause equality rows",
+            |mut region| {
+                let mut ok = region.assign_advice_from_constant(|| "ok_0", cfg.ok, 0, Fp::ONE)?;
 
-            let l_idx = linear_idx_4d(ln, lp, la, ll)?;
-            let r_idx = linear_idx_4d(rn, rp, ra, rl)?;
+                for (row, (l, r)) in eqs.iter().enumerate() {
+                    cfg.q_eq.enable(&mut region, row)?;
 
-            if l_idx >= max_dim || r_idx >= max_dim { return None; }
-            if l_idx == r_idx { return None; } // (0,0)==(0,0) padding equality → nincs sor
+                    let mut side = |s: &Side, col: Column<Advice>| match s {
+                        Side::W(i) => w_cells[*i].copy_advice(|| "w slot", &mut region, col, row),
+                        Side::Const(v) => region.assign_advice_from_constant(|| "constant", col, row, *v),
+                    };
+                    let left = side(l, cfg.left)?;
+                    let right = side(r, cfg.right)?;
 
-            Some((l_idx, r_idx))
-        }).collect();
+                    let d = left.value().zip(right.value()).map(|(l, r)| *l - *r);
+                    region.assign_advice(|| "inv", cfg.inv, row, || d.map(|d| d.invert().unwrap_or(Fp::ZERO)))?;
 
-        let rows = layouter.assign_region(
-            || "assign clause equality rows (4D)",
-            |mut region| {
-                let mut all_rows = Vec::with_capacity(clause_eqs_global.len());
-                let mut row_offset = 0;
-
-                for (row_idx, maybe_pair) in clause_eqs_global.iter().enumerate() {
-                    let mut assigned_row = Vec::with_capacity(max_dim);
-
-                    for k in 0..max_dim {
-                        let val = match maybe_pair {
-                            Some((l_idx, _)) if k == *l_idx => Value::known(Fp::one()),
-                            Some((_, r_idx)) if k == *r_idx => Value::known(-Fp::one()),
-                            _ => Value::known(Fp::zero()),
-                        };
-                        let cell = region.assign_advice(
-                            || format!("row[{row_idx}][{k}]"),
-                            cfg.val,
-                            row_offset,
-                            || val,
-                        )?;
-                        assigned_row.push(cell);
-                        row_offset += 1;
-                    }
-
-                    all_rows.push(assigned_row);
+                    let z_val = d.map(|d| z.unwrap_or(if bool::from(d.is_zero()) { Fp::ONE } else { Fp::ZERO }));
+                    let ok_val = ok.value().zip(z_val).map(|(ok, z)| *ok * z);
+                    ok = region.assign_advice(|| format!("ok_{}", row + 1), cfg.ok, row + 1, || ok_val)?;
                 }
-                
-                Ok(all_rows)
+
+                Ok(ok)
             },
-        )?;
-        Ok(rows)
+        )
+    }
+}
+
+// Egy oldal a w indexével vagy konstansként
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    W(usize),
+    Const(Fp),
+}
+
+// A shape-en kívüli slot hiba: eldobva az egyenlőség ellenőrizetlen maradna
+fn resolve_side(shape: &CircuitShape, side: &EqSide) -> Result<Side, Error> {
+    match side {
+        EqSide::Slot(n, p, a, l) => {
+            linear_idx_4d(shape, fp_to_usize(n), fp_to_usize(p), fp_to_usize(a), fp_to_usize(l))
+                .map(Side::W)
+                .ok_or(Error::Synthesis)
+        }
+        EqSide::Const(v) => Ok(Side::Const(*v)),
     }
 }
 
@@ -101,20 +190,128 @@ fn fp_to_usize(x: &Fp) -> usize {
 }
 #[inline]
 fn linear_idx_4d(
+    shape: &CircuitShape,
     node_idx: usize,
     pred_idx: usize,
     arg_idx: usize,
     list_idx: usize,
 ) -> Option<usize> {
-    if node_idx >= MAX_NODES { return None; }
-    if pred_idx >= MAX_PRED_LIST { return None; }
-    if arg_idx >= MAX_ARITY { return None; }
-    if list_idx >= MAX_PRED_LIST { return None; }
+    if node_idx >= shape.max_nodes() { return None; }
+    if pred_idx >= shape.max_pred_list { return None; }
+    if arg_idx >= shape.max_arity { return None; }
+    if list_idx >= shape.max_pred_list { return None; }
 
     Some(
-        node_idx * PER_NODE
-        + pred_idx * PER_TERM
-        + arg_idx * MAX_PRED_LIST
+        node_idx * shape.per_node()
+        + pred_idx * shape.per_term()
+        + arg_idx * shape.max_pred_list
         + list_idx
     )
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+
+    use super::*;
+
+    const K: u32 = 6;
+
+    // one term of two args per node, a goal and one child: w = [goal a0, goal a1, child a0, child a1, 1]
+    const SHAPE: CircuitShape = CircuitShape {
+        k: K,
+        max_pred_list: 1,
+        max_clauses_per_predicate: 1,
+        max_predicates_overall: 2,
+        max_arity: 2,
+        max_children: 1,
+        fact_tree_depth: 1,
+    };
+
+    // `z` overrides the equality witness of the chip, `expected` is the ok it must give
+    #[derive(Clone, Default)]
+    struct ClauseCircuit {
+        w: Vec<u64>,
+        eqs: Vec<(EqSide, EqSide)>,
+        z: Option<Fp>,
+        expected: bool,
+    }
+
+    impl Circuit<Fp> for ClauseCircuit {
+        type Config = RuleRowsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            RuleRowsChip::configure(meta)
+        }
+
+        fn synthesize(&self, cfg: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = RuleRowsChip::construct(cfg);
+            let w: Vec<Fp> = self.w.iter().map(|v| Fp::from(*v)).collect();
+            let w_cells = chip.assign_w(layouter.namespace(|| "w"), &w)?;
+            let eqs = self
+                .eqs
+                .iter()
+                .map(|(l, r)| Ok((resolve_side(&SHAPE, l)?, resolve_side(&SHAPE, r)?)))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let ok = chip.assign_eq_rows(layouter.namespace(|| "clause"), &eqs, &w_cells, self.z)?;
+            layouter.assign_region(|| "expected", |mut region| region.constrain_constant(ok.cell(), Fp::from(self.expected)))
+        }
+    }
+
+    // arg `a` of node `n`
+    fn slot(n: u64, a: u64) -> EqSide {
+        EqSide::Slot(Fp::from(n), Fp::zero(), Fp::from(a), Fp::zero())
+    }
+
+    // the args of the goal are the args of the child
+    fn same_args(w: [u64; 4], expected: bool) -> ClauseCircuit {
+        ClauseCircuit {
+            w: [w.as_slice(), &[1]].concat(),
+            eqs: vec![(slot(0, 0), slot(1, 0)), (slot(0, 1), slot(1, 1))],
+            z: None,
+            expected,
+        }
+    }
+
+    fn verify(c: &ClauseCircuit) -> bool {
+        MockProver::run(K, c, vec![]).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn every_equality_must_hold() {
+        assert!(verify(&same_args([7, 3, 7, 3], true)));
+        assert!(verify(&same_args([7, 3, 7, 4], false)));
+        assert!(!verify(&same_args([7, 3, 7, 4], true)));
+    }
+
+    #[test]
+    fn wrong_equalities_do_not_cancel() {
+        // (1 - 2) + (3 - 2) = 0: a sum of the ±1 rows would accept it
+        assert!(verify(&same_args([1, 3, 2, 2], false)));
+        assert!(!verify(&same_args([1, 3, 2, 2], true)));
+        // nor does a witness that claims both hold
+        assert!(!verify(&ClauseCircuit { z: Some(Fp::one()), ..same_args([1, 3, 2, 2], true) }));
+    }
+
+    #[test]
+    fn value_equalities() {
+        let value = |w0: u64, expected: bool| ClauseCircuit {
+            w: vec![w0, 0, 0, 0, 1],
+            eqs: vec![(slot(0, 0), EqSide::Const(Fp::from(747)))],
+            z: None,
+            expected,
+        };
+        assert!(verify(&value(747, true)));
+        assert!(verify(&value(748, false)));
+        assert!(!verify(&value(748, true)));
+        assert!(!verify(&ClauseCircuit { z: Some(Fp::one()), ..value(748, true) }));
+    }
+
+    #[test]
+    fn homogeneous_one_is_fixed() {
+        assert!(!verify(&ClauseCircuit { w: vec![7, 3, 7, 3, 2], ..same_args([7, 3, 7, 3], true) }));
+    }
+}
@@ -0,0 +1,100 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Read by issue, prove and verify; if it is missing the MNB shape is used.
+pub const CIRCUIT_SHAPE_PATH: &str = "input/circuit_shape.yaml";
+
+/// Dimensions of the unification circuit (they used to be the MAX_* constants of `common_helpers`).
+///
+/// halo2_proofs 0.3 has no `Circuit::Params`, so `configure` is shape independent and the shape
+/// travels in `UnificationCircuit::shape`: it only decides the layout in `synthesize`. The layout
+/// is part of the keys, so the prover and the verifier have to load the same shape.
+///
+/// Loaded from JSON or YAML (by extension), missing fields keep the MNB values:
+///
+/// ```yaml
+/// k: 16
+/// max_pred_list: 1
+/// max_arity: 2
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitShape {
+    /// 2^k rows
+    pub k: u32,
+    /// terms of one node (goal or subtree entry)
+    pub max_pred_list: usize,
+    pub max_clauses_per_predicate: usize,
+    /// also the number of body rows of a clause
+    pub max_predicates_overall: usize,
+    /// args of a term, every arg is a list of `max_pred_list` elements
+    pub max_arity: usize,
+    /// subtree entries of one proof node
+    pub max_children: usize,
+    /// levels of the issuer's fact registry tree, it holds 2^fact_tree_depth facts
+    pub fact_tree_depth: usize,
+}
+
+impl Default for CircuitShape {
+    fn default() -> Self {
+        Self::mnb()
+    }
+}
+
+impl CircuitShape {
+    /// The MNB case study policy.
+    pub const fn mnb() -> Self {
+        Self {
+            // the socialCreds list arg is [list, 3 tuple fields, tail] wide
+            k: 17,
+            max_pred_list: 5,
+            max_clauses_per_predicate: 4,
+            max_predicates_overall: 10,
+            max_arity: 4,
+            max_children: 10,
+            fact_tree_depth: 6,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read circuit shape {}", path.display()))?;
+        let shape = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&text)?,
+            _ => serde_json::from_str(&text)?,
+        };
+        Ok(shape)
+    }
+
+    /// `load`, or the MNB shape if `path` does not exist.
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if path.exists() { Self::load(path) } else { Ok(Self::default()) }
+    }
+
+    /// Flattened args of one term: `max_arity` lists of `max_pred_list` elements.
+    pub fn per_term(&self) -> usize {
+        self.max_arity * self.max_pred_list
+    }
+
+    pub fn per_node(&self) -> usize {
+        self.max_pred_list * self.per_term()
+    }
+
+    /// Goal node + children
+    pub fn max_nodes(&self) -> usize {
+        1 + self.max_children
+    }
+
+    pub fn max_candidates(&self) -> usize {
+        self.max_predicates_overall * self.max_clauses_per_predicate
+    }
+
+    /// Length of a (name, arity) signature list: separator + `max_predicates_overall` rows of pairs.
+    /// Proof padding rows are `max_children` wide, candidate rows `max_pred_list` wide, both lists
+    /// have to fit so they are padded to the same length.
+    pub fn max_sig_tokens(&self) -> usize {
+        1 + 2 * self.max_predicates_overall * self.max_pred_list.max(self.max_children)
+    }
+}
@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
-use halo2_proofs::pasta::Fp;
-use crate::utils_2::common_helpers::{MAX_ARITY, MAX_PRED_LIST, to_fp_value};
+use halo2_proofs::pasta::{pallas, Fp};
+use halo2curves::{ff::PrimeField, group::prime::PrimeCurveAffine};
+use crate::{
+    circuit_shape::CircuitShape,
+    io::fp_decimal::fp_from_dec_str,
+    term::Term,
+    utils_2::{issuer_signature::affine_xy, revocation::REVOCATION_TREE_DEPTH, term_encoding::{name_value, term_value}},
+};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct RuleTemplateFile {
@@ -24,7 +30,8 @@ pub struct ClauseTemplate {
 #[serde(untagged)]
 pub enum TermSide {
     Ref(TermRefComplex),
-    Value(String),
+    /// typed term, a string is parsed (older rules.json)
+    Value(#[serde(deserialize_with = "crate::term::deserialize_term")] Term),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -62,7 +69,9 @@ pub enum ProofNode {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GoalEntry {
-    pub goal: String,
+    // typed term, a goal string is parsed
+    #[serde(deserialize_with = "crate::term::deserialize_term")]
+    pub goal: Term,
     pub subtree: Vec<ProofNode>,
 }
 
@@ -71,27 +80,109 @@ pub struct GoalEntry {
 pub struct UnificationInputFp {
     pub goal_name: Vec<TermFp>,
     pub subtree_goals: Vec<Vec<TermFp>>,
+    // goal commitment salts: own goal + one per subtree entry (0 for `true` leaves)
+    pub goal_salt: Fp,
+    pub subtree_salts: Vec<Fp>,
+    // issuer signature, registry and revocation paths of the goal head if it is a fact
+    pub fact_witness: FactWitnessFp,
+    // secret key of the holder, proves the holder binding of a fact goal
+    pub holder_sk: Fp,
 }
-impl Default for UnificationInputFp {
-    fn default() -> Self {
+impl UnificationInputFp {
+    // padded input of the keygen circuit
+    pub fn empty(shape: &CircuitShape) -> Self {
+        Self {
+            goal_name: vec![TermFp::empty(shape); shape.max_pred_list],
+            subtree_goals: vec![vec![TermFp::empty(shape); shape.max_pred_list]; shape.max_arity],
+            goal_salt: Fp::zero(),
+            subtree_salts: Vec::new(),
+            fact_witness: FactWitnessFp::placeholder(shape),
+            holder_sk: Fp::one(),
+        }
+    }
+}
+
+/// Schnorr signature of the issuer on a fact hash (`utils_2::issuer_signature`): R = k·G as (x, y)
+/// and s = k + e·sk, below p.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FactSignatureFp {
+    pub r: [Fp; 2],
+    pub s: Fp,
+}
+
+impl FactSignatureFp {
+    // witness of the goals that are not facts: R = G, s = 0, it never verifies
+    pub fn placeholder() -> Self {
+        Self { r: affine_xy(&pallas::Affine::generator()), s: Fp::zero() }
+    }
+}
+
+/// Path of a leaf in a Merkle tree (`utils_2::fact_registry`, `utils_2::revocation`): bit i of
+/// `index` is 1 if the node of level i is a right child, `siblings[i]` is its sibling.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FactPathFp {
+    pub index: u64,
+    pub siblings: Vec<Fp>,
+}
+
+impl FactPathFp {
+    pub fn placeholder(depth: usize) -> Self {
+        Self { index: 0, siblings: vec![Fp::zero(); depth] }
+    }
+}
+
+/// What proves a fact goal, the issuer hands it out with the fact: the issuer's signature on the
+/// fact hash, its path in the fact registry, the path of its (empty) revocation slot, and the
+/// validity period and holder id that were hashed with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FactWitnessFp {
+    pub signature: FactSignatureFp,
+    pub path: FactPathFp,
+    pub revocation_path: FactPathFp,
+    pub validity: FactValidity,
+    pub holder: Fp,
+}
+
+impl FactWitnessFp {
+    // witness of the goals that are not facts, it never verifies
+    pub fn placeholder(shape: &CircuitShape) -> Self {
         Self {
-            goal_name: vec![TermFp::default(); MAX_PRED_LIST],
-            subtree_goals: vec![vec![TermFp::default(); MAX_PRED_LIST];MAX_ARITY],
+            signature: FactSignatureFp::placeholder(),
+            path: FactPathFp::placeholder(shape.fact_tree_depth),
+            revocation_path: FactPathFp::placeholder(REVOCATION_TREE_DEPTH),
+            validity: FactValidity::UNBOUNDED,
+            holder: Fp::zero(),
         }
     }
 }
+
 #[derive(Clone, Debug)]
 pub struct TermFp {
     pub name: Fp,
     pub args: Vec<Vec<Fp>>,
     pub fact_hashes: Fp,
 }
-impl Default for TermFp{
-    fn default() -> Self {
+impl TermFp {
+    pub fn empty(shape: &CircuitShape) -> Self {
+        Self {
+            name: Fp::zero(),
+            args: vec![vec![Fp::zero(); shape.max_pred_list]; shape.max_arity],
+            fact_hashes: Fp::zero(),
+        }
+    }
+
+    // args matrix with every slot unused (-1)
+    pub fn padded_args(shape: &CircuitShape) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::one().neg(); shape.max_pred_list]; shape.max_arity]
+    }
+
+    // subtree entry of a `true` leaf
+    pub fn true_leaf(shape: &CircuitShape) -> Self {
         Self {
             name: Fp::zero(),
-            args: vec![vec![Fp::zero(); MAX_PRED_LIST];MAX_ARITY], 
-            fact_hashes: Fp::zero(), }
+            args: Self::padded_args(shape),
+            fact_hashes: Fp::zero(),
+        }
     }
 }
 
@@ -100,11 +191,48 @@ pub struct RuleTemplateFileFp {
     pub predicates: Vec<PredicateTemplateFp>,
 }
 
+impl RuleTemplateFileFp {
+    /// Canonical flattening of the rule set, the public rules hash is the Poseidon fold of this.
+    ///
+    /// [#preds, per pred: name, arity, #clauses,
+    ///   per clause: #rows, per row: #children, per child: name, arity,
+    ///               #equalities, per equality: packed refs (+ value if the right side is a value)]
+    ///
+    /// Every length is explicit, so two different rule sets never flatten to the same vector.
+    pub fn to_flat_vec(&self) -> Vec<Fp> {
+        let mut flat = vec![Fp::from(self.predicates.len() as u64)];
+
+        for pred in &self.predicates {
+            flat.push(pred.name);
+            flat.push(pred.arity);
+            flat.push(Fp::from(pred.clauses.len() as u64));
+
+            for clause in &pred.clauses {
+                flat.push(Fp::from(clause.children.len() as u64));
+                for row in &clause.children {
+                    flat.push(Fp::from(row.len() as u64));
+                    for ch in row {
+                        flat.push(ch.name);
+                        flat.push(ch.arity);
+                    }
+                }
+
+                flat.push(Fp::from(clause.equalities.len() as u64));
+                for eq in &clause.equalities {
+                    flat.extend(eq.to_flat_tokens());
+                }
+            }
+        }
+
+        flat
+    }
+}
+
 impl From<&RuleTemplateFile> for RuleTemplateFileFp {
     fn from(r: &RuleTemplateFile) -> Self {
         let predicates = r.predicates.iter().map(|p| {
             PredicateTemplateFp {
-                name: to_fp_value(&p.name),
+                name: name_value(&p.name),
                 arity: Fp::from(p.arity as u64),
                 clauses: p.clauses.iter().map(|c| ClauseTemplateFp::from(c)).collect(),
             }
@@ -125,7 +253,7 @@ impl From<&ClauseTemplate> for ClauseTemplateFp {
     fn from(c: &ClauseTemplate) -> Self {
         let children = c.children.iter().map(|row| {
             row.iter().map(|ch| ChildSigFp {
-                name: to_fp_value(&ch.name),
+                name: name_value(&ch.name),
                 arity: Fp::from(ch.arity as u64),
             }).collect()
         }).collect();
@@ -168,6 +296,25 @@ pub struct EqualityFp {
     pub right: TermSideFp,
 }
 
+impl EqualityFp {
+    // (left << 34) | (right << 2) | tag, followed by the value(s) the tag announces:
+    // tag 0 ref = ref, tag 1 ref = value, tag 2 value = value.
+    pub(crate) fn to_flat_tokens(&self) -> Vec<Fp> {
+        let shift = |n: u32| Fp::from_u128(1u128 << n);
+        match (&self.left, &self.right) {
+            (TermSideFp::Ref(l), TermSideFp::Ref(r)) => {
+                vec![l.packed() * shift(34) + r.packed() * shift(2)]
+            }
+            (TermSideFp::Ref(l), TermSideFp::Value(v)) | (TermSideFp::Value(v), TermSideFp::Ref(l)) => {
+                vec![l.packed() * shift(34) + Fp::from(1u64), *v]
+            }
+            (TermSideFp::Value(a), TermSideFp::Value(b)) => {
+                vec![Fp::from(2u64), *a, *b]
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum TermSideFp {
     Ref(TermRefFp),
@@ -183,7 +330,7 @@ impl From<&TermSide> for TermSideFp {
                 arg: Fp::from(r.arg as u64),
                 list_index: Fp::from(r.list_index as u64),
             }),
-            TermSide::Value(v) => TermSideFp::Value(to_fp_value(v)),
+            TermSide::Value(v) => TermSideFp::Value(term_value(v)),
         }
     }
 }
@@ -197,11 +344,75 @@ pub struct TermRefFp {
     pub list_index: Fp,
 }
 
+impl TermRefFp {
+    // node<<24 | pred<<16 | arg<<8 | list, every index is < 256
+    fn packed(&self) -> Fp {
+        self.children_node_list * Fp::from(1u64 << 24)
+            + self.predicate * Fp::from(1u64 << 16)
+            + self.arg * Fp::from(1u64 << 8)
+            + self.list_index
+    }
+}
+
 
 // Config struct to read the yaml
 #[derive(Debug, Deserialize)]
 pub struct FactEntry {
     pub predicate: String,
     pub args: Vec<Vec<String>>, // minden arg stringként jön a YAML-ből
-    pub salt: String,
+    // kézzel megadott salt (decimális, legalább MIN_SALT_BITS bites), nélküle az issuer generál egyet
+    pub salt: Option<String>,
+    // érvényességi idő unix másodpercben (mindkét vége benne van), a hash része
+    pub valid_from: Option<i64>,
+    pub valid_until: Option<i64>,
+    // a birtokos azonosítója (decimális Fp, `utils_2::holder_binding::holder_id`), a hash része
+    pub holder: Option<String>,
+}
+
+impl FactEntry {
+    /// The fact as a term, an arg is its list joined with "," (`[low]` → `low`,
+    /// `["[(a,b)]"]` → `[(a,b)]`).
+    pub fn term(&self) -> anyhow::Result<Term> {
+        if self.args.is_empty() {
+            return Ok(Term::atom(&self.predicate));
+        }
+        let args = self
+            .args
+            .iter()
+            .map(|inner| Term::read(&inner.join(",")))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Term::Compound { name: self.predicate.clone(), args })
+    }
+
+    /// The holder id the fact is bound to, 0 if it is not bound.
+    pub fn holder_id(&self) -> anyhow::Result<Fp> {
+        match &self.holder {
+            Some(id) => fp_from_dec_str(id),
+            None => Ok(Fp::zero()),
+        }
+    }
+
+    /// The validity period, a missing end is unbounded.
+    pub fn validity(&self) -> FactValidity {
+        FactValidity {
+            from: self.valid_from.unwrap_or(FactValidity::UNBOUNDED.from),
+            until: self.valid_until.unwrap_or(FactValidity::UNBOUNDED.until),
+        }
+    }
+}
+
+/// Validity period of a fact in unix seconds, both ends included. It is hashed into the fact and
+/// the circuit compares it with the public verification time as signed 64-bit integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FactValidity {
+    pub from: i64,
+    pub until: i64,
+}
+
+impl FactValidity {
+    pub const UNBOUNDED: Self = Self { from: i64::MIN, until: i64::MAX };
+
+    pub fn contains(&self, time: i64) -> bool {
+        self.from <= time && time <= self.until
+    }
 }
@@ -0,0 +1,182 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Digits after the decimal point: every number is a multiple of 10^-DECIMAL_PLACES. The scale
+/// is a constant of the built-in chip, so the prover and the verifier have to be built with the
+/// same value (it is part of the key fingerprint).
+pub const DECIMAL_PLACES: u32 = 4;
+
+/// 10^DECIMAL_PLACES, the value of 1
+pub const SCALE: i128 = 10i128.pow(DECIMAL_PLACES);
+
+/// How the results of `*` and `/` (and longer literals) are cut to DECIMAL_PLACES.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// towards -inf
+    Floor,
+    /// towards 0
+    TowardZero,
+    /// to the nearest, ties towards +inf
+    HalfUp,
+}
+
+pub const ROUNDING: Rounding = Rounding::HalfUp;
+
+/// A fixed-point number, `units` * 10^-DECIMAL_PLACES. Integers and decimals are the same value
+/// in the circuit (2 and 2.0 are both 2 * SCALE units).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed {
+    units: i128,
+}
+
+impl Fixed {
+    pub const fn from_units(units: i128) -> Self {
+        Self { units }
+    }
+
+    pub const fn from_int(n: i64) -> Self {
+        Self { units: n as i128 * SCALE }
+    }
+
+    pub const fn units(self) -> i128 {
+        self.units
+    }
+
+    /// The integer it is equal to, if there is one.
+    pub fn to_int(self) -> Option<i64> {
+        if self.units % SCALE != 0 {
+            return None;
+        }
+        i64::try_from(self.units / SCALE).ok()
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.units.checked_add(other.units).map(Self::from_units)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.units.checked_sub(other.units).map(Self::from_units)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        self.units.checked_neg().map(Self::from_units)
+    }
+
+    /// `self * other` rounded by ROUNDING.
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        round_div(self.units.checked_mul(other.units)?, SCALE).map(Self::from_units)
+    }
+
+    /// `self / other` rounded by ROUNDING, `None` for a zero divisor.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        round_div(self.units.checked_mul(SCALE)?, other.units).map(Self::from_units)
+    }
+}
+
+/// `num / den` rounded by ROUNDING, computed the way the built-in chip proves it:
+/// `(2*num + h*den) / (2*den)` rounded down (towards zero for TowardZero), h = 1 for HalfUp.
+pub fn round_div(num: i128, den: i128) -> Option<i128> {
+    if den == 0 {
+        return None;
+    }
+    let h = if ROUNDING == Rounding::HalfUp { den } else { 0 };
+    let (acc, dvs) = (num.checked_mul(2)?.checked_add(h)?, den.checked_mul(2)?);
+    let (q, r) = (acc / dvs, acc % dvs);
+    Some(if ROUNDING != Rounding::TowardZero && r != 0 && (r < 0) != (dvs < 0) { q - 1 } else { q })
+}
+
+impl From<i64> for Fixed {
+    fn from(n: i64) -> Self {
+        Self::from_int(n)
+    }
+}
+
+/// Prolog float syntax: `-12.5`, `3`, `1.5e3`, `2.5E-2`. More digits than DECIMAL_PLACES are
+/// rounded by ROUNDING.
+impl FromStr for Fixed {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let bad = || anyhow!("Bad decimal number: {s}");
+        let (neg, body) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (mantissa, exp) = match body.find(['e', 'E']) {
+            Some(i) => (&body[..i], body[i + 1..].parse::<i32>().map_err(|_| bad())?),
+            None => (body, 0),
+        };
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int_part.is_empty() || !(int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit())) {
+            bail!("Bad decimal number: {s}");
+        }
+
+        let digits: i128 = format!("{int_part}{frac_part}").parse().map_err(|_| bad())?;
+        // value = digits * 10^shift units
+        let shift = exp + DECIMAL_PLACES as i32 - frac_part.len() as i32;
+        let digits = if neg { -digits } else { digits };
+        let units = if shift >= 0 {
+            10i128.checked_pow(shift as u32).and_then(|p| digits.checked_mul(p))
+        } else {
+            10i128.checked_pow(shift.unsigned_abs()).and_then(|p| round_div(digits, p))
+        };
+        units.map(Self::from_units).ok_or_else(|| anyhow!("Decimal number out of range: {s}"))
+    }
+}
+
+/// As Prolog writes a float: at least one digit after the point (`2.0`, `373.5`, `-0.05`).
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let abs = self.units.unsigned_abs();
+        let scale = SCALE as u128;
+        let frac = format!("{:0width$}", abs % scale, width = DECIMAL_PLACES as usize);
+        let frac = frac.trim_end_matches('0');
+        let sign = if self.units < 0 { "-" } else { "" };
+        write!(f, "{sign}{}.{}", abs / scale, if frac.is_empty() { "0" } else { frac })
+    }
+}
+
+/// Written as a string (`"373.5"`), read from a string or a JSON number (SWI-Prolog floats).
+impl Serialize for Fixed {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fixed {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct V;
+
+        impl de::Visitor<'_> for V {
+            type Value = Fixed;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a decimal number or its text")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Fixed, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Fixed, E> {
+                Ok(Fixed::from_int(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Fixed, E> {
+                i64::try_from(v).map(Fixed::from_int).map_err(E::custom)
+            }
+
+            // the shortest text that reads back as the same float
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Fixed, E> {
+                if !v.is_finite() {
+                    return Err(E::custom(format!("Not a finite number: {v}")));
+                }
+                format!("{v:?}").parse().map_err(E::custom)
+            }
+        }
+
+        d.deserialize_any(V)
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use halo2_proofs::pasta::{pallas, Fp};
+use halo2curves::ff::PrimeField;
+use num_bigint::BigUint;
+
+// Fp <-> decimal string, the format of every Fp in the json files
+pub fn fp_to_dec_string(x: &Fp) -> String {
+    BigUint::from_bytes_le(x.to_repr().as_ref()).to_str_radix(10)
+}
+
+pub fn fp_from_dec_str(s: &str) -> Result<Fp> {
+    Option::from(Fp::from_repr(repr_from_dec_str(s)?)).ok_or_else(|| anyhow::anyhow!("Invalid Fp conversion: {s}"))
+}
+
+// The issuer secret key is a Pallas scalar, written the same way
+pub fn scalar_to_dec_string(x: &pallas::Scalar) -> String {
+    BigUint::from_bytes_le(x.to_repr().as_ref()).to_str_radix(10)
+}
+
+pub fn scalar_from_dec_str(s: &str) -> Result<pallas::Scalar> {
+    Option::from(pallas::Scalar::from_repr(repr_from_dec_str(s)?))
+        .ok_or_else(|| anyhow::anyhow!("Invalid scalar conversion: {s}"))
+}
+
+fn repr_from_dec_str(s: &str) -> Result<[u8; 32]> {
+    let int_val = BigUint::parse_bytes(s.as_bytes(), 10)
+        .ok_or_else(|| anyhow::anyhow!("Invalid decimal number: {s}"))?;
+    let int_bytes = int_val.to_bytes_le();
+    if int_bytes.len() > 32 {
+        anyhow::bail!("Number does not fit into 32 bytes: {s}");
+    }
+    let mut bytes = [0u8; 32];
+    bytes[..int_bytes.len()].copy_from_slice(&int_bytes);
+    Ok(bytes)
+}
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use halo2_proofs::pasta::Fp;
+use halo2curves::ff::Field;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::fp_decimal::{fp_from_dec_str, fp_to_dec_string},
+    utils_2::holder_binding::holder_id,
+};
+
+/// Holder secret key, only the prover reads it.
+pub const HOLDER_SECRET_KEY_PATH: &str = "prove/holder_sk.json";
+/// Holder id, handed to the issuer to bind facts to the holder (`holder` in facts.yaml).
+pub const HOLDER_ID_PATH: &str = "input/holder_id.json";
+/// Salts of the issued facts, written by the issuer for the holder only (the fact hashes are only
+/// hidden as long as their salts are).
+pub const FACT_SALTS_PATH: &str = "prove/fact_salts.json";
+
+#[derive(Serialize, Deserialize)]
+struct SecretKeyFile {
+    sk: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HolderIdFile {
+    id: String,
+}
+
+/// Salt of one fact, `fact` is the printed fact (as the goals print).
+#[derive(Serialize, Deserialize)]
+struct FactSaltEntry {
+    fact: String,
+    salt: String,
+}
+
+/// The secret key in `path`, a new random one is written there if it does not exist.
+pub fn load_or_create_holder_sk(path: &Path) -> Result<Fp> {
+    if path.exists() {
+        let file: SecretKeyFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let sk = fp_from_dec_str(&file.sk)?;
+        if sk == Fp::zero() {
+            anyhow::bail!("The holder secret key in {} is 0", path.display());
+        }
+        return Ok(sk);
+    }
+
+    let sk = Fp::random(OsRng);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = SecretKeyFile { sk: fp_to_dec_string(&sk) };
+    fs::write(path, serde_json::to_string_pretty(&file)?)?;
+    Ok(sk)
+}
+
+pub fn write_holder_id(path: &Path, sk: Fp) -> Result<Fp> {
+    let id = holder_id(sk);
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, serde_json::to_string_pretty(&HolderIdFile { id: fp_to_dec_string(&id) })?)?;
+    Ok(id)
+}
+
+pub fn write_fact_salts(path: &Path, salts: &[(String, Fp)]) -> Result<()> {
+    let entries: Vec<FactSaltEntry> = salts
+        .iter()
+        .map(|(fact, salt)| FactSaltEntry { fact: fact.clone(), salt: fp_to_dec_string(salt) })
+        .collect();
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+    Ok(())
+}
+
+/// Fact salts keyed by the printed fact, empty if the issuer has not written them yet.
+pub fn read_fact_salts(path: &Path) -> Result<HashMap<String, Fp>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the fact salts {}", path.display()))?;
+    let entries: Vec<FactSaltEntry> = serde_json::from_str(&text)?;
+    entries
+        .into_iter()
+        .map(|e| Ok((e.fact, fp_from_dec_str(&e.salt)?)))
+        .collect()
+}
@@ -0,0 +1,169 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use halo2_proofs::pasta::{pallas, Fp};
+use halo2curves::ff::Field;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{FactPathFp, FactSignatureFp, FactValidity, FactWitnessFp},
+    io::fp_decimal::{fp_from_dec_str, fp_to_dec_string, scalar_from_dec_str, scalar_to_dec_string},
+    utils_2::issuer_signature::{affine_xy, issuer_public_key, point_or_generator},
+};
+
+/// Issuer secret key, only the issuer reads it.
+pub const ISSUER_SECRET_KEY_PATH: &str = "issue/issuer_sk.json";
+/// Issuer public key, the public input of every proof.
+pub const ISSUER_PK_PATH: &str = "input/issuer_pk.json";
+/// Root of the issuer's fact registry, a public input of every proof.
+pub const FACT_ROOT_PATH: &str = "input/fact_root.json";
+/// Root of the issuer's revocation tree, a public input of every proof.
+pub const REVOCATION_ROOT_PATH: &str = "input/revocation_root.json";
+/// Signatures, registry and revocation paths, validity periods and holder ids of the issued facts,
+/// handed to the prover with facts.yaml.
+pub const FACT_WITNESSES_PATH: &str = "input/fact_witnesses.json";
+
+#[derive(Serialize, Deserialize)]
+struct SecretKeyFile {
+    sk: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PublicKeyFile {
+    x: String,
+    y: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RootFile {
+    root: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PathEntry {
+    index: u64,
+    siblings: Vec<String>,
+}
+
+/// One issued fact, `fact` is the printed fact (as the goals print).
+#[derive(Serialize, Deserialize)]
+struct IssuedFactEntry {
+    fact: String,
+    r: [String; 2],
+    s: String,
+    path: PathEntry,
+    revocation_path: PathEntry,
+    valid_from: i64,
+    valid_until: i64,
+    holder: String,
+}
+
+impl From<&FactPathFp> for PathEntry {
+    fn from(p: &FactPathFp) -> Self {
+        Self { index: p.index, siblings: p.siblings.iter().map(fp_to_dec_string).collect() }
+    }
+}
+
+impl TryFrom<&PathEntry> for FactPathFp {
+    type Error = anyhow::Error;
+
+    fn try_from(p: &PathEntry) -> Result<Self> {
+        let siblings = p.siblings.iter().map(|s| fp_from_dec_str(s)).collect::<Result<_>>()?;
+        Ok(Self { index: p.index, siblings })
+    }
+}
+
+/// The secret key in `path`, a new random one is written there if it does not exist.
+pub fn load_or_create_issuer_sk(path: &Path) -> Result<pallas::Scalar> {
+    if path.exists() {
+        let file: SecretKeyFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+        return scalar_from_dec_str(&file.sk);
+    }
+
+    let sk = pallas::Scalar::random(OsRng);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = SecretKeyFile { sk: scalar_to_dec_string(&sk) };
+    fs::write(path, serde_json::to_string_pretty(&file)?)?;
+    Ok(sk)
+}
+
+pub fn write_issuer_pk(path: &Path, sk: &pallas::Scalar) -> Result<[Fp; 2]> {
+    let pk = affine_xy(&issuer_public_key(sk));
+    fs::create_dir_all(path.parent().unwrap())?;
+    let file = PublicKeyFile { x: fp_to_dec_string(&pk[0]), y: fp_to_dec_string(&pk[1]) };
+    fs::write(path, serde_json::to_string_pretty(&file)?)?;
+    Ok(pk)
+}
+
+/// (x, y) of the issuer public key, it has to be a point of Pallas.
+pub fn read_issuer_pk(path: &Path) -> Result<[Fp; 2]> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the issuer public key {}", path.display()))?;
+    let file: PublicKeyFile = serde_json::from_str(&text)?;
+    let pk = [fp_from_dec_str(&file.x)?, fp_from_dec_str(&file.y)?];
+    if affine_xy(&point_or_generator(pk)) != pk {
+        anyhow::bail!("The issuer public key in {} is not a curve point", path.display());
+    }
+    Ok(pk)
+}
+
+/// A tree root (fact registry, revocation tree).
+pub fn write_root(path: &Path, root: &Fp) -> Result<()> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, serde_json::to_string_pretty(&RootFile { root: fp_to_dec_string(root) })?)?;
+    Ok(())
+}
+
+pub fn read_root(path: &Path) -> Result<Fp> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the tree root {}", path.display()))?;
+    let file: RootFile = serde_json::from_str(&text)?;
+    fp_from_dec_str(&file.root)
+}
+
+pub fn write_fact_witnesses(path: &Path, issued: &[(String, FactWitnessFp)]) -> Result<()> {
+    let entries: Vec<IssuedFactEntry> = issued
+        .iter()
+        .map(|(fact, w)| IssuedFactEntry {
+            fact: fact.clone(),
+            r: w.signature.r.map(|c| fp_to_dec_string(&c)),
+            s: fp_to_dec_string(&w.signature.s),
+            path: PathEntry::from(&w.path),
+            revocation_path: PathEntry::from(&w.revocation_path),
+            valid_from: w.validity.from,
+            valid_until: w.validity.until,
+            holder: fp_to_dec_string(&w.holder),
+        })
+        .collect();
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+    Ok(())
+}
+
+/// Fact witnesses keyed by the printed fact.
+pub fn read_fact_witnesses(path: &Path) -> Result<HashMap<String, FactWitnessFp>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the fact witnesses {}", path.display()))?;
+    let entries: Vec<IssuedFactEntry> = serde_json::from_str(&text)?;
+
+    entries
+        .into_iter()
+        .map(|e| {
+            let signature = FactSignatureFp {
+                r: [fp_from_dec_str(&e.r[0])?, fp_from_dec_str(&e.r[1])?],
+                s: fp_from_dec_str(&e.s)?,
+            };
+            let witness = FactWitnessFp {
+                signature,
+                path: FactPathFp::try_from(&e.path)?,
+                revocation_path: FactPathFp::try_from(&e.revocation_path)?,
+                validity: FactValidity { from: e.valid_from, until: e.valid_until },
+                holder: fp_from_dec_str(&e.holder)?,
+            };
+            Ok((e.fact, witness))
+        })
+        .collect()
+}
@@ -0,0 +1,142 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use anyhow::Result;
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    circuit_shape::CircuitShape,
+    fixed::{DECIMAL_PLACES, ROUNDING},
+    io::fp_decimal::fp_to_dec_string,
+    unification_checker_circuit::UnificationCircuit,
+    utils_2::{
+        common_helpers::{RANGE_CHECK_BITS, RANGE_CHECK_LIMB_BITS},
+        revocation::REVOCATION_TREE_DEPTH,
+    },
+};
+
+// Key management: only the `Params` are cached.
+//
+// `Params` only depend on k and are kept as `params_k{k}.bin`. The `VerifyingKey` and the
+// `ProvingKey` are NOT cached: halo2_proofs 0.3 (zcash) can not write or read them, so
+// `keygen_vk` / `keygen_pk` run on every prove and verify. Caching them needs a halo2 with key
+// serialization (the PSE or axiom fork), i.e. porting every chip and the Pallas ECC gadgets of
+// the signature checks to it.
+
+/// What the keys in `manifest.json` were built for: the fingerprint (circuit shape, rules hash)
+/// and the digest of the pinned vk. The prover writes it, the verifier fails if its circuit has
+/// another fingerprint or its vk another digest.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct KeyManifest {
+    pub fingerprint: String,
+    pub vk_digest: String,
+}
+
+pub fn circuit_fingerprint(shape: &CircuitShape, rules_hash: &Fp) -> String {
+    let desc = format!(
+        "k={};pred_list={};clauses={};preds={};arity={};children={};fact_tree={};\
+         range={RANGE_CHECK_BITS}/{RANGE_CHECK_LIMB_BITS};decimals={DECIMAL_PLACES}/{ROUNDING:?};revocation={REVOCATION_TREE_DEPTH};rules={}",
+        shape.k,
+        shape.max_pred_list,
+        shape.max_clauses_per_predicate,
+        shape.max_predicates_overall,
+        shape.max_arity,
+        shape.max_children,
+        shape.fact_tree_depth,
+        fp_to_dec_string(rules_hash),
+    );
+    blake3::hash(desc.as_bytes()).to_hex().to_string()
+}
+
+pub fn vk_digest(vk: &VerifyingKey<EqAffine>) -> String {
+    blake3::hash(format!("{:?}", vk.pinned()).as_bytes()).to_hex().to_string()
+}
+
+pub fn load_or_create_params(dir: &Path, k: u32) -> Result<Params<EqAffine>> {
+    let path = dir.join(format!("params_k{k}.bin"));
+    if path.exists() {
+        let params = Params::read(&mut BufReader::new(File::open(&path)?))?;
+        return Ok(params);
+    }
+
+    let params: Params<EqAffine> = Params::new(k);
+    fs::create_dir_all(dir)?;
+    params.write(&mut BufWriter::new(File::create(&path)?))?;
+    Ok(params)
+}
+
+fn read_manifest(dir: &Path) -> Result<Option<KeyManifest>> {
+    let path = dir.join("manifest.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+fn write_manifest(dir: &Path, manifest: &KeyManifest) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("manifest.json"), serde_json::to_string_pretty(manifest)?)?;
+    Ok(())
+}
+
+fn keygen(
+    dir: &Path,
+    circuit: &UnificationCircuit,
+    rules_hash: &Fp,
+) -> Result<(Params<EqAffine>, VerifyingKey<EqAffine>, KeyManifest)> {
+    let params = load_or_create_params(dir, circuit.shape.k)?;
+    let vk = keygen_vk(&params, circuit)?;
+    let manifest = KeyManifest {
+        fingerprint: circuit_fingerprint(&circuit.shape, rules_hash),
+        vk_digest: vk_digest(&vk),
+    };
+    Ok((params, vk, manifest))
+}
+
+/// Verifier side: params (k from `circuit.shape`) from the cache, vk generated from the keygen
+/// `circuit` and checked against the manifest of the prover, which is never rewritten here.
+pub fn keygen_checked_vk(
+    dir: &Path,
+    circuit: &UnificationCircuit,
+    rules_hash: &Fp,
+) -> Result<(Params<EqAffine>, VerifyingKey<EqAffine>)> {
+    let (params, vk, current) = keygen(dir, circuit, rules_hash)?;
+    let path = dir.join("manifest.json");
+    let Some(m) = read_manifest(dir)? else {
+        anyhow::bail!("No key manifest {}, the prover writes it", path.display());
+    };
+    if m.fingerprint != current.fingerprint {
+        anyhow::bail!("The circuit (shape, rules) differs from the one the proofs were made for in {}", path.display());
+    }
+    if m.vk_digest != current.vk_digest {
+        anyhow::bail!("Verifying key digest differs from the one in {}", path.display());
+    }
+    Ok((params, vk))
+}
+
+/// Prover side: params from the cache, vk and pk generated; the manifest is written for a new
+/// fingerprint, a vk generated for the same fingerprint must have the recorded digest.
+pub fn keygen_checked_pk(
+    dir: &Path,
+    circuit: &UnificationCircuit,
+    rules_hash: &Fp,
+) -> Result<(Params<EqAffine>, ProvingKey<EqAffine>)> {
+    let (params, vk, current) = keygen(dir, circuit, rules_hash)?;
+    match read_manifest(dir)? {
+        Some(m) if m.fingerprint == current.fingerprint && m.vk_digest != current.vk_digest => {
+            anyhow::bail!("Verifying key digest differs from the one in {}", dir.join("manifest.json").display());
+        }
+        Some(m) if m == current => {}
+        _ => write_manifest(dir, &current)?,
+    }
+    let pk = keygen_pk(&params, vk, circuit)?;
+    Ok((params, pk))
+}
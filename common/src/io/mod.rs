@@ -1 +1,4 @@
-pub mod read_fact_hashes;
\ No newline at end of file
+pub mod issuer;
+pub mod holder;
+pub mod fp_decimal;
+pub mod keys;
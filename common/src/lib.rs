@@ -1,4 +1,7 @@
 pub mod data;
+pub mod term;
+pub mod fixed;
+pub mod circuit_shape;
 pub mod unification_checker_circuit;
 pub mod chips;
 pub mod utils_2;
@@ -0,0 +1,561 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::fixed::Fixed;
+
+/// A Prolog term as it is written into proof_tree.json, with explicit structure:
+///
+/// ```json
+/// {"type": "compound", "name": "currentPrice", "args": [
+///     {"type": "number", "value": 747}, {"type": "atom", "name": "HUF"}]}
+/// {"type": "list", "items": [{"type": "var", "name": "H"}], "tail": {"type": "var", "name": "T"}}
+/// ```
+///
+/// `Display` prints it the way SWI-Prolog's `term_string/2` does (`currentPrice(747,'HUF')`),
+/// `read` parses that syntax back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Term {
+    Atom { name: String },
+    Number { value: i64 },
+    /// `373.5`, a float of SWI-Prolog as a fixed-point number (`{"type": "decimal", "value": "373.5"}`)
+    Decimal { value: Fixed },
+    /// `"text"`, a SWI-Prolog string object
+    String { value: String },
+    Var { name: String },
+    Compound { name: String, args: Vec<Term> },
+    /// `tail` is missing for a proper list, `[]` is a list without items
+    List {
+        items: Vec<Term>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tail: Option<Box<Term>>,
+    },
+}
+
+impl Term {
+    pub fn atom(name: &str) -> Self {
+        Term::Atom { name: name.to_string() }
+    }
+
+    pub fn nil() -> Self {
+        Term::List { items: Vec::new(), tail: None }
+    }
+
+    /// Fields of a `(a,b,c)` tuple, the term itself if it is not one.
+    pub fn tuple_fields(&self) -> Vec<&Term> {
+        let mut out = Vec::new();
+        let mut cur = self;
+        while let Term::Compound { name, args } = cur {
+            if name != "," || args.len() != 2 {
+                break;
+            }
+            out.push(&args[0]);
+            cur = &args[1];
+        }
+        out.push(cur);
+        out
+    }
+
+    /// No variables in it.
+    pub fn is_ground(&self) -> bool {
+        match self {
+            Term::Atom { .. } | Term::Number { .. } | Term::Decimal { .. } | Term::String { .. } => true,
+            Term::Var { .. } => false,
+            Term::Compound { args, .. } => args.iter().all(Term::is_ground),
+            Term::List { items, tail } => items.iter().all(Term::is_ground) && tail.as_deref().is_none_or(Term::is_ground),
+        }
+    }
+
+    /// Parses one term in standard Prolog syntax, with the default operator table.
+    pub fn read(text: &str) -> Result<Self> {
+        let mut r = Reader { toks: tokenize(text)?, pos: 0 };
+        let t = r.parse(1200)?;
+        if r.pos != r.toks.len() {
+            bail!("Operator priority clash in {text}");
+        }
+        Ok(t)
+    }
+}
+
+/// Typed term, or the goal string of older proof trees (`"104580 is 1045800*10 div 100"`)
+/// read with `Term::read`, so operators, negative numbers and quoted atoms parse as in Prolog.
+pub fn deserialize_term<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Term, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Typed(Term),
+        Text(String),
+    }
+
+    match Repr::deserialize(d)? {
+        Repr::Typed(t) => Ok(t),
+        Repr::Text(text) => Term::read(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+// ------------------ Operators ------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpType {
+    Xfx,
+    Xfy,
+    Yfx,
+}
+
+/// SWI-Prolog's default infix operator table.
+pub fn infix_op(name: &str) -> Option<(u32, OpType)> {
+    use OpType::*;
+    let op = match name {
+        ":-" | "-->" => (1200, Xfx),
+        ";" | "|" => (1100, Xfy),
+        "->" | "*->" => (1050, Xfy),
+        "," => (1000, Xfy),
+        "=" | "\\=" | "==" | "\\==" | "@<" | "@>" | "@=<" | "@>=" | "=.." | "is" | "=:=" | "=\\=" | "<"
+        | ">" | "=<" | ">=" => (700, Xfx),
+        ":" => (200, Xfy),
+        "+" | "-" | "/\\" | "\\/" | "xor" => (500, Yfx),
+        "*" | "/" | "//" | "mod" | "rem" | "div" | "<<" | ">>" | "rdiv" => (400, Yfx),
+        "**" => (200, Xfx),
+        "^" => (200, Xfy),
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// (priority, max priority of the argument) of the prefix operators.
+pub fn prefix_op(name: &str) -> Option<(u32, u32)> {
+    match name {
+        ":-" | "?-" => Some((1200, 1199)),
+        "\\+" => Some((900, 900)),
+        "-" | "+" | "\\" => Some((200, 200)),
+        _ => None,
+    }
+}
+
+/// Max priority of the (left, right) argument of an infix operator.
+pub fn arg_priorities(prec: u32, ty: OpType) -> (u32, u32) {
+    match ty {
+        OpType::Xfx => (prec - 1, prec - 1),
+        OpType::Xfy => (prec - 1, prec),
+        OpType::Yfx => (prec, prec - 1),
+    }
+}
+
+fn is_symbol_char(c: char) -> bool {
+    "+-*/\\^<>=~:.?@#&$".contains(c)
+}
+
+fn is_alnum(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// ------------------ Reader ------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Int(i64),
+    Dec(Fixed),
+    Str(String),
+    Var(String),
+    Name(String),
+    Punct(char),
+}
+
+struct Token {
+    tok: Tok,
+    // whitespace before the token: `f(` is a compound, `f (` is not; `-1` is a number, `- 1` is not
+    layout_before: bool,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    let mut layout = false;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            layout = true;
+            i += 1;
+            continue;
+        }
+        if c == '%' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let start = i;
+        let tok = if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+                i += 1;
+            }
+            // a '.' is the fraction only if a digit follows, `X = 5.` ends the clause
+            if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let has_exp = matches!(chars.get(i), Some('e' | 'E'))
+                    && match chars.get(i + 1) {
+                        Some('+' | '-') => chars.get(i + 2).is_some_and(char::is_ascii_digit),
+                        Some(c) => c.is_ascii_digit(),
+                        None => false,
+                    };
+                if has_exp {
+                    i += 2;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let literal: String = chars[start..i].iter().collect();
+                Tok::Dec(literal.parse()?)
+            } else {
+                let digits: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+                Tok::Int(digits.parse().map_err(|_| anyhow!("Integer out of range: {digits}"))?)
+            }
+        } else if c.is_ascii_uppercase() || c == '_' {
+            while i < chars.len() && is_alnum(chars[i]) {
+                i += 1;
+            }
+            Tok::Var(chars[start..i].iter().collect())
+        } else if c.is_ascii_lowercase() {
+            while i < chars.len() && is_alnum(chars[i]) {
+                i += 1;
+            }
+            Tok::Name(chars[start..i].iter().collect())
+        } else if c == '\'' || c == '"' {
+            let mut quoted = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => bail!("Unterminated quoted text in {text}"),
+                    Some(q) if *q == c && chars.get(i + 1) == Some(&c) => {
+                        quoted.push(c);
+                        i += 2;
+                    }
+                    Some(q) if *q == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some('\\') => {
+                        let esc = chars.get(i + 1).ok_or_else(|| anyhow!("Bad escape in {text}"))?;
+                        quoted.push(match esc {
+                            'n' => '\n',
+                            't' => '\t',
+                            other => *other,
+                        });
+                        i += 2;
+                    }
+                    Some(ch) => {
+                        quoted.push(*ch);
+                        i += 1;
+                    }
+                }
+            }
+            if c == '"' { Tok::Str(quoted) } else { Tok::Name(quoted) }
+        } else if "()[]{},|".contains(c) {
+            i += 1;
+            Tok::Punct(c)
+        } else if c == '!' || c == ';' {
+            i += 1;
+            Tok::Name(c.to_string())
+        } else if is_symbol_char(c) {
+            while i < chars.len() && is_symbol_char(chars[i]) {
+                i += 1;
+            }
+            Tok::Name(chars[start..i].iter().collect())
+        } else {
+            bail!("Unexpected character '{c}' in {text}");
+        };
+
+        out.push(Token { tok, layout_before: layout });
+        layout = false;
+    }
+    Ok(out)
+}
+
+struct Reader {
+    toks: Vec<Token>,
+    pos: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos).map(|t| &t.tok)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Tok::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) { Ok(()) } else { bail!("Expected '{c}'") }
+    }
+
+    // The next token can start an operand (of a prefix operator)
+    fn starts_term(&self) -> bool {
+        match self.peek() {
+            Some(Tok::Int(_)) | Some(Tok::Dec(_)) | Some(Tok::Str(_)) | Some(Tok::Var(_)) => true,
+            Some(Tok::Punct(c)) => "([{".contains(*c),
+            Some(Tok::Name(n)) => infix_op(n).is_none() || prefix_op(n).is_some(),
+            None => false,
+        }
+    }
+
+    fn parse(&mut self, max: u32) -> Result<Term> {
+        let (mut left, mut left_prec) = self.parse_primary(max)?;
+        loop {
+            let name = match self.peek() {
+                Some(Tok::Name(n)) => n.clone(),
+                Some(Tok::Punct(',')) => ",".to_string(),
+                _ => break,
+            };
+            let Some((prec, ty)) = infix_op(&name) else { break };
+            let (left_max, right_max) = arg_priorities(prec, ty);
+            if prec > max || left_prec > left_max {
+                break;
+            }
+            self.pos += 1;
+            let right = self.parse(right_max)?;
+            left = Term::Compound { name, args: vec![left, right] };
+            left_prec = prec;
+        }
+        Ok(left)
+    }
+
+    fn parse_primary(&mut self, max: u32) -> Result<(Term, u32)> {
+        let tok = self
+            .toks
+            .get(self.pos)
+            .map(|t| t.tok.clone())
+            .ok_or_else(|| anyhow!("Unexpected end of term"))?;
+        self.pos += 1;
+
+        match tok {
+            Tok::Int(value) => Ok((Term::Number { value }, 0)),
+            Tok::Dec(value) => Ok((Term::Decimal { value }, 0)),
+            Tok::Str(value) => Ok((Term::String { value }, 0)),
+            Tok::Var(name) => Ok((Term::Var { name }, 0)),
+            Tok::Punct('(') => {
+                let t = self.parse(1200)?;
+                self.expect(')')?;
+                Ok((t, 0))
+            }
+            Tok::Punct('[') => Ok((self.parse_list()?, 0)),
+            Tok::Name(name) => {
+                let next = self.toks.get(self.pos);
+                // Functional notation
+                if let Some(Token { tok: Tok::Punct('('), layout_before: false }) = next {
+                    self.pos += 1;
+                    let mut args = vec![self.parse(999)?];
+                    while self.eat(',') {
+                        args.push(self.parse(999)?);
+                    }
+                    self.expect(')')?;
+                    return Ok((Term::Compound { name, args }, 0));
+                }
+                // Negative number
+                if let (Some(Token { tok: Tok::Int(n), layout_before: false }), "-") = (next, name.as_str()) {
+                    let value = -*n;
+                    self.pos += 1;
+                    return Ok((Term::Number { value }, 0));
+                }
+                if let (Some(Token { tok: Tok::Dec(d), layout_before: false }), "-") = (next, name.as_str()) {
+                    let value = d.checked_neg().ok_or_else(|| anyhow!("Decimal number out of range: -{d}"))?;
+                    self.pos += 1;
+                    return Ok((Term::Decimal { value }, 0));
+                }
+                if let Some((prec, arg_max)) = prefix_op(&name)
+                    && prec <= max
+                    && self.starts_term()
+                {
+                    let arg = self.parse(arg_max)?;
+                    return Ok((Term::Compound { name, args: vec![arg] }, prec));
+                }
+                Ok((Term::Atom { name }, 0))
+            }
+            Tok::Punct(c) => bail!("Unexpected '{c}'"),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Term> {
+        if self.eat(']') {
+            return Ok(Term::nil());
+        }
+        let mut items = vec![self.parse(999)?];
+        while self.eat(',') {
+            items.push(self.parse(999)?);
+        }
+        let tail = if self.eat('|') { Some(self.parse(999)?) } else { None };
+        self.expect(']')?;
+
+        // [a|[b]] is [a,b]
+        Ok(match tail {
+            Some(Term::List { items: rest, tail }) => {
+                items.extend(rest);
+                Term::List { items, tail }
+            }
+            tail => Term::List { items, tail: tail.map(Box::new) },
+        })
+    }
+}
+
+// ------------------ Writer ------------------
+
+fn atom_needs_quotes(a: &str) -> bool {
+    if matches!(a, "[]" | "!" | ";" | "{}") {
+        return false;
+    }
+    match a.chars().next() {
+        None => true,
+        Some(c) if c.is_ascii_lowercase() => !a.chars().all(is_alnum),
+        Some(_) => !a.chars().all(is_symbol_char),
+    }
+}
+
+fn write_atom(a: &str, out: &mut String) {
+    if !atom_needs_quotes(a) {
+        out.push_str(a);
+        return;
+    }
+    write_quoted(a, '\'', out);
+}
+
+fn write_quoted(text: &str, quote: char, out: &mut String) {
+    out.push(quote);
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+}
+
+// Items of a list and its tail, nested tails ([a|[b|T]]) flattened
+fn list_parts<'a>(items: &'a [Term], tail: Option<&'a Term>) -> (Vec<&'a Term>, Option<&'a Term>) {
+    let mut all: Vec<&Term> = items.iter().collect();
+    let mut tail = tail;
+    while let Some(Term::List { items, tail: t }) = tail {
+        all.extend(items.iter());
+        tail = t.as_deref();
+    }
+    (all, tail)
+}
+
+// Writes `t` as an operand of at most `max` priority, like `writeq/1`
+fn write_term(t: &Term, max: u32, out: &mut String) {
+    match t {
+        Term::Number { value } => out.push_str(&value.to_string()),
+        Term::Decimal { value } => out.push_str(&value.to_string()),
+        Term::String { value } => write_quoted(value, '"', out),
+        Term::Var { name } => out.push_str(name),
+        Term::Atom { name } => write_atom(name, out),
+        Term::List { items, tail } => {
+            let (items, tail) = list_parts(items, tail.as_deref());
+            if items.is_empty() {
+                match tail {
+                    Some(t) => write_term(t, max, out),
+                    None => out.push_str("[]"),
+                }
+                return;
+            }
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_term(item, 999, out);
+            }
+            if let Some(t) = tail {
+                out.push('|');
+                write_term(t, 999, out);
+            }
+            out.push(']');
+        }
+        Term::Compound { name: f, args } if args.len() == 2 && infix_op(f).is_some() => {
+            let (prec, ty) = infix_op(f).unwrap();
+            let (left_max, right_max) = arg_priorities(prec, ty);
+            let mut left = String::new();
+            let mut right = String::new();
+            write_term(&args[0], left_max, &mut left);
+            write_term(&args[1], right_max, &mut right);
+
+            if prec > max {
+                out.push('(');
+            }
+            out.push_str(&left);
+            if f == "," {
+                out.push(',');
+            } else if f.chars().all(is_alnum) {
+                out.push_str(&format!(" {f} "));
+            } else {
+                // a space keeps symbol chars of the operands from gluing to the operator
+                if left.ends_with(is_symbol_char) {
+                    out.push(' ');
+                }
+                write_atom(f, out);
+                if right.starts_with(is_symbol_char) {
+                    out.push(' ');
+                }
+            }
+            out.push_str(&right);
+            if prec > max {
+                out.push(')');
+            }
+        }
+        Term::Compound { name: f, args } if args.len() == 1 && prefix_op(f).is_some() => {
+            let (prec, arg_max) = prefix_op(f).unwrap();
+            let mut arg = String::new();
+            write_term(&args[0], arg_max, &mut arg);
+
+            if prec > max {
+                out.push('(');
+            }
+            write_atom(f, out);
+            if matches!(args[0], Term::Number { .. } | Term::Decimal { .. }) || arg.starts_with(is_symbol_char) || f.chars().all(is_alnum) {
+                out.push(' ');
+            }
+            out.push_str(&arg);
+            if prec > max {
+                out.push(')');
+            }
+        }
+        Term::Compound { name, args } => {
+            write_atom(name, out);
+            out.push('(');
+            for (i, a) in args.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_term(a, 999, out);
+            }
+            out.push(')');
+        }
+    }
+}
+
+/// Quoted, as SWI-Prolog's `term_string/2` prints it (`24006 is 22005+2001`, `currentPrice(747,'HUF')`).
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write_term(self, 1200, &mut out);
+        f.write_str(&out)
+    }
+}
@@ -5,38 +5,45 @@ use halo2_proofs::{
 };
 use crate::{
     chips::{
-         fact_check::fact_hash_chip::{FactChip, FactConfig}, finding_rule::{body_subtree_chip::UnifCompareConfig, sig_check_chip::{SigCheckChip, SigCheckConfig}}, rlc_chip::RlcFixedChip, value_check::{dot_chip::DotExistsChip, rows_compress_config::{RowsCompressChip, RowsCompressConfig}, rule_rows_chip::{RuleRowsChip, RuleRowsConfig}}
+         fact_check::{built_in_check_chip::BuiltinExprChip, fact_hash_chip::{FactChip, FactConfig, FactInput}, issuer_sig_chip::IssuerSigChip}, finding_rule::{body_subtree_chip::UnifCompareConfig, sig_check_chip::{SigCheckChip, SigCheckConfig}}, goal_commit_chip::{GoalCommitChip, GoalCommitConfig}, rlc_chip::RlcFixedChip, rules_check_chip::{RulesChip, RulesConfig}, value_check::{clause_exists_chip::ClauseExistsChip, rule_rows_chip::{EqSide, RuleRowsChip, RuleRowsConfig}}
     },
-    data::{ClauseTemplateFp, RuleTemplateFileFp, TermFp, TermSideFp, UnificationInputFp},
-    utils_2::{common_helpers::{MAX_ARITY, MAX_CANDIDATES, MAX_CHILDREN, MAX_PRED_LIST, to_fp_value}, consistency_helpers::bind_goal_name_args_inputs, predicate_helpers::bind_proof_and_candidates_sig_pairs},
+    circuit_shape::CircuitShape,
+    data::{ClauseTemplateFp, FactWitnessFp, RuleTemplateFileFp, TermFp, TermSideFp, UnificationInputFp},
+    utils_2::{common_helpers::{to_fp_value, TypedValue}, consistency_helpers::{bind_candidates_to_rules, bind_goal_name_args_inputs, bind_goal_to_proof_cells, bind_rules}, predicate_helpers::bind_proof_and_candidates_sig_pairs},
 };
 use halo2_proofs::circuit::Value;
-pub const PER_TERM: usize  = MAX_ARITY * MAX_PRED_LIST;
-pub const PER_NODE: usize  = MAX_PRED_LIST * PER_TERM;
-pub const MAX_NODES: usize = 1 + MAX_CHILDREN;
-pub const MAX_DOT_DIM: usize = MAX_NODES * PER_NODE + 1;
+
+// Instance columns of the circuit: issuer public key, fact root, revocation root, verification time,
+// rules hash, goal commitments (in this order)
+pub const NUM_INSTANCE_COLUMNS: usize = 6;
 
 // Circuit definition
+// `shape` is the runtime Circuit::Params equivalent: configure does not depend on it, synthesize lays out by it
 #[derive(Debug, Clone)]
 pub struct UnificationCircuit {
     pub rules: RuleTemplateFileFp,
     pub unif: UnificationInputFp,
+    pub shape: CircuitShape,
 }
 
 #[derive(Clone, Debug)]
 pub struct UnifConfig {
-    pub dot_cfg: <DotExistsChip as Chip<Fp>>::Config,
+    pub exists_cfg: <ClauseExistsChip as Chip<Fp>>::Config,
     //pub hash_cfg: PoseidonHashConfig,
     pub rlc_cfg: <RlcFixedChip as Chip<Fp>>::Config,
     pub unif_cmp_cfg: UnifCompareConfig,
     pub sig_check_cfg: SigCheckConfig,
-    pub rows_compress_chip :RowsCompressConfig,
     pub rule_rows_cfg: RuleRowsConfig,
     pub fact_cfg: FactConfig,
-    //pub rules_check_cfg: RulesConfig,
-
-    pub public_facts_hashes: Column<Instance>,
-    //pub public_rules_hash: Column<Instance>,
+    pub rules_check_cfg: RulesConfig,
+    pub goal_commit_cfg: GoalCommitConfig,
+
+    pub public_issuer_pk: Column<Instance>,
+    pub public_fact_root: Column<Instance>,
+    pub public_revocation_root: Column<Instance>,
+    pub public_verification_time: Column<Instance>,
+    pub public_rules_hash: Column<Instance>,
+    pub public_goal_commitments: Column<Instance>,
 }
 
 impl Circuit<Fp> for UnificationCircuit {
@@ -49,34 +56,48 @@ impl Circuit<Fp> for UnificationCircuit {
                 predicates: Vec::new(), // empty but valid
             },
             unif: UnificationInputFp {
-                goal_name: vec![TermFp::default()],
+                goal_name: vec![TermFp::empty(&self.shape)],
                 subtree_goals: Vec::new(), // empty tree
+                goal_salt: Fp::zero(),
+                subtree_salts: Vec::new(),
+                fact_witness: FactWitnessFp::placeholder(&self.shape),
+                holder_sk: Fp::one(),
             },
+            shape: self.shape,
         }
     }
 
     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
         let alpha = {
-            to_fp_value("rlc_alpha_v1")
+            to_fp_value(TypedValue::Str("rlc_alpha_v1"))
         };
-        let dot_cfg = DotExistsChip::configure(meta);
+        let exists_cfg = ClauseExistsChip::configure(meta);
         
         let rlc_cfg = RlcFixedChip::configure(meta, alpha);
         let unif_cmp_cfg: UnifCompareConfig = UnifCompareConfig::configure(meta);
         let sig_check_cfg = SigCheckChip::configure(meta,alpha);
-        let rows_compress_chip = RowsCompressChip::configure(meta);
         let rule_rows_cfg: RuleRowsConfig = RuleRowsChip::configure(meta);
 
-        let public_facts_hashes = meta.instance_column();
-        //let public_rules_hash = meta.instance_column();
+        let public_issuer_pk = meta.instance_column();
+        let public_fact_root = meta.instance_column();
+        let public_revocation_root = meta.instance_column();
+        let public_verification_time = meta.instance_column();
+        let public_rules_hash = meta.instance_column();
+        let public_goal_commitments = meta.instance_column();
        
-        meta.enable_equality(public_facts_hashes);
-        //meta.enable_equality(public_rules_hash);
-
-        let fact_cfg = FactChip::configure(meta, public_facts_hashes);
-        //let rules_check_cfg = RulesChip::configure(meta, public_rules_hash);
-
-        UnifConfig {dot_cfg, rlc_cfg,unif_cmp_cfg, sig_check_cfg, rows_compress_chip, rule_rows_cfg, fact_cfg, public_facts_hashes/*, rules_check_cfg,  public_rules_hash*/ }
+        meta.enable_equality(public_rules_hash);
+
+        let fact_cfg = FactChip::configure(
+            meta,
+            public_issuer_pk,
+            public_fact_root,
+            public_revocation_root,
+            public_verification_time,
+        );
+        let rules_check_cfg = RulesChip::configure(meta, public_rules_hash);
+        let goal_commit_cfg = GoalCommitChip::configure(meta, public_goal_commitments);
+
+        UnifConfig {exists_cfg, rlc_cfg,unif_cmp_cfg, sig_check_cfg, rule_rows_cfg, fact_cfg, rules_check_cfg, goal_commit_cfg, public_issuer_pk, public_fact_root, public_revocation_root, public_verification_time, public_rules_hash, public_goal_commitments }
     }
 
     fn synthesize(
@@ -85,6 +106,14 @@ impl Circuit<Fp> for UnificationCircuit {
         mut layouter: impl Layouter<Fp>,
     ) -> Result<(), Error>
     {
+    let shape = &self.shape;
+
+    // Range check lookup table for the builtin comparisons
+    BuiltinExprChip::construct(cfg.fact_cfg.builtin_cfg.clone())
+        .load_range_table(layouter.namespace(|| "range check table"))?;
+    // Lookup table of the ECC chip (issuer signature)
+    IssuerSigChip::construct(cfg.fact_cfg.issuer_cfg.clone())
+        .load_table(layouter.namespace(|| "ecc range check table"))?;
 
     // Consistency check for Goal name + args == Term name + args == Unif goal name + args
     let (
@@ -95,6 +124,7 @@ impl Circuit<Fp> for UnificationCircuit {
         &mut layouter,
         &cfg.fact_cfg,
         &self.unif,
+        shape,
     )?;
 
     //Check if the used unification is a valid rule
@@ -105,7 +135,21 @@ impl Circuit<Fp> for UnificationCircuit {
         &self.unif.goal_name,   // goal TermFp
         &self.unif.subtree_goals,   // subtree TermFp vec
         &self.rules.predicates, // predikátumok
+        shape,
     )?;
+
+    // Rules commitment: Poseidon(flattened rules) == public rules hash, candidates come from those cells
+    let (flattened_rules, rule_sig_cells) = bind_rules(
+        "Bind rules",
+        &mut layouter,
+        &cfg.rules_check_cfg,
+        &self.rules,
+    )?;
+    RulesChip::construct(cfg.rules_check_cfg.clone()).assign(
+        layouter.namespace(|| "Rules hash"),
+        &flattened_rules,
+    )?;
+    bind_candidates_to_rules(&mut layouter, &candidate_pairs_all, &rule_sig_cells)?;
     
     // Rules-only check (fact ág kommentelve)
     let is_fact_cell = layouter.assign_region(
@@ -149,141 +193,127 @@ impl Circuit<Fp> for UnificationCircuit {
         &proof_pairs,
         &candidate_pairs_all,
         &is_fact_cell,
+        shape,
     )?;
     let fact_hash_chip= FactChip::construct(cfg.fact_cfg.clone());
 
-    let mut flag_copy_cells: Vec<AssignedCell<Fp, Fp>> = Vec::new();
-
-    for (i, bflag) in b_flags.iter().enumerate() {
-        let copied = layouter.assign_region(
-            || format!("flag_copy_{i}"),
-            |mut region| {
-                region.assign_advice(
-                    || "flag_copy",
-                    cfg.fact_cfg.salt,
-                    i+1,
-                    || bflag.value().copied()
-                )
-            },
-        )?;
-        flag_copy_cells.push(copied.clone());
-    }
-
-
-    let goal_name_salt_cell = layouter.assign_region(
-        || "assign goal_name_salt",
-        |mut region| {
-            region.assign_advice(
-                || "goal salt",
-                cfg.fact_cfg.salt, // any advice column, e.g., from FactConfig or your own column
-                0,
-                || Value::known(self.unif.goal_name.get(0).unwrap().fact_hashes), // or however your salt is stored
-            )
-        },
-    )?;
-
     let built_in_or_fact = fact_hash_chip.assign(
-        layouter.namespace(|| "Fact membership"),
+        layouter.namespace(|| "Fact signature"),
         &goal_name_cell,
         &goal_name_arg_cells,
-        &goal_name_salt_cell,
         &is_fact_local_for_fact_check,
-        &flag_copy_cells,
+        &b_flags,
+        FactInput {
+            salt: self.unif.goal_name[0].fact_hashes,
+            witness: &self.unif.fact_witness,
+            holder_sk: self.unif.holder_sk,
+        },
     )?;
 
 
+    // w: the args of the goal and of every child, the clause equalities refer to its slots
     let rows_chip = RuleRowsChip::construct(cfg.rule_rows_cfg.clone());
-    let mut all_clause_rows: Vec<Vec<Vec<AssignedCell<Fp, Fp>>>> = Vec::new();
+    let w_fp = build_witness_w_fp(&self.unif.goal_name, &self.unif.subtree_goals, shape);
+    let w_cells = rows_chip.assign_w(layouter.namespace(|| "w"), &w_fp)?;
 
+    // ok_i = [w satisfies every equality of clause i], each equality checked on its own row;
+    // candidates without a clause have no equalities
+    let mut clause_ok: Vec<AssignedCell<Fp, Fp>> = Vec::new();
     for (p_i, pred) in self.rules.predicates.iter().enumerate() {
         for (c_i, clause) in pred.clauses.iter().enumerate() {
-            let eqs_fp_4d = clause_equalities_4d_tuples_fp(clause);
-            let rows_ij = rows_chip.assign_rule_rows_fp_4d(
-                layouter.namespace(|| format!("rows pred{}_clause{}", p_i, c_i)),
-                &eqs_fp_4d,
-                MAX_DOT_DIM,
-            )?;
-            all_clause_rows.push(rows_ij); // [row][k]
+            clause_ok.push(rows_chip.assign_clause_ok(
+                layouter.namespace(|| format!("equalities pred{}_clause{}", p_i, c_i)),
+                &clause_equalities_fp(clause),
+                &w_cells,
+                shape,
+            )?);
         }
     }
+    while clause_ok.len() < shape.max_candidates() {
+        clause_ok.push(rows_chip.assign_clause_ok(layouter.namespace(|| "no clause"), &[], &w_cells, shape)?);
+    }
 
-// 2) minden klózhoz külön c_i (flag NÉLKÜL!)
-let compress_chip = RowsCompressChip::construct(cfg.rows_compress_chip.clone());
-let compressed_c_vecs: Vec<Vec<AssignedCell<Fp,Fp>>> =
-    compress_chip.assign_compressed_all(
-        layouter.namespace(|| "compress all"),
-        &all_clause_rows,
+    // „létezik i”: ok_i * b_i, b_flags a SigCheck-ből
+    ClauseExistsChip::construct(cfg.exists_cfg.clone()).assign_exists_clause(
+        layouter.namespace(|| "exists i: ok_i = 1 and b_i = 1"),
+        &clause_ok,
         &b_flags,
-        MAX_CANDIDATES,
-        MAX_DOT_DIM,
-    )?;              // -> [clause][k]
-
-// 3) w beírása (ahogy eddig)
-let w_fp = build_witness_w_fp(&self.unif.goal_name, &self.unif.subtree_goals);
-let w_cells = assign_w_cells(&mut layouter, &cfg.rows_compress_chip, &w_fp)?;
-
-// 4) „létezik i” dot check a b_flags-szel
-let dot_exists_chip = DotExistsChip { cfg: cfg.dot_cfg /* ha külön config kell, hívd külön configure-rel */ };
-// Ha a DotExistsChip-nek külön configure kellett (ajánlott), kezeld úgy, mint a többi chipet: 
-// let dot_exists_cfg = DotExistsChip::configure(meta); ... itt DotExistsChip::construct(dot_exists_cfg)
-
-dot_exists_chip.assign_exists_dot_zero(
-    layouter.namespace(|| "exists i: dot(w, c_i)=0 with y_i<=b_i and sum y_i=1"),
-    &w_cells,
-    &compressed_c_vecs,
-    &b_flags,         // ezek a SigCheck-ből jöttek
-    &built_in_or_fact,    // 0: rule → aktív; 1: fact → mindez gate-elve
-)?;
+        &built_in_or_fact,    // 0: rule → aktív; 1: fact → mindez gate-elve
+    )?;
+
+    // Goal commitments: head goal → instance row 0, i-th child → row 1 + i, the verifier chains them
+    bind_goal_to_proof_cells(&mut layouter, &goal_name_cell, &goal_name_arg_cells, &proof_pairs, &w_cells, shape)?;
+
+    let zero_names = layouter.assign_region(
+        || "zero names for missing proof rows",
+        |mut region| {
+            (0..shape.max_pred_list)
+                .map(|p| region.assign_advice_from_constant(|| "zero name", cfg.goal_commit_cfg.v, p, Fp::zero()))
+                .collect::<Result<Vec<_>, Error>>()
+        },
+    )?;
+
+    let commit_chip = GoalCommitChip::construct(cfg.goal_commit_cfg.clone());
+    let (per_term, per_node) = (shape.per_term(), shape.per_node());
+    for n in 0..shape.max_nodes() {
+        let names: Vec<AssignedCell<Fp, Fp>> = match proof_pairs.get(n) {
+            Some(row) => row.iter().take(shape.max_pred_list).map(|(name, _)| name.clone()).collect(),
+            None => zero_names.clone(),
+        };
+        let args: Vec<&[AssignedCell<Fp, Fp>]> = (0..shape.max_pred_list)
+            .map(|p| &w_cells[n * per_node + p * per_term..n * per_node + (p + 1) * per_term])
+            .collect();
+        let salt = if n == 0 {
+            self.unif.goal_salt
+        } else {
+            self.unif.subtree_salts.get(n - 1).copied().unwrap_or(Fp::zero())
+        };
+
+        commit_chip.commit(
+            layouter.namespace(|| format!("goal commitment {n}")),
+            Value::known(salt),
+            &names,
+            &args,
+            n,
+        )?;
+    }
             
     Ok(())
     }
 }
 
 
-pub fn clause_equalities_4d_tuples_fp(
-    clause: &ClauseTemplateFp,
-) -> Vec<(Fp,Fp,Fp,Fp,Fp,Fp,Fp,Fp)> {
-    clause
-        .equalities
-        .iter()
-        .filter_map(|eq| match (&eq.left, &eq.right) {
-            (TermSideFp::Ref(l), TermSideFp::Ref(r)) => Some((
-                l.children_node_list, // left node index
-                l.predicate,         // left predicate index
-                l.arg,               // left argument row index
-                l.list_index,        // left list index
-
-                r.children_node_list, // right node index
-                r.predicate,         // right predicate index
-                r.arg,               // right argument row index
-                r.list_index,        // right list index
-            )),
-            _ => None, // ha bármelyik Value => nincs egyenlet sor
-        })
-        .collect()
+/// Both sides of every equality of the clause: a ref is a slot of w, a value is a constant.
+pub fn clause_equalities_fp(clause: &ClauseTemplateFp) -> Vec<(EqSide, EqSide)> {
+    let side = |s: &TermSideFp| match s {
+        TermSideFp::Ref(r) => EqSide::Slot(r.children_node_list, r.predicate, r.arg, r.list_index),
+        TermSideFp::Value(v) => EqSide::Const(*v),
+    };
+    clause.equalities.iter().map(|eq| (side(&eq.left), side(&eq.right))).collect()
 }
 
 pub fn build_witness_w_fp(
-    goal_terms: &[TermFp],         // len ≤ MAX_PRED_LIST
-    subtree_terms: &[Vec<TermFp>], // len ≤ MAX_CHILDREN, mindegyik len ≤ MAX_PRED_LIST
+    goal_terms: &[TermFp],         // len ≤ max_pred_list
+    subtree_terms: &[Vec<TermFp>], // len ≤ max_children, mindegyik len ≤ max_pred_list
+    shape: &CircuitShape,
 ) -> Vec<Fp> {
     let mut w = Vec::new(); // +1 homogén 1-nek a végére
 
     // node 0: goal
-    for p in 0..MAX_PRED_LIST {
+    for p in 0..shape.max_pred_list {
         let term_opt = goal_terms.get(p);
-        let flat = term_opt.map(flatten_term_args).unwrap_or_else(|| vec![Fp::zero(); PER_TERM]);
+        let flat = term_opt.map(|t| flatten_term_args(t, shape)).unwrap_or_else(|| vec![Fp::zero(); shape.per_term()]);
 
         w.extend(flat);
     }
 
-    // node 1..MAX_CHILDREN: subtree
-    for n in 0..MAX_CHILDREN {
+    // node 1..max_children: subtree
+    for n in 0..shape.max_children {
         let row_terms = subtree_terms.get(n); // Option<&Vec<TermFp>>
 
-        for p in 0..MAX_PRED_LIST {
+        for p in 0..shape.max_pred_list {
             let term_opt = row_terms.and_then(|row| row.get(p));
-            let flat = term_opt.map(flatten_term_args).unwrap_or_else(|| vec![Fp::zero(); PER_TERM]);
+            let flat = term_opt.map(|t| flatten_term_args(t, shape)).unwrap_or_else(|| vec![Fp::zero(); shape.per_term()]);
 
             w.extend(flat);           
         }
@@ -296,35 +326,11 @@ pub fn build_witness_w_fp(
 }
 
 
-fn assign_w_cells(
-    layouter: &mut impl Layouter<Fp>,
-    cfg: &RowsCompressConfig,
-    w: &[Fp],
-) -> Result<Vec<AssignedCell<Fp, Fp>>, Error>
-{
-    layouter.assign_region(
-        || "bind w vector",
-        |mut region| {
-            let mut out = Vec::with_capacity(w.len());
-            for (i, val) in w.iter().enumerate() {
-                let c = region.assign_advice(
-                    || format!("w[{i}]"),
-                    cfg.val,
-                    i,
-                    || Value::known(*val),
-                )?;
-                out.push(c);
-            }
-            Ok(out)
-        },
-    )
-}
-
-pub fn flatten_term_args(t: &TermFp) -> Vec<Fp> {
-    let mut flat = Vec::new();
+pub fn flatten_term_args(t: &TermFp, shape: &CircuitShape) -> Vec<Fp> {
+    let mut flat = Vec::with_capacity(shape.per_term());
 
-    for arg_i in 0..MAX_ARITY {
-        for list_i in 0..MAX_PRED_LIST {
+    for arg_i in 0..shape.max_arity {
+        for list_i in 0..shape.max_pred_list {
             flat.push(
                 t.args
                     .get(arg_i)
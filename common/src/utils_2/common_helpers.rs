@@ -1,50 +1,56 @@
 use std::usize;
 
 use halo2_proofs::pasta::Fp;
-
-//for the MNB example
-pub const MAX_PRED_LIST:usize = 4;
-pub const MAX_CLAUSES_PER_PREDICATE: usize = 4;
-pub const MAX_PREDICATES_OVERALL: usize = 10;
-pub const MAX_ARITY: usize = 4;
-pub const MAX_FACTS_HASHES: usize = 33;
-pub const MAX_EQUALITIES: usize = 6;
-pub const MAX_RULE_COMPONENTS: usize = 30;
-pub const MAX_CHILDREN: usize = 10;
-
-//These have to be parameterized for the given policy or for the tests
-/*pub const MAX_PRED_LIST:usize = 1;
-pub const MAX_CLAUSES_PER_PREDICATE: usize = 2;
-
-pub const MAX_PREDICATES_OVERALL: usize = 1;
-pub const MAX_ARITY: usize = 2;
-pub const MAX_FACTS_HASHES: usize = 59; 
-pub const MAX_EQUALITIES: usize = 6;
-
-pub const MAX_CHILDREN: usize = 2;
-pub const MAX_RULE_COMPONENTS: usize = 30;*/
-
-
-pub const MAX_CANDIDATES: usize = MAX_PREDICATES_OVERALL * MAX_CLAUSES_PER_PREDICATE;
-
-
-
-pub const MAX_SIG_TOKENS: usize = MAX_PRED_LIST *(1 + MAX_ARITY) * (1 + MAX_CHILDREN);
-
-
-pub fn to_fp_value(s: &str) -> Fp {
-    let s = s.trim().trim_matches('\'');
-
-    // ✅ 1) Próbáljuk számként értelmezni
-    if let Ok(v) = s.parse::<u64>() {
-        // Hatékony és pontos konverzió az Fp mezőbe
-        let fp_val = Fp::from(v);
-        return fp_val;
+use halo2curves::ff::PrimeField;
+
+use num_bigint::BigUint;
+
+use crate::{fixed::Fixed, io::fp_decimal::fp_from_dec_str};
+
+// The circuit dimensions (MAX_PRED_LIST, MAX_ARITY, ...) are runtime values now, see `circuit_shape::CircuitShape`
+
+// Range checks: values are decomposed into RANGE_CHECK_LIMB_BITS wide limbs looked up in a
+// 2^RANGE_CHECK_LIMB_BITS table (has to fit into the 2^k rows), comparisons and div/rem work on RANGE_CHECK_BITS wide signed integers
+// (a negative n is p - |n|, it is range checked as n + 2^(RANGE_CHECK_BITS-1))
+pub const RANGE_CHECK_LIMB_BITS: usize = 8;
+pub const RANGE_CHECK_BITS: usize = 64;
+
+
+/// A value as the circuit sees it. Numbers are field elements so they can be computed with,
+/// everything else is a blake3 hash whose key is derived from the type, so `12` and `'12'`,
+/// the atom `[]` and the empty list, an atom and a string of the same text all differ.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypedValue<'a> {
+    /// A raw non-negative integer: the field element itself
+    Int(u64),
+    /// An integer or decimal of a term: its units (`Fixed`), a negative one is `p - |units|`, so
+    /// `+`, `-` stay integer arithmetic and 2 and 2.0 are the same value
+    Number(Fixed),
+    Atom(&'a str),
+    Str(&'a str),
+    /// `[]`
+    Nil,
+    /// Any other term (compound, non-empty list, variable) by its printed form
+    Term(&'a str),
+}
+
+pub fn to_fp_value(v: TypedValue) -> Fp {
+    match v {
+        TypedValue::Int(n) => Fp::from(n),
+        TypedValue::Number(x) => {
+            let abs = Fp::from_u128(x.units().unsigned_abs());
+            if x.units() < 0 { -abs } else { abs }
+        }
+        TypedValue::Atom(s) => tagged_hash("atom", s),
+        TypedValue::Str(s) => tagged_hash("string", s),
+        TypedValue::Nil => tagged_hash("nil", ""),
+        TypedValue::Term(s) => tagged_hash("term", s),
     }
+}
 
-    // ✅ 2) Egyébként HASH → mezőelem (jelenlegi logika)
-    let hash = blake3::hash(s.as_bytes());
-    let bytes = hash.as_bytes();
+// blake3 → mezőelem, a kulcs a típusból származik (domain separation)
+fn tagged_hash(kind: &str, s: &str) -> Fp {
+    let bytes = blake3::derive_key(&format!("zk-policy value {kind} v1"), s.as_bytes());
 
     let limbs: [u64; 4] = [
         u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
@@ -54,4 +60,19 @@ pub fn to_fp_value(s: &str) -> Fp {
     ];
 
     Fp::from_raw(limbs)
-}
\ No newline at end of file
+}
+
+// Salts are random field elements (254 bits) generated by the issuer. A hand-written salt must
+// have at least MIN_SALT_BITS bits, a small one like `15414` is found by hashing the fact with
+// every small number
+pub const MIN_SALT_BITS: u64 = 128;
+
+/// A salt given in facts.yaml: a decimal integer below p of at least `MIN_SALT_BITS` bits.
+pub fn salt_from_dec_str(s: &str) -> anyhow::Result<Fp> {
+    let salt = fp_from_dec_str(s.trim())?;
+    let bits = BigUint::from_bytes_le(salt.to_repr().as_ref()).bits();
+    if bits < MIN_SALT_BITS {
+        anyhow::bail!("Weak salt {s} ({bits} bits, at least {MIN_SALT_BITS}), leave it out to get a random one");
+    }
+    Ok(salt)
+}
@@ -4,8 +4,8 @@ use halo2_proofs::{
     plonk::Error,
 };
 
-use crate::{chips::{fact_check::fact_hash_chip::FactConfig}, utils_2::common_helpers::MAX_ARITY};
-use crate::data::UnificationInputFp;
+use crate::{chips::{fact_check::fact_hash_chip::FactConfig, rules_check_chip::RulesConfig}, circuit_shape::CircuitShape};
+use crate::data::{RuleTemplateFileFp, TermFp, UnificationInputFp};
 
 /// Segédfüggvény a goal, unif_goal és term mezők bekötéséhez.
 /// Ez lesz hívva a fő circuit synthesize-ban.
@@ -14,6 +14,7 @@ pub fn bind_goal_name_args_inputs(
     layouter: &mut impl Layouter<Fp>,
     cfg: &FactConfig,
     unif: &UnificationInputFp,
+    shape: &CircuitShape,
 ) -> Result<(
         Vec<AssignedCell<Fp, Fp>>,     // goal_name_cells
         Vec<Vec<Vec<AssignedCell<Fp, Fp>>>>,// goal_argument_cells[p][(a,l)]
@@ -27,7 +28,10 @@ pub fn bind_goal_name_args_inputs(
             let mut all_arg_cells = Vec::new();
 
 
-            for (p_i, pred) in unif.goal_name.iter().enumerate() {
+            // a hiányzó predikátumok üresek (mint a w-ben), így a layout nem függ a goal hosszától
+            let empty = TermFp::empty(shape);
+            for p_i in 0..shape.max_pred_list {
+                let pred = unif.goal_name.get(p_i).unwrap_or(&empty);
                 // --- name ---
                 let name_cell = region.assign_advice(
                     || format!("goal_name_{}", p_i),
@@ -43,7 +47,8 @@ pub fn bind_goal_name_args_inputs(
                 for (a_i, arg_row) in pred.args.iter().enumerate() {
                     let mut pred_arg_cells = Vec::new();
                     for (l_i, arg_val) in arg_row.iter().enumerate() {
-                        let row_idx = p_i * MAX_ARITY + a_i;  // ✅ unique placement per predicate
+                        // every list element gets its own row, otherwise they overwrite each other
+                        let row_idx = (p_i * shape.max_arity + a_i) * shape.max_pred_list + l_i;
 
                         let c = region.assign_advice(
                             || format!("goal_arg_p{}_a{}_l{}", p_i, a_i, l_i),
@@ -63,121 +68,129 @@ pub fn bind_goal_name_args_inputs(
     )
 }
 
-/*pub fn bind_rules(
+/// A szabályhalmaz kanonikus lapítását (`RuleTemplateFileFp::to_flat_vec`) köti be a `rules` oszlopba.
+/// Visszaadja a lapított cellákat (ezekből lesz a publikus rules hash) és jelöltenként a
+/// (name, arity) cellákat [candidate][row][col] alakban, ahol a 0. sor a fej.
+pub fn bind_rules(
     region_name: &str,
     layouter: &mut impl Layouter<Fp>,
     cfg: &RulesConfig,
     rules: &RuleTemplateFileFp,
-) -> Result<Vec<AssignedCell<Fp, Fp>>,Error>
+) -> Result<(
+        Vec<AssignedCell<Fp, Fp>>,                                   // flattened_rules
+        Vec<Vec<Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>>>, // sig_cells[candidate][row][col]
+    ), Error>
 {
     layouter.assign_region(
         || region_name,
         |mut region| {
-            let mut flattened_rules = Vec::new();
-            let mut row: usize = 0 ;
-            // Flattening predicates
+            let mut flattened_rules: Vec<AssignedCell<Fp, Fp>> = Vec::new();
+            let mut push = |v: Fp| -> Result<AssignedCell<Fp, Fp>, Error> {
+                let row = flattened_rules.len();
+                let c = region.assign_advice(|| format!("rules[{row}]"), cfg.rules, row, || Value::known(v))?;
+                flattened_rules.push(c.clone());
+                Ok(c)
+            };
 
-            // Iterate over predicates
-            for pred in rules.predicates.iter() {
-                let pred_name_cell = region.assign_advice(
-                    || "rule_name",
-                    cfg.rules, 
-                    row,
-                    || Value::known(pred.name),
-                )?;
-                flattened_rules.push(pred_name_cell);
+            let mut sig_cells = Vec::new();
 
-                let pred_arity_cell = region.assign_advice(
-                    || "rule_arity",
-                    cfg.rules, 
-                    row+1,
-                    || Value::known(pred.arity),
-                )?;
-                flattened_rules.push(pred_arity_cell);
-                row += 2;
+            push(Fp::from(rules.predicates.len() as u64))?;
+            for pred in rules.predicates.iter() {
+                let head = (push(pred.name)?, push(pred.arity)?);
+                push(Fp::from(pred.clauses.len() as u64))?;
 
-                // Iterate over clauses
                 for clause in pred.clauses.iter() {
-
-                    // Iterate over children
-                    for child in clause.children.iter() {
-                        let pred_ch_name_cell = region.assign_advice(
-                            || "ch_name",
-                            cfg.rules, 
-                            row,
-                            || Value::known(child.name),
-                        )?;
-                        flattened_rules.push(pred_ch_name_cell);
-
-                        let pred_ch_arity_cell = region.assign_advice(
-                            || "ch_arity",
-                            cfg.rules, 
-                            row+1,
-                            || Value::known(child.arity),
-                        )?;
-                        flattened_rules.push(pred_ch_arity_cell);
-                        row += 2;
+                    let mut rows = vec![vec![head.clone()]];
+
+                    push(Fp::from(clause.children.len() as u64))?;
+                    for row_children in clause.children.iter() {
+                        push(Fp::from(row_children.len() as u64))?;
+                        let mut r = Vec::new();
+                        for ch in row_children.iter() {
+                            r.push((push(ch.name)?, push(ch.arity)?));
+                        }
+                        rows.push(r);
                     }
 
-                    // Iterate over equalities
+                    push(Fp::from(clause.equalities.len() as u64))?;
                     for eq in clause.equalities.iter() {
-                        let left_node_cell = region.assign_advice(
-                            || "left_node",
-                            cfg.rules, 
-                            row,
-                            || Value::known(eq.left.node),
-                        )?;
-                        flattened_rules.push(left_node_cell);
+                        for t in eq.to_flat_tokens() {
+                            push(t)?;
+                        }
+                    }
 
-                        let left_arg_cell = region.assign_advice(
-                            || "left_arg",
-                            cfg.rules, 
-                            row+1,
-                            || Value::known(eq.left.arg),
-                        )?;
-                        flattened_rules.push(left_arg_cell);
+                    sig_cells.push(rows);
+                }
+            }
 
-                        let right_node_cell = region.assign_advice(
-                            || "right_node",
-                            cfg.rules, 
-                            row+2,
-                            || Value::known(eq.right.node),
-                        )?;
-                        flattened_rules.push(right_node_cell);
+            // ugyanaz a sorrend, mint a natív rules hash-nél
+            debug_assert_eq!(flattened_rules.len(), rules.to_flat_vec().len());
+            Ok((flattened_rules, sig_cells))
+        },
+    )
+}
 
-                        let right_arg_cell = region.assign_advice(
-                            || "right_arg",
-                            cfg.rules, 
-                            row+3,
-                            || Value::known(eq.right.arg),
-                        )?;
-                        flattened_rules.push(right_arg_cell);
-                        row += 4;
+/// A jelöltek (name, arity) celláit a hash-elt szabály cellákhoz köti (a padding 0), így a SigCheck
+/// csak a publikus rules hash mögötti szabályokat fogadhatja el.
+pub fn bind_candidates_to_rules(
+    layouter: &mut impl Layouter<Fp>,
+    candidate_pairs_all: &[Vec<Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>>],
+    rule_sig_cells: &[Vec<Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>>],
+) -> Result<(), Error>
+{
+    layouter.assign_region(
+        || "bind candidates to committed rules",
+        |mut region| {
+            for (c_i, cand) in candidate_pairs_all.iter().enumerate() {
+                for (r_i, cand_row) in cand.iter().enumerate() {
+                    for (k, (cn, ca)) in cand_row.iter().enumerate() {
+                        match rule_sig_cells.get(c_i).and_then(|r| r.get(r_i)).and_then(|r| r.get(k)) {
+                            Some((rn, ra)) => {
+                                region.constrain_equal(cn.cell(), rn.cell())?;
+                                region.constrain_equal(ca.cell(), ra.cell())?;
+                            }
+                            // padding
+                            None => {
+                                region.constrain_constant(cn.cell(), Fp::zero())?;
+                                region.constrain_constant(ca.cell(), Fp::zero())?;
+                            }
+                        }
                     }
                 }
-            } 
-
-            // Iterate over facts
-            for fact in rules.facts.iter() {
-                let fact_name_cell = region.assign_advice(
-                    || "fact_name",
-                    cfg.rules, 
-                    row,
-                    || Value::known(fact.name),
-                )?;
-                flattened_rules.push(fact_name_cell);
-
-                let fact_arity_cell = region.assign_advice(
-                    || "fact_arity",
-                    cfg.rules, 
-                    row+1,
-                    || Value::known(fact.arity),
-                )?;
-                flattened_rules.push(fact_arity_cell);
-                row += 2;
             }
+            Ok(())
+        },
+    )
+}
 
-            Ok(flattened_rules)
+/// A FactChip által használt goal cellákat a SigCheck (proof_pairs) és a klóz-egyenlőségek (w) celláihoz köti,
+/// így a head goal commitment ugyanarra a goalra vonatkozik, amit a többi chip ellenőriz.
+pub fn bind_goal_to_proof_cells(
+    layouter: &mut impl Layouter<Fp>,
+    goal_name_cells: &[AssignedCell<Fp, Fp>],
+    goal_arg_cells: &[Vec<Vec<AssignedCell<Fp, Fp>>>], // [p][a][l]
+    proof_pairs: &[Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>],
+    w_cells: &[AssignedCell<Fp, Fp>],
+    shape: &CircuitShape,
+) -> Result<(), Error>
+{
+    layouter.assign_region(
+        || "bind goal to proof cells",
+        |mut region| {
+            for (p, name) in goal_name_cells.iter().enumerate().take(shape.max_pred_list) {
+                if let Some((proof_name, _)) = proof_pairs.first().and_then(|row| row.get(p)) {
+                    region.constrain_equal(name.cell(), proof_name.cell())?;
+                }
+
+                // node 0 a w elején: p*per_term + a*max_pred_list + l
+                for (a, arg_row) in goal_arg_cells[p].iter().enumerate().take(shape.max_arity) {
+                    for (l, c) in arg_row.iter().enumerate().take(shape.max_pred_list) {
+                        let w = &w_cells[p * shape.per_term() + a * shape.max_pred_list + l];
+                        region.constrain_equal(c.cell(), w.cell())?;
+                    }
+                }
+            }
+            Ok(())
         },
     )
-}*/
\ No newline at end of file
+}
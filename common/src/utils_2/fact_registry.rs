@@ -0,0 +1,68 @@
+use anyhow::Result;
+use halo2_proofs::pasta::Fp;
+
+use crate::{data::FactPathFp, utils_2::off_circuit_poseidon::poseidon_hash2_native};
+
+/// Parent of two nodes of the registry tree, `PoseidonHashChip::hash2` in-circuit.
+pub fn merkle_node(left: Fp, right: Fp) -> Fp {
+    poseidon_hash2_native(left, right)
+}
+
+/// The issuer's fact registry: a Poseidon Merkle tree of `depth` levels over the salted fact
+/// hashes, the empty leaves are 0. Only the root is published, so the public input does not
+/// depend on (or tell) the number of facts.
+pub struct FactRegistry {
+    // levels[0] = leaves, levels[depth] = [root]; the nodes right of the last fact are not stored
+    levels: Vec<Vec<Fp>>,
+    // root of an empty subtree of height i
+    empty: Vec<Fp>,
+}
+
+impl FactRegistry {
+    pub fn new(leaves: &[Fp], depth: usize) -> Result<Self> {
+        if leaves.len() > 1usize << depth {
+            anyhow::bail!("{} facts do not fit into a registry of depth {depth}", leaves.len());
+        }
+
+        let mut empty = vec![Fp::zero()];
+        for i in 0..depth {
+            empty.push(merkle_node(empty[i], empty[i]));
+        }
+
+        let mut levels = vec![leaves.to_vec()];
+        for i in 0..depth {
+            let parents = levels[i]
+                .chunks(2)
+                .map(|pair| merkle_node(pair[0], pair.get(1).copied().unwrap_or(empty[i])))
+                .collect();
+            levels.push(parents);
+        }
+        Ok(Self { levels, empty })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> Fp {
+        self.levels[self.depth()].first().copied().unwrap_or(self.empty[self.depth()])
+    }
+
+    /// Inclusion path of the `index`-th leaf.
+    pub fn path(&self, index: usize) -> FactPathFp {
+        let siblings = (0..self.depth())
+            .map(|i| {
+                let sib = (index >> i) ^ 1;
+                self.levels[i].get(sib).copied().unwrap_or(self.empty[i])
+            })
+            .collect();
+        FactPathFp { index: index as u64, siblings }
+    }
+}
+
+/// Root the path leads to from `leaf`, the native version of `MerkleChip::check_root`.
+pub fn root_from_path(leaf: Fp, path: &FactPathFp) -> Fp {
+    path.siblings.iter().enumerate().fold(leaf, |cur, (i, sib)| {
+        if (path.index >> i) & 1 == 1 { merkle_node(*sib, cur) } else { merkle_node(cur, *sib) }
+    })
+}
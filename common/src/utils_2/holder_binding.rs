@@ -0,0 +1,23 @@
+use halo2_proofs::pasta::{pallas, Fp};
+use halo2curves::group::{Curve, Group};
+
+use crate::utils_2::{
+    issuer_signature::{affine_xy, base_to_scalar},
+    off_circuit_poseidon::poseidon_hash2_native,
+};
+
+/// Holder keys, the facts of a holder can only be proven with its secret key:
+///
+/// - key: sk ∈ Fp (used as an integer below p < q), PK = sk·G
+/// - id:  Poseidon(PK.x, PK.y), the issuer hashes it into the facts of the holder
+///
+/// The circuit witnesses sk, recomputes the id and compares it with the one in the fact hash. A
+/// fact with id 0 is not bound to a holder.
+pub fn holder_public_key(sk: Fp) -> pallas::Affine {
+    (pallas::Point::generator() * base_to_scalar(sk)).to_affine()
+}
+
+pub fn holder_id(sk: Fp) -> Fp {
+    let [x, y] = affine_xy(&holder_public_key(sk));
+    poseidon_hash2_native(x, y)
+}
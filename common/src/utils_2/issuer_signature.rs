@@ -0,0 +1,72 @@
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    pasta::{pallas, Fp},
+};
+use halo2curves::{
+    ff::{Field, PrimeField},
+    group::{prime::PrimeCurveAffine, Curve, Group},
+};
+use rand_core::RngCore;
+
+use crate::{data::FactSignatureFp, utils_2::off_circuit_poseidon::poseidon_hash_list_native};
+
+/// Schnorr signatures of the issuer on the fact hashes, over Pallas (its base field is `Fp`):
+///
+/// - key:       sk ∈ Fq, PK = sk·G
+/// - signature: R = k·G, e = Poseidon(R.x, R.y, PK.x, PK.y, m), s = k + e·sk
+/// - check:     s·G == R + e·PK
+///
+/// e and s are used as integers below p (p < q), so the circuit can take them as base field
+/// elements; `sign_fact_hash` picks a new k until s is below p.
+pub fn issuer_public_key(sk: &pallas::Scalar) -> pallas::Affine {
+    (pallas::Point::generator() * sk).to_affine()
+}
+
+/// (x, y) of a point that is not the identity.
+pub fn affine_xy(p: &pallas::Affine) -> [Fp; 2] {
+    let c = p.coordinates().expect("identity has no coordinates");
+    [*c.x(), *c.y()]
+}
+
+/// The point (x, y), or the generator if it is not on the curve or is the identity ((0, 0) is
+/// accepted by `from_xy`). The circuit witnesses points this way, a bad point then fails the check
+/// instead of the synthesis.
+pub fn point_or_generator(xy: [Fp; 2]) -> pallas::Affine {
+    Option::from(pallas::Affine::from_xy(xy[0], xy[1]))
+        .filter(|p: &pallas::Affine| !bool::from(p.is_identity()))
+        .unwrap_or(pallas::Affine::generator())
+}
+
+/// e = Poseidon(R, PK, m), the same fold as `PoseidonHashChip::hash_list`.
+pub fn schnorr_challenge(r: [Fp; 2], pk: [Fp; 2], msg: Fp) -> Fp {
+    poseidon_hash_list_native(&[r[0], r[1], pk[0], pk[1], msg])
+}
+
+// p < q, every base field element is a scalar
+pub(crate) fn base_to_scalar(x: Fp) -> pallas::Scalar {
+    pallas::Scalar::from_repr(x.to_repr()).unwrap()
+}
+
+pub fn sign_fact_hash(sk: &pallas::Scalar, msg: Fp, mut rng: impl RngCore) -> FactSignatureFp {
+    let pk = affine_xy(&issuer_public_key(sk));
+    loop {
+        let k = pallas::Scalar::random(&mut rng);
+        let r = affine_xy(&(pallas::Point::generator() * k).to_affine());
+        let e = schnorr_challenge(r, pk, msg);
+        let s = k + base_to_scalar(e) * sk;
+        if let Some(s) = Option::<Fp>::from(Fp::from_repr(s.to_repr())) {
+            return FactSignatureFp { r, s };
+        }
+    }
+}
+
+pub fn verify_fact_signature(pk: [Fp; 2], msg: Fp, sig: &FactSignatureFp) -> bool {
+    let (Some(pk_point), Some(r_point)) = (
+        Option::<pallas::Affine>::from(pallas::Affine::from_xy(pk[0], pk[1])),
+        Option::<pallas::Affine>::from(pallas::Affine::from_xy(sig.r[0], sig.r[1])),
+    ) else {
+        return false;
+    };
+    let e = base_to_scalar(schnorr_challenge(sig.r, pk, msg));
+    pallas::Point::generator() * base_to_scalar(sig.s) == r_point.to_curve() + pk_point * e
+}
@@ -1,4 +1,9 @@
 pub mod consistency_helpers;
 pub mod common_helpers;
 pub mod predicate_helpers;
-pub mod off_circuit_poseidon;
\ No newline at end of file
+pub mod off_circuit_poseidon;
+pub mod term_encoding;
+pub mod issuer_signature;
+pub mod fact_registry;
+pub mod revocation;
+pub mod holder_binding;
@@ -3,11 +3,18 @@ use halo2_gadgets::poseidon::primitives::{
 };
 use halo2_proofs::pasta::Fp;
 
-use crate::utils_2::common_helpers::{MAX_ARITY, MAX_PRED_LIST, to_fp_value};
+use crate::{
+    chips::fact_check::compare_chip::i128_to_fp,
+    circuit_shape::CircuitShape,
+    data::{FactValidity, TermFp, UnificationInputFp},
+    term::Term,
+    unification_checker_circuit::flatten_term_args,
+    utils_2::term_encoding::encode_predicate,
+};
 
 
 #[inline]
-fn poseidon_hash2_native(a: Fp, b: Fp) -> Fp {
+pub fn poseidon_hash2_native(a: Fp, b: Fp) -> Fp {
     // This matches: Hash::<Fp, _, P128Pow5T3, ConstantLength<2>, 3, 2> in-circuit
     PoseidonHash::<Fp, P128Pow5T3, ConstantLength<2>, 3, 2>::init()
                 .hash([a, b])
@@ -24,45 +31,34 @@ pub fn poseidon_hash_list_native(values: &[Fp]) -> Fp {
     acc
 }
 
-/// Public function: hash(name, args, salt) exactly like the chip.
+/// Public function: hash(name, args, valid_from, valid_until, holder, salt) exactly like the chip.
 ///
 /// Inputs:
-/// - `name`: predicate/fact name (e.g. "parent")
-/// - `args`: predicate args as strings (e.g. ["alice","bob"])
-/// - `salt`: Fp salt (convert your BigUint→Fp off-chain the same way you do in-circuit)
+/// - `fact`: the fact as a term (e.g. `parent(alice,bob)`)
+/// - `salt`: the salt of the fact, random or given in facts.yaml (`io::holder::FACT_SALTS_PATH`)
+/// - `validity`: the validity period of facts.yaml (`FactEntry::validity`), signed field elements
+/// - `holder`: the holder id of facts.yaml (`FactEntry::holder_id`), 0 if the fact is not bound
+/// - `shape`: the args are encoded like the goals (`term_encoding::encode_args`), padded to
+///   `max_arity` lists of `max_pred_list` elements
 ///
 /// Output:
 /// - Fp hash identical to the chip’s Poseidon fold.
-pub fn fact_hash_native_salted(name: &str, args: &[&[&str]], salt: &str) -> Fp {
-    let mut tokens: Vec<Fp> = Vec::with_capacity(1 + MAX_PRED_LIST*MAX_ARITY + 1);
-
-    tokens.push(to_fp_value(name));
-    let mut used_lists = 0usize;
-    for arg_list in args.iter().take(MAX_ARITY) {
-        let mut filled = 0usize;
+pub fn fact_hash_native_salted(
+    fact: &Term,
+    salt: Fp,
+    validity: &FactValidity,
+    holder: Fp,
+    shape: &CircuitShape,
+) -> Fp {
+    let term = encode_predicate(fact, Fp::zero(), shape);
 
-        // elemek (legfeljebb MAX_ARITY)
-        for element in (*arg_list).iter().take(MAX_PRED_LIST) {
-            tokens.push(to_fp_value(element));
-            filled += 1;
-        }
-        // per-lista padding
-        while filled < MAX_PRED_LIST {
-            tokens.push(Fp::one().neg());
-            filled += 1;
-        }
-        
-        used_lists += 1;
-    }
-
-    // 3) hiányzó blokkok paddinggel (MAX_PRED_LIST-ig)
-    while used_lists < MAX_ARITY {
-        for _ in 0..MAX_PRED_LIST {
-            tokens.push(Fp::one().neg());
-        }
-        used_lists += 1;
-    }
-    tokens.push(to_fp_value(salt));
+    let mut tokens: Vec<Fp> = Vec::with_capacity(1 + shape.per_term() + 4);
+    tokens.push(term.name);
+    tokens.extend(term.args.into_iter().flatten());
+    tokens.push(i128_to_fp(validity.from.into()));
+    tokens.push(i128_to_fp(validity.until.into()));
+    tokens.push(holder);
+    tokens.push(salt);
     poseidon_hash_list_native(&tokens)
 }
 
@@ -73,4 +69,40 @@ pub fn fact_hash_native_term(name: &Fp, args: &[Fp]) -> Fp {
         tokens.push(*a);
     }
     poseidon_hash_list_native(&tokens)
-}
\ No newline at end of file
+}
+
+/// Native version of `GoalCommitChip::commit`: salted commitment to a node's goal terms,
+/// padded to `max_pred_list` terms with zero names and zero args like the circuit rows.
+pub fn goal_commitment_native(terms: &[TermFp], salt: Fp, shape: &CircuitShape) -> Fp {
+    let mut tokens = vec![salt];
+
+    for p in 0..shape.max_pred_list {
+        let (name, args) = match terms.get(p) {
+            Some(t) => (t.name, flatten_term_args(t, shape)),
+            None => (Fp::zero(), vec![Fp::zero(); shape.per_term()]),
+        };
+        tokens.push(name);
+        tokens.extend(args);
+    }
+
+    poseidon_hash_list_native(&tokens)
+}
+
+/// The `public_goal_commitments` column of one proof: head goal, then `max_children` child slots.
+pub fn goal_commitments_native(unif: &UnificationInputFp, shape: &CircuitShape) -> Vec<Fp> {
+    let mut out = vec![goal_commitment_native(&unif.goal_name, unif.goal_salt, shape)];
+    for i in 0..shape.max_children {
+        let terms = unif.subtree_goals.get(i).map(|t| t.as_slice()).unwrap_or(&[]);
+        let salt = unif.subtree_salts.get(i).copied().unwrap_or(Fp::zero());
+        out.push(goal_commitment_native(terms, salt, shape));
+    }
+    out
+}
+
+/// Child slots that need no proof of their own: an empty slot and a `true` leaf (both salt 0).
+pub fn leaf_goal_commitments(shape: &CircuitShape) -> [Fp; 2] {
+    [
+        goal_commitment_native(&[], Fp::zero(), shape),
+        goal_commitment_native(&[TermFp::true_leaf(shape)], Fp::zero(), shape),
+    ]
+}
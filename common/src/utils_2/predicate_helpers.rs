@@ -7,8 +7,8 @@ use halo2_proofs::{
 
 use crate::{
     chips::finding_rule::body_subtree_chip::UnifCompareConfig,
+    circuit_shape::CircuitShape,
     data::{PredicateTemplateFp, TermFp},
-    utils_2::common_helpers::{MAX_CANDIDATES, MAX_CHILDREN, MAX_PRED_LIST, MAX_PREDICATES_OVERALL},
 };
 
 
@@ -19,6 +19,7 @@ pub fn bind_proof_and_candidates_sig_pairs(
     goal_terms: &[TermFp],         // ✅ Vec<TermFp>
     subtree_terms: &[Vec<TermFp>], // ✅ Vec<Vec<TermFp>>
     rules: &[PredicateTemplateFp],
+    shape: &CircuitShape,
 ) -> Result<
     (
         Vec<Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>>,               // final_proof_pairs [rows][(name,arity)]
@@ -66,7 +67,7 @@ pub fn bind_proof_and_candidates_sig_pairs(
                 }
 
                 // padding a sor végén
-                while row.len() < MAX_PRED_LIST {
+                while row.len() < shape.max_pred_list {
                     let pn = region.assign_advice(
                         || "proof.goal.pad.name",
                         cfg.proof_pairs,
@@ -108,7 +109,7 @@ pub fn bind_proof_and_candidates_sig_pairs(
                 }
 
                 // padding a sor végén
-                while row.len() < MAX_PRED_LIST {
+                while row.len() < shape.max_pred_list {
                     let pn = region.assign_advice(
                         || "proof.sub.pad.name",
                         cfg.proof_pairs,
@@ -128,10 +129,10 @@ pub fn bind_proof_and_candidates_sig_pairs(
                 final_proof_pairs.push(row);
             }
 
-            // ── (opcionális) teljes proof rows padding MAX_CHILDREN-ig ──────────────
-            while final_proof_pairs.len() < MAX_PREDICATES_OVERALL {
+            // ── (opcionális) teljes proof rows padding max_predicates_overall-ig ──────────────
+            while final_proof_pairs.len() < shape.max_predicates_overall {
                 let mut row: Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)> = Vec::new();
-                for _ in 0..MAX_CHILDREN {
+                for _ in 0..shape.max_children {
                     let pn = region.assign_advice(
                         || "proof.total.pad.name",
                         cfg.proof_pairs,
@@ -178,7 +179,7 @@ pub fn bind_proof_and_candidates_sig_pairs(
                         head_row.push((hn, ha));
                         candidate_row_offset += 2;
 
-                        while head_row.len() < MAX_PRED_LIST {
+                        while head_row.len() < shape.max_pred_list {
                             let pn = region.assign_advice(
                                 || "cand.head.pad.name",
                                 cfg.candidate_pairs,
@@ -222,7 +223,7 @@ pub fn bind_proof_and_candidates_sig_pairs(
                         }
 
                         // OSZLOPOK (párok) paddingje a SOR VÉGÉN — UGYANÚGY, mint a proof-nál
-                        while r.len() < MAX_PRED_LIST {
+                        while r.len() < shape.max_pred_list {
                             let pn = region.assign_advice(
                                 || "cand.child.pad.name",
                                 cfg.candidate_pairs,
@@ -244,9 +245,9 @@ pub fn bind_proof_and_candidates_sig_pairs(
                     }
 
                     // ✅ MIUTÁN minden BODY sor bekerült: SOROK SZÁMÁNAK paddingje
-                    while rows.len() < MAX_PREDICATES_OVERALL {
+                    while rows.len() < shape.max_predicates_overall {
                         let mut empty_row: Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)> = Vec::new();
-                        for _ in 0..MAX_PRED_LIST {
+                        for _ in 0..shape.max_pred_list {
                             let pn = region.assign_advice(
                                 || "cand.rows.pad.name",
                                 cfg.candidate_pairs,
@@ -270,13 +271,13 @@ pub fn bind_proof_and_candidates_sig_pairs(
                 }
             }
 
-            // kandidátusok paddingje MAX_CANDIDATES-ig
-            while candidate_pairs_all.len() < MAX_CANDIDATES {
-                // üres jelölt: MAX_CHILDREN sor, soronként MAX_CHILDREN pár (mind 0)
+            // kandidátusok paddingje max_candidates-ig
+            while candidate_pairs_all.len() < shape.max_candidates() {
+                // üres jelölt: max_predicates_overall sor, soronként max_pred_list pár (mind 0)
                 let mut empty_rule: Vec<Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)>> = Vec::new();
-                for _ in 0..MAX_PREDICATES_OVERALL {
+                for _ in 0..shape.max_predicates_overall {
                     let mut row: Vec<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>)> = Vec::new();
-                    for _ in 0..MAX_PRED_LIST {
+                    for _ in 0..shape.max_pred_list {
                         let pn = region.assign_advice(
                             || "cand.full.pad.name",
                             cfg.candidate_pairs,
@@ -0,0 +1,74 @@
+use std::collections::{BTreeSet, HashMap};
+
+use halo2_proofs::pasta::Fp;
+use halo2curves::ff::PrimeField;
+
+use crate::{data::FactPathFp, utils_2::fact_registry::merkle_node};
+
+/// Levels of the revocation tree. The slot of a fact is the low REVOCATION_TREE_DEPTH bits of its
+/// hash, the circuit range checks the rest on 254 - REVOCATION_TREE_DEPTH bits, so it has to be a
+/// multiple of `RANGE_CHECK_LIMB_BITS` (hashes from 2^254 on can not be proven, ~2^-129 of them).
+pub const REVOCATION_TREE_DEPTH: usize = 30;
+
+/// Slot of a fact hash in the revocation tree.
+pub fn revocation_slot(fact_hash: &Fp) -> u64 {
+    let repr = fact_hash.to_repr();
+    let low = u64::from_le_bytes(repr.as_ref()[..8].try_into().unwrap());
+    low & ((1u64 << REVOCATION_TREE_DEPTH) - 1)
+}
+
+/// The issuer's revocation list as a sparse Poseidon Merkle tree: the leaf of a revoked slot is 1,
+/// every other leaf is 0. A fact is not revoked if the path of its slot leads from a 0 leaf to the
+/// public root. Two facts can share a slot (a live fact in a revoked slot could not be proven any
+/// more, the issuer gives it a new salt), but a revoked fact never has a 0 leaf.
+pub struct RevocationRegistry {
+    // non-empty nodes by (level, index), level 0 = leaves
+    nodes: HashMap<(usize, u64), Fp>,
+    // root of an empty subtree of height i
+    empty: Vec<Fp>,
+}
+
+impl RevocationRegistry {
+    pub fn new(revoked: &[Fp]) -> Self {
+        let mut empty = vec![Fp::zero()];
+        for _ in 0..REVOCATION_TREE_DEPTH {
+            let below = *empty.last().unwrap();
+            empty.push(merkle_node(below, below));
+        }
+
+        let mut nodes = HashMap::new();
+        let mut level: BTreeSet<u64> = revoked.iter().map(revocation_slot).collect();
+        for &slot in &level {
+            nodes.insert((0, slot), Fp::one());
+        }
+        for (i, &empty_child) in empty[..REVOCATION_TREE_DEPTH].iter().enumerate() {
+            let parents: BTreeSet<u64> = level.iter().map(|idx| idx >> 1).collect();
+            for &p in &parents {
+                let child = |c: u64| nodes.get(&(i, c)).copied().unwrap_or(empty_child);
+                let node = merkle_node(child(2 * p), child(2 * p + 1));
+                nodes.insert((i + 1, p), node);
+            }
+            level = parents;
+        }
+        Self { nodes, empty }
+    }
+
+    fn node(&self, level: usize, index: u64) -> Fp {
+        self.nodes.get(&(level, index)).copied().unwrap_or(self.empty[level])
+    }
+
+    pub fn root(&self) -> Fp {
+        self.node(REVOCATION_TREE_DEPTH, 0)
+    }
+
+    pub fn is_revoked(&self, fact_hash: &Fp) -> bool {
+        self.node(0, revocation_slot(fact_hash)) != Fp::zero()
+    }
+
+    /// Path of the slot of `fact_hash`, it proves non-revocation if `is_revoked` is false.
+    pub fn path(&self, fact_hash: &Fp) -> FactPathFp {
+        let index = revocation_slot(fact_hash);
+        let siblings = (0..REVOCATION_TREE_DEPTH).map(|i| self.node(i, (index >> i) ^ 1)).collect();
+        FactPathFp { index, siblings }
+    }
+}
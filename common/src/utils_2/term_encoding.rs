@@ -0,0 +1,240 @@
+use halo2_proofs::pasta::Fp;
+
+use crate::{
+    circuit_shape::CircuitShape,
+    data::TermFp,
+    fixed::{Fixed, SCALE},
+    term::Term,
+    utils_2::common_helpers::{to_fp_value, TypedValue},
+};
+
+/// Field value of a whole term by its type (`TypedValue`): lists other than `[]`, compounds and
+/// variables are hashed by their printed form, so `[2001,2000]` is one value.
+pub fn term_value(t: &Term) -> Fp {
+    match t {
+        Term::Number { value } => to_fp_value(TypedValue::Number(Fixed::from_int(*value))),
+        Term::Decimal { value } => to_fp_value(TypedValue::Number(*value)),
+        Term::Atom { name } => to_fp_value(TypedValue::Atom(name)),
+        Term::String { value } => to_fp_value(TypedValue::Str(value)),
+        Term::List { items, tail: None } if items.is_empty() => to_fp_value(TypedValue::Nil),
+        other => to_fp_value(TypedValue::Term(&other.to_string())),
+    }
+}
+
+/// Value of a predicate or operator name.
+pub fn name_value(name: &str) -> Fp {
+    to_fp_value(TypedValue::Atom(name))
+}
+
+/// Row of one argument in the args matrix, the same layout as the `list_index` of rules.json:
+///
+/// - scalar, variable, compound: `[value]`
+/// - `[]`:                       `[value("[]")]`
+/// - `[H|T]`:                    `[value(whole list), fields of H.., value(T)]`
+///
+/// where the fields of a `(a,b,c)` tuple head are `a, b, c`, otherwise the head itself.
+pub fn arg_row(t: &Term) -> Vec<Fp> {
+    let Term::List { items, tail } = t else {
+        return vec![term_value(t)];
+    };
+    let Some((head, rest)) = items.split_first() else {
+        return match tail {
+            Some(tail) => arg_row(tail),
+            None => vec![term_value(t)],
+        };
+    };
+
+    let rest = Term::List { items: rest.to_vec(), tail: tail.clone() };
+    let mut row = vec![term_value(t)];
+    row.extend(head.tuple_fields().into_iter().map(term_value));
+    row.push(term_value(&rest));
+    row
+}
+
+/// Args matrix of a predicate, padded with -1 (`TermFp::padded_args`). Whatever does not fit
+/// into the shape is cut off.
+pub fn encode_args(args: &[Term], shape: &CircuitShape) -> Vec<Vec<Fp>> {
+    args_matrix(args.iter().map(arg_row), shape)
+}
+
+fn args_matrix(rows: impl IntoIterator<Item = Vec<Fp>>, shape: &CircuitShape) -> Vec<Vec<Fp>> {
+    let mut matrix = TermFp::padded_args(shape);
+    for (row, values) in matrix.iter_mut().zip(rows) {
+        for (cell, v) in row.iter_mut().zip(values) {
+            *cell = v;
+        }
+    }
+    matrix
+}
+
+/// A goal as one `TermFp`: `fact_hashes` is the salt of the fact it was proven by (0 for rules).
+pub fn encode_predicate(goal: &Term, salt: Fp, shape: &CircuitShape) -> TermFp {
+    let (name, args) = match goal {
+        Term::Compound { name, args } => (name.as_str(), args.as_slice()),
+        Term::Atom { name } => (name.as_str(), &[][..]),
+        other => return TermFp { name: term_value(other), args: TermFp::padded_args(shape), fact_hashes: salt },
+    };
+    TermFp { name: name_value(name), args: encode_args(args, shape), fact_hashes: salt }
+}
+
+/// Relations checked by the built-in chip, `Some(true)` if their sides are evaluated.
+pub fn builtin_relation(name: &str) -> Option<bool> {
+    match name {
+        "is" | "=:=" | "=\\=" | "<" | ">" | "=<" | ">=" => Some(true),
+        "=" | "\\=" | "==" | "\\==" => Some(false),
+        _ => None,
+    }
+}
+
+/// Arithmetic operators the built-in chip evaluates, anything else is a leaf of the expression.
+pub fn arith_op(name: &str) -> bool {
+    matches!(name, "+" | "-" | "*" | "/" | "div" | "//" | "mod" | "rem")
+}
+
+/// Operand of a relation or of an arithmetic node.
+#[derive(Debug, Clone, Copy)]
+pub enum Operand<'a> {
+    /// A term taken as it is: variable, number, atom, or a term that is not arithmetic
+    Leaf(&'a Term),
+    /// The result of `nodes[i]`
+    Node(usize),
+}
+
+/// `left op right`, `term` is the subterm it stands for.
+#[derive(Debug, Clone)]
+pub struct ArithNode<'a> {
+    pub op: &'a str,
+    pub left: Operand<'a>,
+    pub right: Operand<'a>,
+    pub term: &'a Term,
+}
+
+/// A built-in goal as an expression tree: the relation with its two operands and the
+/// arithmetic nodes in post-order (left side first), so a node only uses earlier nodes.
+///
+/// `X is (A+B)*C` → `is(X, #1)`, `#0 = +(A, B)`, `#1 = *(#0, C)`.
+///
+/// Only `is` and the arithmetic comparisons are evaluated, the sides of `=`, `\=`, `==`, `\==`
+/// are leaves compared by value.
+#[derive(Debug, Clone)]
+pub struct BuiltinLayout<'a> {
+    pub rel: &'a str,
+    pub lhs: Operand<'a>,
+    pub rhs: Operand<'a>,
+    pub nodes: Vec<ArithNode<'a>>,
+}
+
+pub fn builtin_layout(goal: &Term) -> Option<BuiltinLayout<'_>> {
+    let Term::Compound { name, args } = goal else {
+        return None;
+    };
+    let [lhs, rhs] = args.as_slice() else {
+        return None;
+    };
+    let evaluated = builtin_relation(name)?;
+
+    let mut nodes = Vec::new();
+    let (lhs, rhs) = if evaluated {
+        let lhs = expr_operand(lhs, &mut nodes);
+        (lhs, expr_operand(rhs, &mut nodes))
+    } else {
+        (Operand::Leaf(lhs), Operand::Leaf(rhs))
+    };
+    Some(BuiltinLayout { rel: name, lhs, rhs, nodes })
+}
+
+fn expr_operand<'a>(t: &'a Term, nodes: &mut Vec<ArithNode<'a>>) -> Operand<'a> {
+    match t {
+        Term::Compound { name, args } if args.len() == 2 && arith_op(name) => {
+            let left = expr_operand(&args[0], nodes);
+            let right = expr_operand(&args[1], nodes);
+            nodes.push(ArithNode { op: name, left, right, term: t });
+            Operand::Node(nodes.len() - 1)
+        }
+        other => Operand::Leaf(other),
+    }
+}
+
+/// `is/2` or a comparison, the goals that are checked by the built-in chip.
+pub fn is_builtin_goal(goal: &Term) -> bool {
+    builtin_layout(goal).is_some()
+}
+
+// The numbers are fixed-point (`Fixed`), computed the way the built-in chip does it: `*` and `/`
+// are rounded by `fixed::ROUNDING`; div, //, mod, rem divide the units with SWI-Prolog's integer
+// semantics (div and mod round towards -inf, // and rem towards 0), exact for integers.
+fn apply_op(op: &str, x: Fixed, y: Fixed) -> Option<Fixed> {
+    let (a, b) = (x.units(), y.units());
+    match op {
+        "+" => x.checked_add(y),
+        "-" => x.checked_sub(y),
+        "*" => x.checked_mul(y),
+        "/" => x.checked_div(y),
+        "//" => a.checked_div(b)?.checked_mul(SCALE).map(Fixed::from_units),
+        "rem" => a.checked_rem(b).map(Fixed::from_units),
+        "div" => {
+            let q = a.checked_div(b)?;
+            let q = if a % b != 0 && (a < 0) != (b < 0) { q - 1 } else { q };
+            q.checked_mul(SCALE).map(Fixed::from_units)
+        }
+        "mod" => {
+            let r = a.checked_rem(b)?;
+            Some(Fixed::from_units(if r != 0 && (r < 0) != (b < 0) { r + b } else { r }))
+        }
+        _ => None,
+    }
+}
+
+impl BuiltinLayout<'_> {
+    /// Field values of the node results. A node that can not be evaluated (unbound or not a
+    /// number) gets the value of its printed subterm, the circuit rejects it then.
+    pub fn node_values(&self) -> Vec<Fp> {
+        let mut nums: Vec<Option<Fixed>> = Vec::with_capacity(self.nodes.len());
+        for n in &self.nodes {
+            let num = |o: &Operand| match o {
+                Operand::Leaf(Term::Number { value }) => Some(Fixed::from_int(*value)),
+                Operand::Leaf(Term::Decimal { value }) => Some(*value),
+                Operand::Leaf(_) => None,
+                Operand::Node(i) => nums[*i],
+            };
+            let v = num(&n.left).zip(num(&n.right)).and_then(|(x, y)| apply_op(n.op, x, y));
+            nums.push(v);
+        }
+        self.nodes
+            .iter()
+            .zip(nums)
+            .map(|(n, v)| match v {
+                Some(value) => to_fp_value(TypedValue::Number(value)),
+                None => term_value(n.term),
+            })
+            .collect()
+    }
+}
+
+/// A built-in goal as rules.json lays it out (`BuiltinLayout`): `rel(lhs, rhs)` and then one
+/// `op(left, right, result)` per arithmetic node, an operand that is a node is its result.
+pub fn encode_builtin(goal: &Term, shape: &CircuitShape) -> Vec<TermFp> {
+    let Some(layout) = builtin_layout(goal) else {
+        return vec![encode_predicate(goal, Fp::zero(), shape)];
+    };
+    let values = layout.node_values();
+    let row = |o: &Operand| match o {
+        Operand::Leaf(t) => arg_row(t),
+        Operand::Node(i) => vec![values[*i]],
+    };
+    let term = |name: &str, rows: Vec<Vec<Fp>>| TermFp {
+        name: name_value(name),
+        args: args_matrix(rows, shape),
+        fact_hashes: Fp::zero(),
+    };
+
+    std::iter::once(term(layout.rel, vec![row(&layout.lhs), row(&layout.rhs)]))
+        .chain(
+            layout
+                .nodes
+                .iter()
+                .zip(&values)
+                .map(|(n, v)| term(n.op, vec![row(&n.left), row(&n.right), vec![*v]])),
+        )
+        .collect()
+}
@@ -0,0 +1,484 @@
+use anyhow::{bail, Result};
+use common::{
+    data::{GoalEntry, ProofNode},
+    fixed::Fixed,
+};
+
+use crate::{
+    program::{Clause, Program},
+    term::{to_json, Term},
+};
+
+// Deepest goal nesting before giving up (left recursion would never stop)
+const MAX_DEPTH: usize = 10_000;
+
+/// Value of an arithmetic expression.
+#[derive(Debug, Clone, Copy)]
+enum Num {
+    Int(i64),
+    Dec(Fixed),
+}
+
+impl Num {
+    fn fixed(self) -> Fixed {
+        match self {
+            Num::Int(n) => Fixed::from_int(n),
+            Num::Dec(d) => d,
+        }
+    }
+
+    fn term(self) -> Term {
+        match self {
+            Num::Int(n) => Term::Int(n),
+            Num::Dec(d) => Term::Dec(d),
+        }
+    }
+}
+
+/// Proof tree of `meta_interpreter.pl`: a goal with the trees of its body, or `true`.
+#[derive(Debug, Clone)]
+pub enum Node {
+    True,
+    Goal { goal: Term, subtree: Vec<Node> },
+}
+
+// Called with every proof of a goal; `Ok(true)` stops the search (the bindings are kept).
+type Cont<'a> = dyn FnMut(&mut Machine, Vec<Node>) -> Result<bool> + 'a;
+
+#[derive(Clone, Copy)]
+struct Mark {
+    trail: usize,
+    vars: usize,
+}
+
+/// SLD resolution with chronological backtracking, in the order of `meta_interpreter.pl`:
+///
+/// - `true` is `[true]`
+/// - `(A, B)` appends the trees of A and B, and commits to its first proof (the cut after `append`)
+/// - `(A ; B)` is the tree of A, then the tree of B on backtracking
+/// - a built-in is `[state{goal, subtree:[true]}]`, it is called without recording a tree; `!` is
+///   a built-in too, `call(!)` succeeds without cutting anything
+/// - otherwise every matching clause in source order: `[state{goal, subtree:BodyTree}]`
+///
+/// so the first proof gives the same tree as `prove(Goal, [Tree])` in SWI-Prolog. The database is
+/// flat: modules and their export lists are not modeled.
+pub struct Machine {
+    program: Program,
+    bindings: Vec<Option<Term>>,
+    trail: Vec<usize>,
+}
+
+impl Machine {
+    pub fn new(program: Program) -> Self {
+        Self { program, bindings: Vec::new(), trail: Vec::new() }
+    }
+
+    /// First proof of `goal` (its variables are `Var(0..vars)`), as `proof_tree.json` nodes.
+    pub fn prove_first(&mut self, goal: &Term, vars: usize) -> Result<Option<Vec<ProofNode>>> {
+        self.bindings.resize(vars, None);
+        let mut found = None;
+        self.prove(goal, 0, &mut |_, tree| {
+            found = Some(tree);
+            Ok(true)
+        })?;
+        Ok(found.map(|tree| tree.iter().map(|n| self.proof_node(n)).collect()))
+    }
+
+    fn proof_node(&self, n: &Node) -> ProofNode {
+        match n {
+            Node::True => ProofNode::True(true),
+            Node::Goal { goal, subtree } => ProofNode::GoalNode(GoalEntry {
+                goal: to_json(&self.resolve(goal)),
+                subtree: subtree.iter().map(|c| self.proof_node(c)).collect(),
+            }),
+        }
+    }
+
+    // ------------------ Resolution ------------------
+
+    fn prove(&mut self, goal: &Term, depth: usize, k: &mut Cont) -> Result<bool> {
+        if depth > MAX_DEPTH {
+            bail!("Depth limit ({MAX_DEPTH}) exceeded at {}", self.resolve(goal));
+        }
+        let goal = self.walk(goal);
+        match &goal {
+            Term::Var(_) => bail!("Arguments are not sufficiently instantiated"),
+            Term::Int(_) | Term::Dec(_) | Term::Str(_) => bail!("Type error: callable expected, found {goal}"),
+            Term::Atom(a) if a == "true" => k(self, vec![Node::True]),
+            Term::Compound(f, args) if f == "," && args.len() == 2 => self.prove_conj(&args[0], &args[1], depth, k),
+            Term::Compound(f, args) if f == ";" && args.len() == 2 => {
+                if self.prove(&args[0], depth + 1, k)? {
+                    return Ok(true);
+                }
+                self.prove(&args[1], depth + 1, k)
+            }
+            _ => {
+                let (name, arity) = goal.functor().unwrap();
+                if is_builtin(name, arity) {
+                    self.prove_builtin(&goal, depth, k)
+                } else {
+                    self.prove_user(&goal, depth, k)
+                }
+            }
+        }
+    }
+
+    fn prove_conj(&mut self, a: &Term, b: &Term, depth: usize, k: &mut Cont) -> Result<bool> {
+        let mark = self.mark();
+        let mut found = None;
+        self.prove(a, depth + 1, &mut |m, tree_a| {
+            m.prove(b, depth + 1, &mut |_, tree_b| {
+                found = Some([tree_a.clone(), tree_b].concat());
+                Ok(true)
+            })
+        })?;
+
+        // No other proof is tried after the first one
+        match found {
+            Some(tree) => {
+                if k(self, tree)? {
+                    return Ok(true);
+                }
+                self.undo(mark);
+                Ok(false)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn prove_user(&mut self, goal: &Term, depth: usize, k: &mut Cont) -> Result<bool> {
+        let (name, arity) = goal.functor().unwrap();
+        let Some(clauses) = self.program.predicates.get(&(name.to_string(), arity)).cloned() else {
+            bail!("Unknown procedure: {name}/{arity}");
+        };
+
+        for clause in clauses.iter() {
+            let mark = self.mark();
+            let (head, body) = self.rename(clause);
+            if self.unify(goal, &head) {
+                let stop = self.prove(&body, depth + 1, &mut |m, tree| {
+                    k(m, vec![Node::Goal { goal: goal.clone(), subtree: tree }])
+                })?;
+                if stop {
+                    return Ok(true);
+                }
+            }
+            self.undo(mark);
+        }
+        Ok(false)
+    }
+
+    fn prove_builtin(&mut self, goal: &Term, depth: usize, k: &mut Cont) -> Result<bool> {
+        let leaf = vec![Node::Goal { goal: goal.clone(), subtree: vec![Node::True] }];
+        let mark = self.mark();
+        let Term::Compound(name, args) = goal else {
+            // !, fail, false
+            if goal == &Term::atom("!") && k(self, leaf)? {
+                return Ok(true);
+            }
+            return Ok(false);
+        };
+
+        let ok = match (name.as_str(), args.as_slice()) {
+            ("\\+", [g]) => {
+                let found = self.prove(g, depth + 1, &mut |_, _| Ok(true))?;
+                self.undo(mark);
+                !found
+            }
+            ("call", [g]) => return self.prove(g, depth + 1, &mut |m, _| k(m, leaf.clone())),
+            ("->", [cond, then]) => {
+                if !self.prove(cond, depth + 1, &mut |_, _| Ok(true))? {
+                    return Ok(false);
+                }
+                let stop = self.prove(then, depth + 1, &mut |m, _| k(m, leaf.clone()))?;
+                if !stop {
+                    self.undo(mark);
+                }
+                return Ok(stop);
+            }
+            ("=", [a, b]) => self.unify(a, b),
+            ("\\=", [a, b]) => {
+                let unifies = self.unify(a, b);
+                self.undo(mark);
+                !unifies
+            }
+            ("==", [a, b]) => self.resolve(a) == self.resolve(b),
+            ("\\==", [a, b]) => self.resolve(a) != self.resolve(b),
+            ("is", [a, b]) => {
+                let v = self.eval(b)?;
+                self.unify(a, &v.term())
+            }
+            (op, [a, b]) => {
+                let (x, y) = (self.eval(a)?.fixed(), self.eval(b)?.fixed());
+                match op {
+                    "=:=" => x == y,
+                    "=\\=" => x != y,
+                    "<" => x < y,
+                    ">" => x > y,
+                    "=<" => x <= y,
+                    ">=" => x >= y,
+                    _ => unreachable!("is_builtin"),
+                }
+            }
+            _ => unreachable!("is_builtin"),
+        };
+
+        if ok && k(self, leaf)? {
+            return Ok(true);
+        }
+        self.undo(mark);
+        Ok(false)
+    }
+
+    // ------------------ Arithmetic ------------------
+
+    fn eval(&self, t: &Term) -> Result<Num> {
+        let overflow = || anyhow::anyhow!("Arithmetic overflow in {}", self.resolve(t));
+        match self.walk(t) {
+            Term::Int(n) => Ok(Num::Int(n)),
+            Term::Dec(d) => Ok(Num::Dec(d)),
+            Term::Var(_) => bail!("Arguments are not sufficiently instantiated"),
+            Term::Compound(f, args) if args.len() == 1 => {
+                let x = self.eval(&args[0])?;
+                let neg = |x: Num| match x {
+                    Num::Int(n) => n.checked_neg().map(Num::Int),
+                    Num::Dec(d) => d.checked_neg().map(Num::Dec),
+                };
+                match f.as_str() {
+                    "-" => neg(x).ok_or_else(overflow),
+                    "+" => Ok(x),
+                    "abs" if x.fixed() < Fixed::from_int(0) => neg(x).ok_or_else(overflow),
+                    "abs" => Ok(x),
+                    _ => bail!("Arithmetic: {f}/1 is not a function"),
+                }
+            }
+            Term::Compound(f, args) if args.len() == 2 => {
+                let (x, y) = (self.eval(&args[0])?, self.eval(&args[1])?);
+                if y.fixed() == Fixed::from_int(0) && matches!(f.as_str(), "/" | "//" | "div" | "mod" | "rem") {
+                    bail!("Arithmetic: evaluation error: zero_divisor");
+                }
+                // integers stay integers, with a decimal the result is a decimal (rounded as the circuit does)
+                let v = match (f.as_str(), x, y) {
+                    ("+", Num::Int(a), Num::Int(b)) => a.checked_add(b).map(Num::Int),
+                    ("+", ..) => x.fixed().checked_add(y.fixed()).map(Num::Dec),
+                    ("-", Num::Int(a), Num::Int(b)) => a.checked_sub(b).map(Num::Int),
+                    ("-", ..) => x.fixed().checked_sub(y.fixed()).map(Num::Dec),
+                    ("*", Num::Int(a), Num::Int(b)) => a.checked_mul(b).map(Num::Int),
+                    ("*", ..) => x.fixed().checked_mul(y.fixed()).map(Num::Dec),
+                    // an inexact integer division is a float in SWI-Prolog
+                    ("/", Num::Int(a), Num::Int(b)) if a.checked_rem(b) == Some(0) => a.checked_div(b).map(Num::Int),
+                    ("/", ..) => x.fixed().checked_div(y.fixed()).map(Num::Dec),
+                    ("//", Num::Int(a), Num::Int(b)) => a.checked_div(b).map(Num::Int),
+                    ("rem", Num::Int(a), Num::Int(b)) => a.checked_rem(b).map(Num::Int),
+                    // rounded towards -inf, the remainder has the sign of the divisor
+                    ("div", Num::Int(a), Num::Int(b)) => a
+                        .checked_div(b)
+                        .map(|q| Num::Int(if a % b != 0 && (a < 0) != (b < 0) { q - 1 } else { q })),
+                    ("mod", Num::Int(a), Num::Int(b)) => a
+                        .checked_rem(b)
+                        .map(|r| Num::Int(if r != 0 && (r < 0) != (b < 0) { r + b } else { r })),
+                    ("//" | "rem" | "div" | "mod", ..) => {
+                        bail!("Type error: integer expected in {}", self.resolve(t))
+                    }
+                    ("min", ..) => Some(if y.fixed() < x.fixed() { y } else { x }),
+                    ("max", ..) => Some(if y.fixed() > x.fixed() { y } else { x }),
+                    _ => bail!("Arithmetic: {f}/2 is not a function"),
+                };
+                v.ok_or_else(overflow)
+            }
+            other => bail!("Arithmetic: {other} is not a function"),
+        }
+    }
+
+    // ------------------ Bindings ------------------
+
+    fn mark(&self) -> Mark {
+        Mark { trail: self.trail.len(), vars: self.bindings.len() }
+    }
+
+    fn undo(&mut self, mark: Mark) {
+        for v in self.trail.drain(mark.trail..) {
+            self.bindings[v] = None;
+        }
+        self.bindings.truncate(mark.vars);
+    }
+
+    fn rename(&mut self, clause: &Clause) -> (Term, Term) {
+        let offset = self.bindings.len();
+        self.bindings.resize(offset + clause.vars, None);
+        let body = Term::conjunction(clause.body.iter().map(|g| shift(g, offset)).collect());
+        (shift(&clause.head, offset), body)
+    }
+
+    fn walk(&self, t: &Term) -> Term {
+        let mut t = t;
+        while let Term::Var(v) = t {
+            match &self.bindings[*v] {
+                Some(b) => t = b,
+                None => break,
+            }
+        }
+        t.clone()
+    }
+
+    fn resolve(&self, t: &Term) -> Term {
+        match self.walk(t) {
+            Term::Compound(f, args) => Term::Compound(f, args.iter().map(|a| self.resolve(a)).collect()),
+            other => other,
+        }
+    }
+
+    // No occurs check, like SWI-Prolog's default
+    fn unify(&mut self, a: &Term, b: &Term) -> bool {
+        let (a, b) = (self.walk(a), self.walk(b));
+        match (&a, &b) {
+            (Term::Var(x), Term::Var(y)) if x == y => true,
+            (Term::Var(x), _) => {
+                self.bind(*x, b);
+                true
+            }
+            (_, Term::Var(y)) => {
+                self.bind(*y, a);
+                true
+            }
+            (Term::Int(x), Term::Int(y)) => x == y,
+            (Term::Dec(x), Term::Dec(y)) => x == y,
+            (Term::Str(x), Term::Str(y)) => x == y,
+            (Term::Atom(x), Term::Atom(y)) => x == y,
+            (Term::Compound(f, xs), Term::Compound(g, ys)) => {
+                f == g && xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| self.unify(x, y))
+            }
+            _ => false,
+        }
+    }
+
+    fn bind(&mut self, v: usize, t: Term) {
+        self.bindings[v] = Some(t);
+        self.trail.push(v);
+    }
+}
+
+fn shift(t: &Term, offset: usize) -> Term {
+    match t {
+        Term::Var(v) => Term::Var(v + offset),
+        Term::Compound(f, args) => Term::Compound(f.clone(), args.iter().map(|a| shift(a, offset)).collect()),
+        other => other.clone(),
+    }
+}
+
+// The SWI-Prolog built-ins that are evaluated (`predicate_property(Goal, built_in)`)
+fn is_builtin(name: &str, arity: usize) -> bool {
+    matches!(
+        (name, arity),
+        ("!" | "fail" | "false", 0)
+            | ("\\+" | "call", 1)
+            | ("->" | "=" | "\\=" | "==" | "\\==" | "is" | "=:=" | "=\\=" | "<" | ">" | "=<" | ">=", 2)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::program::parse_goal;
+
+    // tests/testN: the facts and the policy in the consult order of main.pl
+    fn load_test(n: usize) -> Program {
+        let dir = format!("{}/../tests/test{n}", env!("CARGO_MANIFEST_DIR"));
+        let mut program = Program::default();
+        for file in ["input.pl", "policy.pl"] {
+            program.add_source(&fs::read_to_string(format!("{dir}/{file}")).unwrap()).unwrap();
+        }
+        program
+    }
+
+    fn load_src(src: &str) -> Program {
+        let mut program = Program::default();
+        program.add_source(src).unwrap();
+        program
+    }
+
+    fn prove(program: Program, goal: &str) -> Option<Value> {
+        let (goal, scope) = parse_goal(goal).unwrap();
+        let tree = Machine::new(program).prove_first(&goal, scope.len()).unwrap();
+        tree.map(|t| serde_json::to_value(t).unwrap())
+    }
+
+    // What prolog/main.pl wrote for the goal of tests/testN/main.pl
+    fn known_good(n: usize) -> Value {
+        let path = format!("{}/../tests/test{n}/proof_tree.json", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn atom(name: &str) -> Value {
+        json!({"type": "atom", "name": name})
+    }
+
+    fn num(value: i64) -> Value {
+        json!({"type": "number", "value": value})
+    }
+
+    fn compound(name: &str, args: Vec<Value>) -> Value {
+        json!({"type": "compound", "name": name, "args": args})
+    }
+
+    // a fact or a built-in: `state{goal, subtree:[true]}`
+    fn leaf(goal: Value) -> Value {
+        json!({"goal": goal, "subtree": [true]})
+    }
+
+    #[test]
+    fn same_tree_as_the_meta_interpreter() {
+        let cases = [
+            (1, "ancestor(alice, bob)"),
+            (2, "ancestor(alice, laura)"),
+            (3, "ancestor(alice, dora)"),
+            (7, "transactions_completed(alice, bob)"),
+            (17, "deepwide(alice, dora)"),
+        ];
+        for (n, goal) in cases {
+            assert_eq!(prove(load_test(n), goal), Some(known_good(n)), "tests/test{n}: {goal}");
+        }
+    }
+
+    #[test]
+    fn failing_goals_have_no_proof() {
+        assert_eq!(prove(load_test(3), "ancestor(dora, alice)"), None);
+        assert_eq!(prove(load_test(7), "transactions_completed(bob, alice)"), None);
+        assert_eq!(prove(load_test(17), "deepwide(alice, jack)"), None);
+        assert_eq!(prove(load_src("p(X) :- X > 1.\n"), "p(0)"), None);
+    }
+
+    #[test]
+    fn builtins_are_leaves() {
+        let program = load_src("price(X, Y) :- Y is X * 2, Y > 10, \\+ banned(X).\nbanned(3).\n");
+        let expected = json!([{
+            "goal": compound("price", vec![num(7), num(14)]),
+            "subtree": [
+                leaf(compound("is", vec![num(14), compound("*", vec![num(7), num(2)])])),
+                leaf(compound(">", vec![num(14), num(10)])),
+                leaf(compound("\\+", vec![compound("banned", vec![num(7)])])),
+            ],
+        }]);
+        assert_eq!(prove(program, "price(7, Y)"), Some(expected));
+        assert_eq!(prove(load_src("price(X, Y) :- Y is X * 2, Y > 10.\n"), "price(5, Y)"), None);
+    }
+
+    // `call(!)` in meta_interpreter.pl cuts nothing: s(1) is retried after the cut
+    #[test]
+    fn cut_does_not_prune() {
+        let program = load_src("r(X) :- s(X), !, X > 1.\ns(1).\ns(2).\n");
+        let expected = json!([{
+            "goal": compound("r", vec![num(2)]),
+            "subtree": [
+                {"goal": compound("s", vec![num(2)]), "subtree": [true]},
+                leaf(atom("!")),
+                leaf(compound(">", vec![num(2), num(1)])),
+            ],
+        }]);
+        assert_eq!(prove(program, "r(X)"), Some(expected));
+    }
+}
@@ -0,0 +1,57 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+
+mod engine;
+mod program;
+mod term;
+
+use engine::Machine;
+use program::{parse_goal, Program};
+
+// The MNB case study: prolog/main.pl proves this goal with these modules
+const DEFAULT_GOAL: &str = "endPrice(931220)";
+const DEFAULT_SOURCES: [&str; 3] = ["prolog/policy.pl", "prolog/matrix.pl", "prolog/input.pl"];
+
+// Deep proofs recurse deeply, the main thread's stack is not enough for them
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// cargo run -p interpreter --release -- [GOAL [FILE...]]
+fn main() -> Result<()> {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)?
+        .join()
+        .map_err(|_| anyhow::anyhow!("The interpreter thread panicked"))?
+}
+
+fn run() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let goal_text = args.first().map(String::as_str).unwrap_or(DEFAULT_GOAL);
+    let sources: Vec<&str> = if args.len() > 1 {
+        args[1..].iter().map(String::as_str).collect()
+    } else {
+        DEFAULT_SOURCES.to_vec()
+    };
+
+    // Policy + facts, in consult order
+    let mut program = Program::default();
+    for path in &sources {
+        let src = fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+        program.add_source(&src).with_context(|| format!("Failed to load {path}"))?;
+    }
+
+    let (goal, scope) = parse_goal(goal_text)?;
+    let mut machine = Machine::new(program);
+    let Some(tree) = machine.prove_first(&goal, scope.len())? else {
+        bail!("No proof could be found for {goal_text}");
+    };
+
+    // Same document as `tree_json([Tree], Json)` in prolog/main.pl
+    let out_path = Path::new("input/proof_tree.json");
+    fs::create_dir_all(out_path.parent().unwrap())?;
+    fs::write(out_path, serde_json::to_string_pretty(&tree)?)?;
+
+    println!("Proof tree of {goal_text} saved to {}", out_path.display());
+    Ok(())
+}
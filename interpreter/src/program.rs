@@ -0,0 +1,57 @@
+use std::{collections::HashMap, rc::Rc};
+
+use anyhow::{bail, Context, Result};
+use tokens::ast;
+
+use crate::term::{from_json, Term, VarScope};
+
+#[derive(Debug, Clone)]
+pub struct Clause {
+    pub head: Term,
+    pub body: Vec<Term>,
+    /// Variables of the clause are `Var(0..vars)`
+    pub vars: usize,
+}
+
+/// Clauses by (name, arity), in source order.
+#[derive(Default)]
+pub struct Program {
+    pub predicates: HashMap<(String, usize), Rc<Vec<Clause>>>,
+}
+
+impl Program {
+    /// Every file goes through the `tokens` parser, the clauses are appended in file order.
+    pub fn add_source(&mut self, src: &str) -> Result<()> {
+        for c in ast::parse_clauses(src) {
+            let clause = from_ast(&c).with_context(|| format!("In the clause of {}", ast::term_as_string(&c.head)))?;
+            let Some((name, arity)) = clause.head.functor() else {
+                bail!("Clause head is not callable: {}", clause.head);
+            };
+            let key = (name.to_string(), arity);
+            Rc::make_mut(self.predicates.entry(key).or_default()).push(clause);
+        }
+        Ok(())
+    }
+}
+
+/// The goal is parsed as a fact (`goal.`), so it is read the same way as the program.
+pub fn parse_goal(goal: &str) -> Result<(Term, VarScope)> {
+    let clauses = ast::parse_clauses(&format!("{}.", goal.trim().trim_end_matches('.')));
+    let [c] = clauses.as_slice() else {
+        bail!("Expected exactly one goal: {goal}");
+    };
+    let mut scope = VarScope::default();
+    let t = convert(&c.head, &mut scope)?;
+    Ok((t, scope))
+}
+
+pub fn from_ast(c: &ast::Clause) -> Result<Clause> {
+    let mut scope = VarScope::default();
+    let head = convert(&c.head, &mut scope)?;
+    let body = c.body.iter().map(|t| convert(t, &mut scope)).collect::<Result<Vec<_>>>()?;
+    Ok(Clause { head, body, vars: scope.len() })
+}
+
+fn convert(t: &ast::Term, scope: &mut VarScope) -> Result<Term> {
+    Ok(from_json(&ast::to_term(t)?, scope))
+}
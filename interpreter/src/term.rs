@@ -0,0 +1,130 @@
+use std::{collections::HashMap, fmt};
+
+use common::{fixed::Fixed, term as json};
+
+pub const NIL: &str = "[]";
+// SWI-Prolog 7 list constructor
+pub const CONS: &str = "[|]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Atom(String),
+    Int(i64),
+    /// A float, as the fixed-point number the circuit computes with
+    Dec(Fixed),
+    Str(String),
+    /// Index into the machine's bindings (clause local before renaming)
+    Var(usize),
+    Compound(String, Vec<Term>),
+}
+
+impl Term {
+    pub fn atom(name: &str) -> Self {
+        Term::Atom(name.to_string())
+    }
+
+    pub fn list(items: Vec<Term>, tail: Term) -> Self {
+        items
+            .into_iter()
+            .rev()
+            .fold(tail, |t, h| Term::Compound(CONS.to_string(), vec![h, t]))
+    }
+
+    /// Right nested `','/2` of the goals, `true` if there are none.
+    pub fn conjunction(goals: Vec<Term>) -> Self {
+        let mut it = goals.into_iter().rev();
+        match it.next() {
+            None => Term::atom("true"),
+            Some(last) => it.fold(last, |t, g| Term::Compound(",".to_string(), vec![g, t])),
+        }
+    }
+
+    pub fn functor(&self) -> Option<(&str, usize)> {
+        match self {
+            Term::Atom(a) => Some((a, 0)),
+            Term::Compound(f, args) => Some((f, args.len())),
+            _ => None,
+        }
+    }
+}
+
+/// Variables of one clause by name, every `_` is a new variable.
+#[derive(Default)]
+pub struct VarScope {
+    names: HashMap<String, usize>,
+    next: usize,
+}
+
+impl VarScope {
+    pub fn var(&mut self, name: &str) -> Term {
+        if name == "_" {
+            self.next += 1;
+            return Term::Var(self.next - 1);
+        }
+        let next = &mut self.next;
+        let v = *self.names.entry(name.to_string()).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        });
+        Term::Var(v)
+    }
+
+    pub fn len(&self) -> usize {
+        self.next
+    }
+}
+
+pub fn from_json(t: &json::Term, scope: &mut VarScope) -> Term {
+    match t {
+        json::Term::Atom { name } => Term::Atom(name.clone()),
+        json::Term::Number { value } => Term::Int(*value),
+        json::Term::Decimal { value } => Term::Dec(*value),
+        json::Term::String { value } => Term::Str(value.clone()),
+        json::Term::Var { name } => scope.var(name),
+        json::Term::Compound { name, args } => {
+            Term::Compound(name.clone(), args.iter().map(|a| from_json(a, scope)).collect())
+        }
+        json::Term::List { items, tail } => {
+            let tail = tail.as_ref().map_or(Term::atom(NIL), |t| from_json(t, scope));
+            Term::list(items.iter().map(|i| from_json(i, scope)).collect(), tail)
+        }
+    }
+}
+
+/// The proof_tree.json form, unbound variables are `_G<index>`.
+pub fn to_json(t: &Term) -> json::Term {
+    match t {
+        Term::Atom(a) if a == NIL => json::Term::nil(),
+        Term::Atom(a) => json::Term::atom(a),
+        Term::Int(n) => json::Term::Number { value: *n },
+        Term::Dec(d) => json::Term::Decimal { value: *d },
+        Term::Str(s) => json::Term::String { value: s.clone() },
+        Term::Var(v) => json::Term::Var { name: format!("_G{v}") },
+        Term::Compound(f, _) if f == CONS => {
+            let mut items = Vec::new();
+            let mut cur = t;
+            while let Term::Compound(f, args) = cur {
+                if f != CONS || args.len() != 2 {
+                    break;
+                }
+                items.push(to_json(&args[0]));
+                cur = &args[1];
+            }
+            let tail = match cur {
+                Term::Atom(a) if a == NIL => None,
+                other => Some(Box::new(to_json(other))),
+            };
+            json::Term::List { items, tail }
+        }
+        Term::Compound(f, args) => {
+            json::Term::Compound { name: f.clone(), args: args.iter().map(to_json).collect() }
+        }
+    }
+}
+
+/// Quoted, as SWI-Prolog's `term_string/2` prints it (`24006 is 22005+2001`, `currentPrice(747,'HUF')`).
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", to_json(self))
+    }
+}
@@ -1,16 +1,37 @@
 use std::{fs, path::Path};
-use anyhow::Result;
+use anyhow::{Context, Result};
 use halo2_proofs::pasta::Fp;
-use num_bigint::BigUint;
+use halo2curves::ff::Field;
+use rand_core::OsRng;
 
 use common::{
-    data::FactEntry,
-    utils_2::{common_helpers::{MAX_FACTS_HASHES}, off_circuit_poseidon::fact_hash_native_salted},
+    circuit_shape::{CircuitShape, CIRCUIT_SHAPE_PATH},
+    data::{FactEntry, FactValidity, FactWitnessFp},
+    io::holder::{read_fact_salts, write_fact_salts, FACT_SALTS_PATH},
+    io::issuer::{
+        load_or_create_issuer_sk, write_fact_witnesses, write_issuer_pk, write_root, FACT_ROOT_PATH,
+        FACT_WITNESSES_PATH, ISSUER_PK_PATH, ISSUER_SECRET_KEY_PATH, REVOCATION_ROOT_PATH,
+    },
+    utils_2::{
+        common_helpers::salt_from_dec_str,
+        fact_registry::{root_from_path, FactRegistry},
+        issuer_signature::{sign_fact_hash, verify_fact_signature},
+        off_circuit_poseidon::fact_hash_native_salted,
+        revocation::RevocationRegistry,
+    },
 };
-use halo2curves::ff::{ PrimeField};
+
+// One fact of facts.yaml, `printed` as the goals print it
+struct IssuedFact {
+    printed: String,
+    validity: FactValidity,
+    holder: Fp,
+    salt: Fp,
+    hash: Fp,
+}
 
 fn main() -> Result<()> {
-    
+
     // facts.yaml is the MNB case study factsX X = the test we want
     let config_file = "issue/src/facts.yaml";
 
@@ -18,35 +39,108 @@ fn main() -> Result<()> {
     let file_content = fs::read_to_string(config_file)?;
     let fact_configs: Vec<FactEntry> = serde_yaml::from_str(&file_content)?;
 
-    // Calculate the hashes as decimal strings
-    let mut fact_hashes: Vec<String> = fact_configs
+    // Revoked facts, in the format of facts.yaml (the salt can be left out if it was generated)
+    let revoked_file = Path::new("issue/src/revoked.yaml");
+    let revoked_configs: Vec<FactEntry> = if revoked_file.exists() {
+        serde_yaml::from_str(&fs::read_to_string(revoked_file)?)?
+    } else {
+        Vec::new()
+    };
+
+    // The hashes are padded to the circuit's args matrix, the registry has fact_tree_depth levels
+    let shape = CircuitShape::load_or_default(Path::new(CIRCUIT_SHAPE_PATH))?;
+
+    // Issuer keypair: the secret key stays with the issuer, the public key is the verifier's input
+    let sk = load_or_create_issuer_sk(Path::new(ISSUER_SECRET_KEY_PATH))?;
+    let pk = write_issuer_pk(Path::new(ISSUER_PK_PATH), &sk)?;
+
+    // Salts of the last run, a fact without a salt in facts.yaml keeps its salt (and its hash)
+    let old_salts = read_fact_salts(Path::new(FACT_SALTS_PATH))?;
+
+    // Revocation tree over the hashes of the revoked facts, their salts are kept for the next run
+    let mut revoked_salts: Vec<(String, Fp)> = Vec::new();
+    let revoked_hashes: Vec<Fp> = revoked_configs
         .iter()
         .map(|f| {
-            let args_vecvec: Vec<Vec<&str>> = f
-                .args
-                .iter()
-                .map(|inner| inner.iter().map(|s| s.as_str()).collect())
-                .collect();
-
-            // 2) Take &[&str] slices referencing the inner Vecs
-            let args_ref: Vec<&[&str]> = args_vecvec.iter().map(|v| v.as_slice()).collect();
-            let hash_fp: Fp = fact_hash_native_salted(&f.predicate, &args_ref, &f.salt);
-            let hash_bytes = hash_fp.to_repr();
-            let hash_int = BigUint::from_bytes_le(hash_bytes.as_ref());
-            hash_int.to_str_radix(10)
+            let fact = f.term().with_context(|| format!("Bad args of the revoked fact {}", f.predicate))?;
+            let salt = match &f.salt {
+                Some(s) => salt_from_dec_str(s)?,
+                None => *old_salts
+                    .get(&fact.to_string())
+                    .with_context(|| format!("No salt for the revoked fact {fact} in revoked.yaml or {FACT_SALTS_PATH}"))?,
+            };
+            let holder = f.holder_id().with_context(|| format!("Bad holder id of the revoked fact {fact}"))?;
+            revoked_salts.push((fact.to_string(), salt));
+            Ok(fact_hash_native_salted(&fact, salt, &f.validity(), holder, &shape))
         })
-        .collect();
-    
-    while fact_hashes.len() < MAX_FACTS_HASHES {
-        fact_hashes.push("0".to_string());
+        .collect::<Result<_>>()?;
+    let revocation = RevocationRegistry::new(&revoked_hashes);
+    write_root(Path::new(REVOCATION_ROOT_PATH), &revocation.root())?;
+
+    // Salted hash of every fact (with its validity period and holder), they are the leaves of the
+    // registry. A random salt is drawn again while the fact would land in a revoked slot
+    let mut facts: Vec<IssuedFact> = Vec::new();
+    for f in &fact_configs {
+        let fact = f.term().with_context(|| format!("Bad args of the fact {}", f.predicate))?;
+        let printed = fact.to_string();
+        let validity = f.validity();
+        if validity.from > validity.until {
+            anyhow::bail!("The fact {fact} is valid from {} until {}", validity.from, validity.until);
+        }
+        let holder = f.holder_id().with_context(|| format!("Bad holder id of the fact {fact}"))?;
+        let given = match &f.salt {
+            Some(s) => Some(salt_from_dec_str(s).with_context(|| format!("Bad salt of the fact {fact}"))?),
+            None => None,
+        };
+
+        let mut salt = given.or_else(|| old_salts.get(&printed).copied()).unwrap_or_else(|| Fp::random(OsRng));
+        let mut hash = fact_hash_native_salted(&fact, salt, &validity, holder, &shape);
+        while revocation.is_revoked(&hash) && !revoked_hashes.contains(&hash) {
+            if given.is_some() {
+                anyhow::bail!("{fact} shares its revocation slot with a revoked fact, give it a new salt");
+            }
+            salt = Fp::random(OsRng);
+            hash = fact_hash_native_salted(&fact, salt, &validity, holder, &shape);
+        }
+        facts.push(IssuedFact { printed, validity, holder, salt, hash });
+    }
+
+    let leaves: Vec<Fp> = facts.iter().map(|f| f.hash).collect();
+    let registry = FactRegistry::new(&leaves, shape.fact_tree_depth)?;
+    write_root(Path::new(FACT_ROOT_PATH), &registry.root())?;
+
+    // The salts only go to the holder, the proofs reveal neither them nor the hashes. The revoked
+    // facts keep theirs too (unless the fact was issued again), so the next run finds the same
+    // revoked hashes
+    let mut salts: Vec<(String, Fp)> = facts.iter().map(|f| (f.printed.clone(), f.salt)).collect();
+    for (printed, salt) in revoked_salts {
+        if !salts.iter().any(|(p, _)| *p == printed) {
+            salts.push((printed, salt));
+        }
     }
-    
-    // Write JSON file
-    let out_path = Path::new("input/fact_hashes.json");
-    fs::create_dir_all(out_path.parent().unwrap())?;
-    let json = serde_json::to_string_pretty(&fact_hashes)?;
-    fs::write(out_path, json)?;
-
-    println!("Saved {} fact hashes to {:?}", fact_hashes.len(), out_path);
+    write_fact_salts(Path::new(FACT_SALTS_PATH), &salts)?;
+
+    // Signature, registry and revocation path of every fact that is not revoked, with the validity
+    // period and holder id that were hashed
+    let mut issued: Vec<(String, FactWitnessFp)> = Vec::new();
+    for (i, f) in facts.into_iter().enumerate() {
+        if revocation.is_revoked(&f.hash) {
+            continue;
+        }
+        let signature = sign_fact_hash(&sk, f.hash, OsRng);
+        let path = registry.path(i);
+        debug_assert!(verify_fact_signature(pk, f.hash, &signature));
+        debug_assert_eq!(root_from_path(f.hash, &path), registry.root());
+        let revocation_path = revocation.path(&f.hash);
+        let witness = FactWitnessFp { signature, path, revocation_path, validity: f.validity, holder: f.holder };
+        issued.push((f.printed, witness));
+    }
+
+    let out_path = Path::new(FACT_WITNESSES_PATH);
+    write_fact_witnesses(out_path, &issued)?;
+
+    println!("Issuer public key saved to {ISSUER_PK_PATH}, fact registry root to {FACT_ROOT_PATH}");
+    println!("{} revoked facts, revocation root saved to {REVOCATION_ROOT_PATH}", revoked_hashes.len());
+    println!("Saved {} fact witnesses to {:?}, their salts to {FACT_SALTS_PATH}", issued.len(), out_path);
     Ok(())
 }
@@ -21,8 +21,9 @@ write_kb :-
 
 write_proof :-
     prove(endPrice(931220), [Tree]),
+    tree_json([Tree], Json),                          % typed goal terms
     open('input/proof_tree.json', write, Stream),           % fájl megnyitása írásra
-    json_write_dict(Stream, [Tree], [width(100), serialize_unknown(true)]),
+    json_write_dict(Stream, Json, [width(100), serialize_unknown(true)]),
     close(Stream),                                    % fájl lezárása
     halt.
 
@@ -1,4 +1,4 @@
-:- module(meta_interpreter, [prove/2]).
+:- module(meta_interpreter, [prove/2, tree_json/2, term_json/2]).
 
 % --- base case: true ---
 prove(true, [true]) :- !.
@@ -25,4 +25,45 @@ prove(Goal, [state{goal:Goal, subtree:[true]}]) :-
 % --- general rule ---
 prove(Goal, [state{goal:Goal, subtree:Tree}]) :-
     clause(Goal, Body),
-    prove(Body, Tree).
\ No newline at end of file
+    prove(Body, Tree).
+
+% --- proof tree -> proof_tree.json ---
+% every goal is written as a typed term:
+% {"type":"atom","name":..}, {"type":"number","value":..}, {"type":"var","name":..},
+% {"type":"compound","name":..,"args":[..]}, {"type":"list","items":[..],"tail":..}
+tree_json(Tree, Json) :-
+    maplist(node_json, Tree, Json).
+
+node_json(true, true) :- !.
+node_json(state{goal:Goal, subtree:Tree}, _{goal:G, subtree:Json}) :-
+    term_json(Goal, G),
+    tree_json(Tree, Json).
+
+term_json(T, _{type:var, name:N}) :-
+    var(T), !,
+    term_string(T, N).
+term_json(T, _{type:number, value:T}) :-
+    integer(T), !.
+term_json(T, _{type:string, value:T}) :-
+    string(T), !.
+term_json(T, _{type:decimal, value:T}) :-
+    float(T), !.
+term_json([], _{type:list, items:[]}) :- !.
+term_json(T, _{type:atom, name:T}) :-
+    atom(T), !.
+term_json([H|T], Json) :- !,
+    list_items([H|T], Items, Tail),
+    maplist(term_json, Items, ItemsJson),
+    (   Tail == []
+    ->  Json = _{type:list, items:ItemsJson}
+    ;   term_json(Tail, TailJson),
+        Json = _{type:list, items:ItemsJson, tail:TailJson}
+    ).
+term_json(T, _{type:compound, name:Name, args:ArgsJson}) :-
+    compound_name_arguments(T, Name, Args),
+    maplist(term_json, Args, ArgsJson).
+
+list_items(L, [], L) :-
+    \+ ( nonvar(L), L = [_|_] ), !.
+list_items([H|T], [H|Items], Tail) :-
+    list_items(T, Items, Tail).
\ No newline at end of file
@@ -1,36 +1,54 @@
 mod writer;
+mod shape_inference;
 pub mod helpers;
 
 
 use std::path::Path;
 use std::{collections::HashMap, fs};
 use std::sync::Arc;
-use anyhow::Result;
-use common::data::{RuleTemplateFileFp};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{Context, Result};
+use common::data::{FactWitnessFp, RuleTemplateFileFp};
+use common::chips::fact_check::compare_chip::i128_to_fp;
 use common::unification_checker_circuit::UnificationCircuit;
-//use common::utils_2::off_circuit_poseidon::poseidon_hash_list_native;
+use common::utils_2::off_circuit_poseidon::{goal_commitment_native, goal_commitments_native, poseidon_hash_list_native};
+use halo2curves::ff::Field;
 use rand_core::OsRng;
 use rayon::prelude::*;
 
 use halo2_proofs::{
     pasta::{EqAffine, Fp},
-    plonk::{keygen_pk, keygen_vk, ProvingKey, VerifyingKey},
+    plonk::ProvingKey,
     poly::commitment::Params,
     transcript::{Blake2bWrite, Challenge255},
 };
 
 use common::{data, data::UnificationInputFp};
 //use common::unification_checker_circuit::UnificationCircuit;
-use common::io::read_fact_hashes::read_fact_hashes;
+use common::io::{holder::{load_or_create_holder_sk, read_fact_salts, write_holder_id, FACT_SALTS_PATH, HOLDER_ID_PATH, HOLDER_SECRET_KEY_PATH}, issuer::{read_fact_witnesses, read_issuer_pk, read_root, FACT_ROOT_PATH, FACT_WITNESSES_PATH, ISSUER_PK_PATH, REVOCATION_ROOT_PATH}, keys::keygen_checked_pk};
+use common::circuit_shape::{CircuitShape, CIRCUIT_SHAPE_PATH};
 use data::FactEntry;
 use helpers::{build_fact_map, unification_input_from_goal_and_facts};
+use shape_inference::infer_shape;
 
 use writer::{write_proof};
 
-//use crate::helpers::{encode_str_to_termfp, unification_input_from_goal_and_facts};
 use crate::writer::remove_proofs_file;
 
 fn main() -> Result<()> {
+    // --infer-shape: only write the minimal circuit shape for these rules, proof tree and facts
+    let infer_mode = std::env::args().any(|a| a == "--infer-shape");
+    // --at <unix seconds>: the verification time the facts have to be valid at (default: now)
+    let verification_time = verification_time_arg()?;
+
+    // Holder key of the prover, its id binds facts to it. --holder-id: only write the id
+    let holder_sk = load_or_create_holder_sk(Path::new(HOLDER_SECRET_KEY_PATH))?;
+    let holder_id = write_holder_id(Path::new(HOLDER_ID_PATH), holder_sk)?;
+    if std::env::args().any(|a| a == "--holder-id") {
+        println!("Saved holder id to {HOLDER_ID_PATH}");
+        return Ok(());
+    }
+
     // Fact struct
     
     // facts.yaml is the MNB case study factsX X = the test we want
@@ -41,8 +59,12 @@ fn main() -> Result<()> {
     let fact_configs: Vec<FactEntry> = serde_yaml::from_str(&file_content)
         .expect("Wrong YAML format");
 
-    // Building fact HashMap
-    let facts = build_fact_map(&fact_configs);
+    // Building fact HashMap, the salts were written by the issuer for this holder
+    let fact_salts = read_fact_salts(Path::new(FACT_SALTS_PATH))?;
+    let facts = build_fact_map(&fact_configs, &fact_salts)?;
+    // Circuit dimensions, the verifier has to use the same file
+    let circuit_shape = CircuitShape::load_or_default(Path::new(CIRCUIT_SHAPE_PATH))?;
+
     // Processing the rules
     let rules_text = fs::read_to_string("input/rules.json")?;
 
@@ -50,27 +72,30 @@ fn main() -> Result<()> {
 
     let rules_fp = RuleTemplateFileFp::from(&rules);
     
-    // Flar rules for rule hash commitment
-    //let rules_vec_fp = RuleTemplateFileFp::to_flat_vec(&rules_fp);
+    // Flat rules for rule hash commitment
+    let rules_vec_fp = RuleTemplateFileFp::to_flat_vec(&rules_fp);
 
     // Processing the proof tree
     let proof_text = fs::read_to_string("input/proof_tree.json")?;
     
     let tree: Vec<data::ProofNode> = serde_json::from_str(&proof_text)?;
 
-    // Public input hashes
-    let path = Path::new("input/fact_hashes.json");
-    let public_facts_hashes: Vec<Fp> = read_fact_hashes(path)?;
-    //let public_rules_hashes = poseidon_hash_list_native(&rules_vec_fp);
+    if infer_mode {
+        let inferred = infer_shape(&rules, &tree, &facts)?;
+        fs::create_dir_all(Path::new(CIRCUIT_SHAPE_PATH).parent().unwrap())?;
+        fs::write(CIRCUIT_SHAPE_PATH, serde_yaml::to_string(&inferred)?)?;
+        println!("Saved circuit shape to {CIRCUIT_SHAPE_PATH}: {inferred:?}");
+        return Ok(());
+    }
 
-    // Creating the public inputs
-    let instance_columns: &[&[Fp]] = &[
-        &public_facts_hashes/*,   // first instance column
-        std::slice::from_ref(&public_rules_hashes),  // second instance column*/
-    ];
+    // Public inputs: issuer key, fact registry and revocation roots, rules hash. The facts are
+    // proven by the issuer's signatures, their registry paths and their empty revocation slots
+    let issuer_pk = read_issuer_pk(Path::new(ISSUER_PK_PATH))?;
+    let fact_root = read_root(Path::new(FACT_ROOT_PATH))?;
+    let revocation_root = read_root(Path::new(REVOCATION_ROOT_PATH))?;
+    let fact_witnesses = read_fact_witnesses(Path::new(FACT_WITNESSES_PATH))?;
+    let public_rules_hashes = poseidon_hash_list_native(&rules_vec_fp);
 
-    // Wrap into &[&[&[Fp]]] for create_proof
-    let public_inputs: &[&[&[Fp]]] = &[instance_columns];
     // Debug
     println!(
         "Loaded {} predicates, {} proof nodes.",
@@ -78,20 +103,13 @@ fn main() -> Result<()> {
         tree.len()
     );
     
-    // Params + keygen
-    //let params: Params<EqAffine> = Params::new(16);
-
-    //Testing
-
-    //for tests
-    let params: Params<EqAffine> = Params::new(16);
-    
+    // Params + keygen (only the params are cached in keys/, the keys are generated every run)
     let shape = UnificationCircuit {
         rules: rules_fp.clone(),
-        unif: UnificationInputFp::default(),
+        unif: UnificationInputFp::empty(&circuit_shape),
+        shape: circuit_shape,
     };
-    let vk: VerifyingKey<EqAffine> = keygen_vk(&params, &shape)?;
-    let pk: ProvingKey<EqAffine> = keygen_pk(&params, vk.clone(), &shape)?;
+    let (params, pk) = keygen_checked_pk(Path::new("keys"), &shape, &public_rules_hashes)?;
 
     let params = Arc::new(params);
     let pk = Arc::new(pk);
@@ -106,34 +124,118 @@ fn main() -> Result<()> {
 
     /*pool.install(|| {
         let _ = tree.iter()
-         .try_for_each(|node|prove_tree(&rules_fp, node, &params,  &pk, &facts, &public_inputs));
+         .try_for_each(|node|prove_tree(&rules_fp, node, Fp::zero(), &params,  &pk, &facts, &fact_witnesses, &shared));
     });*/
 
+    let shared = SharedInstances {
+        issuer_pk,
+        fact_root,
+        revocation_root,
+        verification_time,
+        rules_hash: public_rules_hashes,
+        holder_sk,
+        holder_id,
+        shape: circuit_shape,
+    };
+
+    // Root goals use salt 0, so their commitment can be recomputed from the goal alone
+    for node in &tree {
+        if let data::ProofNode::GoalNode(g) = node {
+            let root = unification_input_from_goal_and_facts(g, &facts, Fp::zero(), Vec::new(), &circuit_shape);
+            println!("Root goal {} commitment: {:?}", g.goal, goal_commitment_native(&root.goal_name, Fp::zero(), &circuit_shape));
+        }
+    }
+
     tree.iter()
-         .try_for_each(|node|prove_tree(&rules_fp, node, &params,  &pk, &facts, &public_inputs))?;
+         .try_for_each(|node|prove_tree(&rules_fp, node, Fp::zero(), &params,  &pk, &facts, &fact_witnesses, &shared))?;
 
     println!("All unification goals proof saved!");
     Ok(())
 }
 
 // Recursive proving function
+// Instance columns that are the same for every proof, the circuit shape they are laid out for and
+// the holder key the facts are proven with
+struct SharedInstances {
+    issuer_pk: [Fp; 2],
+    fact_root: Fp,
+    revocation_root: Fp,
+    verification_time: i64,
+    rules_hash: Fp,
+    shape: CircuitShape,
+    holder_sk: Fp,
+    holder_id: Fp,
+}
+
+// `salt` is the goal commitment salt of this node, the parent used the same one for its child slot
 fn prove_tree(
     rules_fp: &data::RuleTemplateFileFp,
     node: &data::ProofNode,
+    salt: Fp,
     params: &Arc<Params<EqAffine>>,
     pk: &Arc<ProvingKey<EqAffine>>,
-    facts: &HashMap<String, Fp>,
-    public_inputs: &[&[&[Fp]]],
+    facts: &HashMap<String, Option<Fp>>,
+    fact_witnesses: &HashMap<String, FactWitnessFp>,
+    shared: &SharedInstances,
 ) -> Result<()> {
     if let data::ProofNode::GoalNode(g) = node {
+        // Fresh salt for every child goal, `true` leaves keep 0
+        let subtree_salts: Vec<Fp> = g.subtree
+            .iter()
+            .map(|sub| match sub {
+                data::ProofNode::GoalNode(_) => Fp::random(OsRng),
+                _ => Fp::zero(),
+            })
+            .collect();
+
         // Constructing the Unification inputs from the goal node and the facts hashmap
-        let unif_input_fp = unification_input_from_goal_and_facts(g, facts);
+        let mut unif_input_fp = unification_input_from_goal_and_facts(g, facts, salt, subtree_salts.clone(), &shared.shape);
+        // A fact goal is proven by the issuer's signature on its salted hash and its registry path
+        let goal = g.goal.to_string();
+        if let Some(fact_salt) = facts.get(&goal) {
+            if fact_salt.is_none() {
+                anyhow::bail!("No salt for the fact {goal} in {FACT_SALTS_PATH}, run the issuer again");
+            }
+            let w = fact_witnesses
+                .get(&goal)
+                .with_context(|| format!("No issuer signature for the fact {goal} (or it is revoked)"))?;
+            if w.path.siblings.len() != shared.shape.fact_tree_depth {
+                anyhow::bail!("The registry path of {goal} does not match fact_tree_depth, run the issuer again");
+            }
+            if !w.validity.contains(shared.verification_time) {
+                anyhow::bail!(
+                    "The fact {goal} is not valid at {} (valid from {} until {})",
+                    shared.verification_time, w.validity.from, w.validity.until,
+                );
+            }
+            if w.holder != Fp::zero() && w.holder != shared.holder_id {
+                anyhow::bail!("The fact {goal} is bound to another holder than {HOLDER_ID_PATH}");
+            }
+            unif_input_fp.fact_witness = w.clone();
+            unif_input_fp.holder_sk = shared.holder_sk;
+        }
+        let goal_commitments = goal_commitments_native(&unif_input_fp, &shared.shape);
+
+        // Creating the public inputs
+        let verification_time = i128_to_fp(shared.verification_time.into());
+        let instance_columns: &[&[Fp]] = &[
+            &shared.issuer_pk,                             // first instance column
+            std::slice::from_ref(&shared.fact_root),       // second instance column
+            std::slice::from_ref(&shared.revocation_root), // third instance column
+            std::slice::from_ref(&verification_time),      // fourth instance column
+            std::slice::from_ref(&shared.rules_hash),      // fifth instance column
+            &goal_commitments,                             // sixth instance column
+        ];
+
+        // Wrap into &[&[&[Fp]]] for create_proof
+        let public_inputs: &[&[&[Fp]]] = &[instance_columns];
 
         //println!("UNIF: {:?}", unif_input_fp);
         // Circuit Fp with proper inputs
         let circuit = UnificationCircuit {
             rules: rules_fp.clone(),
             unif: unif_input_fp,
+            shape: shared.shape,
         };
 
         // Proof generation
@@ -150,10 +252,23 @@ fn prove_tree(
         )?;
         let proof = transcript.finalize();
 
-        write_proof("unif", &proof)?;
+        write_proof("unif", &proof, &goal_commitments, shared.verification_time)?;
         // Recursion
         g.subtree.par_iter()
-            .try_for_each(|sub| prove_tree(rules_fp, sub, params, pk, facts, &public_inputs))?;
+            .zip(subtree_salts.par_iter())
+            .try_for_each(|(sub, sub_salt)| prove_tree(rules_fp, sub, *sub_salt, params, pk, facts, fact_witnesses, shared))?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+// Value of `--at <unix seconds>`, the current time without it
+fn verification_time_arg() -> Result<i64> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|a| a == "--at") {
+        Some(i) => {
+            let value = args.get(i + 1).context("--at needs a unix time in seconds")?;
+            value.parse().with_context(|| format!("Bad verification time {value}"))
+        }
+        None => Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64),
+    }
+}
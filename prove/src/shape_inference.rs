@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use common::{
+    circuit_shape::CircuitShape,
+    data::{GoalEntry, ProofNode, RuleTemplateFile, RuleTemplateFileFp, TermFp, TermSide, UnificationInputFp},
+    unification_checker_circuit::{UnificationCircuit, NUM_INSTANCE_COLUMNS},
+    utils_2::common_helpers::RANGE_CHECK_LIMB_BITS,
+};
+use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::Error};
+
+use crate::helpers::unification_input_from_goal_and_facts;
+
+// The goals are encoded with this args matrix first, then measured
+const SCAN_WIDTH: usize = 32;
+// Largest k that is tried
+const MAX_K: u32 = 24;
+
+/// Smallest `CircuitShape` that fits the rules, the proof tree and the facts, with the smallest k
+/// the largest proof node (and the keygen circuit) can be laid out in.
+///
+/// rules.json:      arity of the heads and children, clauses per predicate, body rows and row width,
+///                  and every equality reference (an out of range reference would drop the row)
+/// proof_tree.json: terms per node, arity and list width of the encoded goals, children per node
+/// facts:           depth of the fact registry tree
+pub fn infer_shape(
+    rules: &RuleTemplateFile,
+    tree: &[ProofNode],
+    facts: &HashMap<String, Option<Fp>>,
+) -> Result<CircuitShape> {
+    let mut shape = CircuitShape {
+        k: RANGE_CHECK_LIMB_BITS as u32 + 1,
+        max_pred_list: 1,
+        max_clauses_per_predicate: 1,
+        max_predicates_overall: rules.predicates.len().max(1),
+        max_arity: 1,
+        max_children: 1,
+        fact_tree_depth: facts.len().max(2).next_power_of_two().trailing_zeros() as usize,
+    };
+
+    // Rules
+    for pred in &rules.predicates {
+        shape.max_arity = shape.max_arity.max(pred.arity);
+        shape.max_clauses_per_predicate = shape.max_clauses_per_predicate.max(pred.clauses.len());
+
+        for clause in &pred.clauses {
+            // head + body rows, the i-th body row is node 1 + i
+            shape.max_predicates_overall = shape.max_predicates_overall.max(1 + clause.children.len());
+            shape.max_children = shape.max_children.max(clause.children.len());
+            for row in &clause.children {
+                shape.max_pred_list = shape.max_pred_list.max(row.len());
+                for ch in row {
+                    shape.max_arity = shape.max_arity.max(ch.arity);
+                }
+            }
+
+            for eq in &clause.equalities {
+                for side in [&eq.left, &eq.right] {
+                    if let TermSide::Ref(r) = side {
+                        shape.max_children = shape.max_children.max(r.children_node_list);
+                        shape.max_pred_list = shape.max_pred_list.max(r.predicate + 1).max(r.list_index + 1);
+                        shape.max_arity = shape.max_arity.max(r.arg + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    // Proof tree, encoded the same way as for proving
+    let scan = CircuitShape { max_pred_list: SCAN_WIDTH, max_arity: SCAN_WIDTH, ..shape };
+    let mut goals = Vec::new();
+    collect_goals(tree, &mut goals);
+
+    for g in &goals {
+        let unif = unification_input_from_goal_and_facts(g, facts, Fp::zero(), Vec::new(), &scan);
+
+        shape.max_children = shape.max_children.max(unif.subtree_goals.len());
+        shape.max_predicates_overall = shape.max_predicates_overall.max(1 + unif.subtree_goals.len());
+        for terms in std::iter::once(&unif.goal_name).chain(unif.subtree_goals.iter()) {
+            shape.max_pred_list = shape.max_pred_list.max(terms.len());
+            for t in terms {
+                let (arity, width) = measure_args(t);
+                shape.max_arity = shape.max_arity.max(arity);
+                shape.max_pred_list = shape.max_pred_list.max(width);
+            }
+        }
+    }
+
+    // k: every proof node and the keygen circuit has to fit
+    let rules_fp = RuleTemplateFileFp::from(rules);
+    let mut inputs = vec![UnificationInputFp::empty(&shape)];
+    inputs.extend(
+        goals
+            .iter()
+            .map(|g| unification_input_from_goal_and_facts(g, facts, Fp::zero(), Vec::new(), &shape)),
+    );
+
+    for unif in inputs {
+        let circuit = UnificationCircuit { rules: rules_fp.clone(), unif, shape };
+        while !fits(&circuit, shape.k)? {
+            if shape.k == MAX_K {
+                anyhow::bail!("The circuit does not fit into 2^{MAX_K} rows");
+            }
+            shape.k += 1;
+        }
+    }
+
+    Ok(shape)
+}
+
+fn collect_goals<'a>(nodes: &'a [ProofNode], out: &mut Vec<&'a GoalEntry>) {
+    for node in nodes {
+        if let ProofNode::GoalNode(g) = node {
+            out.push(g);
+            collect_goals(&g.subtree, out);
+        }
+    }
+}
+
+// (used args, used list width): last row / column that is not -1 padding
+fn measure_args(t: &TermFp) -> (usize, usize) {
+    let pad = Fp::one().neg();
+    let mut arity = 0;
+    let mut width = 0;
+    for (a, row) in t.args.iter().enumerate() {
+        if let Some(l) = row.iter().rposition(|v| *v != pad) {
+            arity = a + 1;
+            width = width.max(l + 1);
+        }
+    }
+    (arity, width)
+}
+
+// Only the layout is checked, the instances are left empty
+fn fits(circuit: &UnificationCircuit, k: u32) -> Result<bool> {
+    match MockProver::run(k, circuit, vec![Vec::new(); NUM_INSTANCE_COLUMNS]) {
+        Ok(_) => Ok(true),
+        Err(Error::NotEnoughRowsAvailable { .. }) => Ok(false),
+        Err(e) => Err(anyhow::anyhow!("Layout of the circuit failed: {e:?}")),
+    }
+}
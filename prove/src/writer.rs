@@ -7,9 +7,15 @@ use anyhow::Result;
 use once_cell::sync::Lazy;
 use serde::{Serialize, Deserialize};
 use base64::{engine::general_purpose, Engine as _};
+use common::io::fp_decimal::fp_to_dec_string;
+use halo2_proofs::pasta::Fp;
 #[derive(Serialize, Deserialize)]
 struct ProofEntry {
     proof_b64: String,
+    // public_goal_commitments of the proof (decimal): head goal, then the child slots
+    goal_commitments: Vec<String>,
+    // public verification time (unix seconds) the facts of the proof were checked at
+    verification_time: i64,
 }
 pub fn remove_proofs_file(name: &str) -> Result<()> {
     let file_path = Path::new("output").join(format!("{}", name));
@@ -21,7 +27,7 @@ pub fn remove_proofs_file(name: &str) -> Result<()> {
 
 static FILE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
-pub fn write_proof(name: &str, proof_bytes: &[u8]) -> anyhow::Result<()> {
+pub fn write_proof(name: &str, proof_bytes: &[u8], goal_commitments: &[Fp], verification_time: i64) -> anyhow::Result<()> {
     // Mutex lock – one thread can write at a time
     let _guard = FILE_LOCK.lock().unwrap();
 
@@ -30,7 +36,8 @@ pub fn write_proof(name: &str, proof_bytes: &[u8]) -> anyhow::Result<()> {
     let file_path = out_dir.join(format!("{}_proofs.json", name));
 
     let proof_b64 = general_purpose::STANDARD.encode(proof_bytes);
-    let entry = ProofEntry { proof_b64 };
+    let goal_commitments = goal_commitments.iter().map(fp_to_dec_string).collect();
+    let entry = ProofEntry { proof_b64, goal_commitments, verification_time };
 
     let mut existing: Vec<ProofEntry> = if file_path.exists() {
         let mut content = String::new();
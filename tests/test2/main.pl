@@ -9,7 +9,8 @@
 % run the proof for ancestor(x,y)
 run_proof(Tree) :-
     Goal = ancestor(alice, laura),
-    prove(Goal, Tree).
+    prove(Goal, Proof),
+    tree_json(Proof, Tree).
 
 % export proof tree as JSON
 export_proof(File) :-
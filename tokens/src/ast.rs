@@ -0,0 +1,315 @@
+use antlr4rust::{common_token_stream::CommonTokenStream, InputStream};
+use antlr4rust::tree::{ParseTree, ParseTreeVisitorCompat};
+use common::term::{self as typed, arg_priorities, infix_op};
+
+use crate::parser::prologlexer::prologLexer;
+use crate::parser::prologparser::*;
+use crate::parser::prologvisitor::prologVisitor;
+
+// ------------------ Belső (egyszerű) AST ------------------
+
+#[derive(Debug, Clone)]
+pub enum Term {
+    Atom(String),
+    Var(String),
+    Predicate { name: String, args: Vec<Term> },
+    ListCell { head: Box<Term>, tail: Box<Term> },
+    EmptyList,
+}
+
+impl Term {
+    /*fn arity(&self) -> usize {
+        match self {
+            Term::Predicate { args, .. } => args.len(),
+            _ => 0,
+        }
+    }*/
+}
+
+// ------------------ Clause gyűjtő ------------------
+
+#[derive(Debug, Clone)]
+pub struct Clause {
+    pub head: Term,
+    pub body: Vec<Term>, // a body vesszővel tagolt elemei. Minden elem egy "sor".
+}
+
+// ------------------ Visitor → AST builder ------------------
+
+pub struct AstBuilder {
+    pub clauses: Vec<Clause>,
+    _unit: (),
+}
+
+impl AstBuilder {
+    pub fn new() -> Self {
+        Self { clauses: vec![], _unit: () }
+    }
+
+    // Egyszerű szögletes listaparszoló → cons-lánc
+    fn parse_list(&self, s: &str) -> Term {
+        let inner = &s[1..s.len() - 1];
+        if inner.trim().is_empty() {
+            return Term::EmptyList;
+        }
+        let (left, tail_opt) = if let Some(pos) = inner.find('|') {
+            (inner[..pos].trim(), Some(inner[pos + 1..].trim().to_string()))
+        } else {
+            (inner.trim(), None)
+        };
+
+        let parts = split_top_level_commas(left)
+            .into_iter()
+            .map(|p| p.trim().to_string())
+            .collect::<Vec<_>>();
+
+        let mut t = match tail_opt {
+            Some(tail_s) => self.parse_term_str(&tail_s),
+            None => Term::EmptyList,
+        };
+        for elem in parts.into_iter().rev() {
+            t = Term::ListCell {
+                head: Box::new(self.parse_term_str(&elem)),
+                tail: Box::new(t),
+            };
+        }
+        t
+    }
+
+    fn parse_term_str(&self, s: &str) -> Term {
+        if s.starts_with('[') && s.ends_with(']') {
+            return self.parse_list(s);
+        }
+        Term::Atom(s.into())
+    }
+
+    fn parse_term(&self, ctx: &TermContextAll) -> Term {
+        use TermContextAll::*;
+        let text = ctx.get_text();
+        if text.starts_with('[') && text.ends_with(']') {
+            return self.parse_list(&text);
+        }
+
+        match ctx {
+            VariableContext(v) => Term::Var(v.get_text()),
+            Integer_termContext(i) => Term::Atom(i.get_text()),
+            FloatContext(f) => Term::Atom(f.get_text()),
+            Atom_termContext(a) => Term::Atom(a.get_text()),
+            List_termContext(l) => Term::Atom(l.get_text()),
+            Compound_termContext(c) => {
+                let name = c.atom().unwrap().get_text();
+                let args = c
+                    .termlist().unwrap()
+                    .term_all()
+                    .into_iter()
+                    .map(|t| self.parse_term(&t))
+                    .collect();
+                Term::Predicate { name, args }
+            }
+            Binary_operatorContext(b) => {
+                let left = self.parse_term(&b.term(0).unwrap());
+                let right = self.parse_term(&b.term(1).unwrap());
+                let op = b.operator_().unwrap().get_text();
+                Term::Predicate { name: op, args: vec![left, right] }
+            }
+            Unary_operatorContext(u) => {
+                let arg = self.parse_term(&u.term().unwrap());
+                let op = u.operator_().unwrap().get_text();
+                Term::Predicate { name: op, args: vec![arg] }
+            }
+            _ => Term::Atom(ctx.get_text()),
+        }
+    }
+}
+
+pub fn split_top_level_commas(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth_paren = 0;
+    let mut last = 0;
+    let chars: Vec<char> = s.chars().collect();
+    for (i, ch) in chars.iter().enumerate() {
+        match ch {
+            '(' => depth_paren += 1,
+            ')' => if depth_paren > 0 { depth_paren -= 1 },
+            ',' if depth_paren == 0 => {
+                out.push(&s[last..i]);
+                last = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if last <= s.len() {
+        out.push(&s[last..]);
+    }
+    out
+}
+
+// Tuple "(a,b,c)" → mezők (underscore marad indexeléshez, de nem veszünk fel előfordulást)
+pub fn parse_tuple_fields_keep_all(atom_str: &str) -> Vec<String> {
+    let s = atom_str.trim();
+    if !(s.starts_with('(') && s.ends_with(')')) {
+        return vec![s.to_string()];
+    }
+    let inner = &s[1..s.len() - 1];
+    split_top_level_commas(inner)
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .collect()
+}
+
+pub fn is_var_name(s: &str) -> bool {
+    if s == "_" || s.is_empty() { return false; }
+    let first = s.chars().next().unwrap();
+    first.is_ascii_uppercase() || first == '_'
+}
+
+pub fn term_as_string(t: &Term) -> String {
+    match t {
+        Term::Atom(s) => s.clone(),
+        Term::Var(v) => v.clone(),
+        Term::EmptyList => "[]".to_string(),
+        Term::Predicate { name, args } => {
+            let args_s = args.iter().map(|a| term_as_string(a)).collect::<Vec<_>>().join(", ");
+            format!("{}({})", name, args_s)
+        }
+        Term::ListCell { head, tail } => {
+            let mut elems = vec![term_as_string(head)];
+            let mut cur = *tail.clone();
+            let mut guard = 0usize;
+            while let Term::ListCell { head: h, tail: t2 } = cur.clone() {
+                elems.push(term_as_string(&h));
+                cur = (*t2).clone();
+                guard += 1; if guard > 1024 { break; }
+            }
+            let tail_str = match cur {
+                Term::EmptyList => "".to_string(),
+                other => format!("|{}", term_as_string(&other)),
+            };
+            let body = elems.join(",");
+            if tail_str.is_empty() { format!("[{}]", body) } else { format!("[{}{}]", body, tail_str) }
+        }
+    }
+}
+
+/// Típusos term (`common::term::Term`) az AST-ből. A nyelvtanban minden bináris operátor
+/// egyforma prioritású, ezért az operátorláncot forrássorrendben szétszedjük (a zárójeles
+/// részek Atom szövegek, operandusok maradnak) és a szabványos prioritásokkal újraépítjük:
+/// `A * B div 100` → `(A * B) div 100`, `A - B * C` → `A - (B * C)`.
+pub fn to_term(t: &Term) -> anyhow::Result<typed::Term> {
+    match t {
+        Term::Var(v) => Ok(typed::Term::Var { name: v.clone() }),
+        // számok, idézett atomok, listaelemek és zárójeles termek szövegként
+        Term::Atom(text) => typed::Term::read(text),
+        Term::EmptyList => Ok(typed::Term::nil()),
+        Term::ListCell { .. } => {
+            let mut items = Vec::new();
+            let mut cur = t;
+            while let Term::ListCell { head, tail } = cur {
+                items.push(to_term(head)?);
+                cur = tail;
+            }
+            let tail = match cur {
+                Term::EmptyList => None,
+                other => Some(Box::new(to_term(other)?)),
+            };
+            Ok(typed::Term::List { items, tail })
+        }
+        Term::Predicate { name, args } if args.len() == 2 && infix_op(name).is_some() => {
+            let mut operands = Vec::new();
+            let mut ops = Vec::new();
+            flatten_ops(t, &mut operands, &mut ops)?;
+
+            let mut operands = operands.into_iter();
+            let mut pos = 0;
+            let term = climb(&mut operands, &ops, &mut pos, 1200);
+            if pos != ops.len() {
+                anyhow::bail!("Operator priority clash at {}", ops[pos]);
+            }
+            Ok(term)
+        }
+        Term::Predicate { name, args } => {
+            let name = match typed::Term::read(name)? {
+                typed::Term::Atom { name } => name,
+                _ => anyhow::bail!("Bad functor {name}"),
+            };
+            let args = args.iter().map(to_term).collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(typed::Term::Compound { name, args })
+        }
+    }
+}
+
+fn flatten_ops(t: &Term, operands: &mut Vec<typed::Term>, ops: &mut Vec<String>) -> anyhow::Result<()> {
+    match t {
+        Term::Predicate { name, args } if args.len() == 2 && infix_op(name).is_some() => {
+            flatten_ops(&args[0], operands, ops)?;
+            ops.push(name.clone());
+            flatten_ops(&args[1], operands, ops)
+        }
+        other => {
+            operands.push(to_term(other)?);
+            Ok(())
+        }
+    }
+}
+
+fn climb(operands: &mut impl Iterator<Item = typed::Term>, ops: &[String], pos: &mut usize, max: u32) -> typed::Term {
+    let mut left = operands.next().expect("one more operand than operator");
+    let mut left_prec = 0;
+    while let Some(op) = ops.get(*pos) {
+        let (prec, ty) = infix_op(op).expect("flatten_ops only collects operators");
+        let (left_max, right_max) = arg_priorities(prec, ty);
+        if prec > max || left_prec > left_max {
+            break;
+        }
+        *pos += 1;
+        let right = climb(operands, ops, pos, right_max);
+        left = typed::Term::Compound { name: op.clone(), args: vec![left, right] };
+        left_prec = prec;
+    }
+    left
+}
+
+// ------------------ Visitor impl ------------------
+
+impl<'input> ParseTreeVisitorCompat<'input> for AstBuilder {
+    type Node = prologParserContextType;
+    type Return = ();
+    fn temp_result(&mut self) -> &mut Self::Return { &mut self._unit }
+}
+
+impl<'input> prologVisitor<'input> for AstBuilder {
+    fn visit_fact(&mut self, ctx: &FactContext<'input>) {
+        let head = self.parse_term(&ctx.term().unwrap());
+        self.clauses.push(Clause { head, body: vec![] });
+        self.visit_children(ctx);
+    }
+
+    fn visit_rule_(&mut self, ctx: &Rule_Context<'input>) {
+        let head = self.parse_term(&ctx.head().unwrap().term().unwrap());
+        if let Some(body) = ctx.body() {
+            for tl in body.termlist_all() {
+                let terms = tl.term_all()
+                    .into_iter()
+                    .map(|t| self.parse_term(&t))
+                    .collect::<Vec<_>>();
+                self.clauses.push(Clause { head: head.clone(), body: terms });
+            }
+        }
+        self.visit_children(ctx);
+    }
+}
+// ------------------ Belépési pont ------------------
+
+/// Egy Prolog forrás klózai (az ANTLR fáról, a direktívák nélkül), a `;` ágai külön klózok.
+pub fn parse_clauses(src: &str) -> Vec<Clause> {
+    let input_stream = InputStream::new(src);
+    let lexer = prologLexer::new(input_stream);
+    let token_stream = CommonTokenStream::new(lexer);
+    let mut parser = prologParser::new(token_stream);
+
+    let tree = parser.p_text().expect("parse");
+
+    let mut builder = AstBuilder::new();
+    builder.visit_p_text(&tree);
+    builder.clauses
+}
@@ -1,14 +1,11 @@
-use antlr4rust::{common_token_stream::CommonTokenStream, InputStream};
-use antlr4rust::tree::{ParseTree, ParseTreeVisitorCompat};
 use std::collections::{HashMap, HashSet};
 
-pub mod parser;
-use parser::prologlexer::prologLexer;
-use parser::prologparser::*;
-use parser::prologvisitor::prologVisitor;
 use serde::Serialize;
 use std::fs;
 use std::path::Path;
+use common::term as typed;
+use common::utils_2::term_encoding::{builtin_layout, builtin_relation, Operand};
+use tokens::ast::{is_var_name, parse_clauses, parse_tuple_fields_keep_all, term_as_string, to_term, Clause, Term};
 
 // ------------------ Kimeneti JSON model ------------------
 
@@ -43,14 +40,15 @@ struct NodeArgRef4 {
     children_node_list: usize, // LOGIKAI index: 0 = head, 1.. = N. body-sor
     predicate: usize,          // az adott listában hányadik predikátum
     arg: usize,                // az adott predikátum argument indexe
-    list_index: usize,         // tuple/list flatten index (0..k-1), tail = k, sima = 0
+    list_index: usize,         // lista: 0 = teljes lista, head mezők 1..k, tail = k+1; sima = 0
 }
 
 #[derive(Serialize, Clone)]
 #[serde(untagged)]
 enum RightSide {
     Ref(NodeArgRef4),
-    Atom(String),
+    // típusos konstans (szám, atom, string, lista...), a prove ugyanúgy kódolja, mint a goalokat
+    Value(typed::Term),
 }
 
 #[derive(Serialize, Clone)]
@@ -59,222 +57,6 @@ struct Equality {
     right: RightSide,
 }
 
-
-// ------------------ Belső (egyszerű) AST ------------------
-
-#[derive(Debug, Clone)]
-enum Term {
-    Atom(String),
-    Var(String),
-    Predicate { name: String, args: Vec<Term> },
-    ListCell { head: Box<Term>, tail: Box<Term> },
-    EmptyList,
-}
-
-impl Term {
-    /*fn arity(&self) -> usize {
-        match self {
-            Term::Predicate { args, .. } => args.len(),
-            _ => 0,
-        }
-    }*/
-}
-
-// ------------------ Clause gyűjtő ------------------
-
-#[derive(Clone)]
-struct Clause {
-    head: Term,
-    body: Vec<Term>, // a body vesszővel tagolt elemei. Minden elem egy "sor".
-}
-
-// ------------------ Visitor → AST builder ------------------
-
-struct AstBuilder {
-    clauses: Vec<Clause>,
-    _unit: (),
-}
-
-impl AstBuilder {
-    fn new() -> Self {
-        Self { clauses: vec![], _unit: () }
-    }
-
-    // Egyszerű szögletes listaparszoló → cons-lánc
-    fn parse_list(&self, s: &str) -> Term {
-        let inner = &s[1..s.len() - 1];
-        if inner.trim().is_empty() {
-            return Term::EmptyList;
-        }
-        let (left, tail_opt) = if let Some(pos) = inner.find('|') {
-            (inner[..pos].trim(), Some(inner[pos + 1..].trim().to_string()))
-        } else {
-            (inner.trim(), None)
-        };
-
-        let parts = split_top_level_commas(left)
-            .into_iter()
-            .map(|p| p.trim().to_string())
-            .collect::<Vec<_>>();
-
-        let mut t = match tail_opt {
-            Some(tail_s) => self.parse_term_str(&tail_s),
-            None => Term::EmptyList,
-        };
-        for elem in parts.into_iter().rev() {
-            t = Term::ListCell {
-                head: Box::new(self.parse_term_str(&elem)),
-                tail: Box::new(t),
-            };
-        }
-        t
-    }
-
-    fn parse_term_str(&self, s: &str) -> Term {
-        if s.starts_with('[') && s.ends_with(']') {
-            return self.parse_list(s);
-        }
-        Term::Atom(s.into())
-    }
-
-    fn parse_term(&self, ctx: &TermContextAll) -> Term {
-        use TermContextAll::*;
-        let text = ctx.get_text();
-        if text.starts_with('[') && text.ends_with(']') {
-            return self.parse_list(&text);
-        }
-
-        match ctx {
-            VariableContext(v) => Term::Var(v.get_text()),
-            Integer_termContext(i) => Term::Atom(i.get_text()),
-            FloatContext(f) => Term::Atom(f.get_text()),
-            Atom_termContext(a) => Term::Atom(a.get_text()),
-            List_termContext(l) => Term::Atom(l.get_text()),
-            Compound_termContext(c) => {
-                let name = c.atom().unwrap().get_text();
-                let args = c
-                    .termlist().unwrap()
-                    .term_all()
-                    .into_iter()
-                    .map(|t| self.parse_term(&t))
-                    .collect();
-                Term::Predicate { name, args }
-            }
-            Binary_operatorContext(b) => {
-                let left = self.parse_term(&b.term(0).unwrap());
-                let right = self.parse_term(&b.term(1).unwrap());
-                let op = b.operator_().unwrap().get_text();
-                Term::Predicate { name: op, args: vec![left, right] }
-            }
-            Unary_operatorContext(u) => {
-                let arg = self.parse_term(&u.term().unwrap());
-                let op = u.operator_().unwrap().get_text();
-                Term::Predicate { name: op, args: vec![arg] }
-            }
-            _ => Term::Atom(ctx.get_text()),
-        }
-    }
-}
-
-fn split_top_level_commas(s: &str) -> Vec<&str> {
-    let mut out = Vec::new();
-    let mut depth_paren = 0;
-    let mut last = 0;
-    let chars: Vec<char> = s.chars().collect();
-    for (i, ch) in chars.iter().enumerate() {
-        match ch {
-            '(' => depth_paren += 1,
-            ')' => if depth_paren > 0 { depth_paren -= 1 },
-            ',' if depth_paren == 0 => {
-                out.push(&s[last..i]);
-                last = i + 1;
-            }
-            _ => {}
-        }
-    }
-    if last <= s.len() {
-        out.push(&s[last..]);
-    }
-    out
-}
-
-// Tuple "(a,b,c)" → mezők (underscore marad indexeléshez, de nem veszünk fel előfordulást)
-fn parse_tuple_fields_keep_all(atom_str: &str) -> Vec<String> {
-    let s = atom_str.trim();
-    if !(s.starts_with('(') && s.ends_with(')')) {
-        return vec![s.to_string()];
-    }
-    let inner = &s[1..s.len() - 1];
-    split_top_level_commas(inner)
-        .into_iter()
-        .map(|p| p.trim().to_string())
-        .collect()
-}
-
-fn is_var_name(s: &str) -> bool {
-    if s == "_" || s.is_empty() { return false; }
-    let first = s.chars().next().unwrap();
-    first.is_ascii_uppercase() || first == '_'
-}
-
-fn term_as_string(t: &Term) -> String {
-    match t {
-        Term::Atom(s) => s.clone(),
-        Term::Var(v) => v.clone(),
-        Term::EmptyList => "[]".to_string(),
-        Term::Predicate { name, args } => {
-            let args_s = args.iter().map(|a| term_as_string(a)).collect::<Vec<_>>().join(", ");
-            format!("{}({})", name, args_s)
-        }
-        Term::ListCell { head, tail } => {
-            let mut elems = vec![term_as_string(head)];
-            let mut cur = *tail.clone();
-            let mut guard = 0usize;
-            while let Term::ListCell { head: h, tail: t2 } = cur.clone() {
-                elems.push(term_as_string(&h));
-                cur = (*t2).clone();
-                guard += 1; if guard > 1024 { break; }
-            }
-            let tail_str = match cur {
-                Term::EmptyList => "".to_string(),
-                other => format!("|{}", term_as_string(&other)),
-            };
-            let body = elems.join(",");
-            if tail_str.is_empty() { format!("[{}]", body) } else { format!("[{}{}]", body, tail_str) }
-        }
-    }
-}
-
-// ------------------ Visitor impl ------------------
-
-impl<'input> ParseTreeVisitorCompat<'input> for AstBuilder {
-    type Node = prologParserContextType;
-    type Return = ();
-    fn temp_result(&mut self) -> &mut Self::Return { &mut self._unit }
-}
-
-impl<'input> prologVisitor<'input> for AstBuilder {
-    fn visit_fact(&mut self, ctx: &FactContext<'input>) {
-        let head = self.parse_term(&ctx.term().unwrap());
-        self.clauses.push(Clause { head, body: vec![] });
-        self.visit_children(ctx);
-    }
-
-    fn visit_rule_(&mut self, ctx: &Rule_Context<'input>) {
-        let head = self.parse_term(&ctx.head().unwrap().term().unwrap());
-        if let Some(body) = ctx.body() {
-            for tl in body.termlist_all() {
-                let terms = tl.term_all()
-                    .into_iter()
-                    .map(|t| self.parse_term(&t))
-                    .collect::<Vec<_>>();
-                self.clauses.push(Clause { head: head.clone(), body: terms });
-            }
-        }
-        self.visit_children(ctx);
-    }
-}
-
 // ------------------ Builtin op-készlet ------------------
 
 fn builtin_ops_set() -> HashSet<&'static str> {
@@ -288,38 +70,6 @@ fn builtin_ops_set() -> HashSet<&'static str> {
     ].into_iter().collect()
 }
 
-// Arith/rel kifejezésben láncolandó op-ok (az "is" kivételével)
-fn chainable_op(name: &str) -> bool {
-    matches!(name,
-        "+" | "-" | "*" | "/" | "div" | "mod" | "//" | "rem" | "**" | "^" |
-        "<<" | ">>" | "<" | ">" | ">=" | "=<" | "=:=" | "=\\=" | "=" | "\\=" | "==" | "\\=="
-    )
-}
-
-// Inorder bejárás: bal → op → jobb; minden csomópontról (op,right) visszaadjuk
-fn collect_right_args_inorder(term: &Term, acc: &mut Vec<(String, Term)>) {
-    if let Term::Predicate { name, args } = term {
-        if args.len() == 2 && chainable_op(name) {
-            collect_right_args_inorder(&args[0], acc);
-            acc.push((name.clone(), args[1].clone())); // op, right
-            collect_right_args_inorder(&args[1], acc);
-        }
-    }
-}
-
-// Bal szélső levél kinyerése egy op-fából (ha van)
-fn leftmost_leaf(term: &Term) -> Option<Term> {
-    let mut cur = term.clone();
-    loop {
-        match cur {
-            Term::Predicate { ref name, ref args } if args.len() == 2 && chainable_op(name) => {
-                cur = args[0].clone();
-            }
-            _ => return Some(cur),
-        }
-    }
-}
-
 // ------------------ Clause → kimenet ------------------
 
 fn to_output(clauses: Vec<Clause>) -> OutRoot {
@@ -344,8 +94,9 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
                     Term::Var(v) => v.clone(),
                     other => term_as_string(other),
                 };
+                // li 0 = a teljes lista értéke, a head mezői 1..k
                 let fields = parse_tuple_fields_keep_all(&head_str);
-                let mut idx = 0usize;
+                let mut idx = 1usize;
                 for f in fields {
                     if f != "_" {
                         if is_var_name(&f) {
@@ -356,7 +107,7 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
                     }
                     idx += 1;
                 }
-                // Tail = k, nem bontjuk tovább
+                // Tail = k+1, nem bontjuk tovább
                 let tail_str = match &**tail {
                     Term::Var(v) => v.clone(),
                     other => term_as_string(other),
@@ -369,6 +120,10 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
                     }
                 }
             }
+            // Üres lista: a "[]" konstans
+            Term::EmptyList => {
+                atom_pos.push((Occ4 { l, p, a, li: 0 }, "[]".to_string()));
+            }
             Term::Var(v) => {
                 // minden '_' és '_Valami' wildcard → IGNORE
                 if v == "_" || v.starts_with("_") {
@@ -379,7 +134,26 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
             Term::Atom(s) => {
                 atom_pos.push((Occ4 { l, p, a, li: 0 }, s.clone()));
             }
-            // Beágyazott predikátumot itt nem bontunk (kivéve a láncolt op-oknál külön kezeljük)
+            // Beágyazott predikátumot itt nem bontunk (a builtin kifejezésfákat külön kezeljük)
+            _ => {}
+        }
+    }
+
+    // Kifejezésfa levele: változó, vagy tömör term a (visszaolvasható) kiírt alakjával
+    fn leaf_collect(
+        leaf: &typed::Term,
+        o: Occ4,
+        var_pos: &mut HashMap<String, Vec<Occ4>>,
+        atom_pos: &mut Vec<(Occ4, String)>,
+    ) {
+        match leaf {
+            typed::Term::Var { name } => {
+                if !name.starts_with('_') {
+                    var_pos.entry(name.clone()).or_default().push(o);
+                }
+            }
+            other if other.is_ground() => atom_pos.push((o, other.to_string())),
+            // változót tartalmazó nem aritmetikai term: nem kötjük
             _ => {}
         }
     }
@@ -397,6 +171,8 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
         // Equalities gyűjtők
         let mut var_pos: HashMap<String, Vec<Occ4>> = HashMap::new();
         let mut atom_pos: Vec<(Occ4, String)> = vec![];
+        // kifejezésfa: (operandus, a csúcs eredménye) párok
+        let mut node_links: Vec<(Occ4, Occ4)> = vec![];
 
         // Default clause detektálás (pl consumptionClass default)
         let mut is_default_clause = false;
@@ -452,44 +228,39 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
     }
 
                 Term::Predicate { name, args } if builtin_ops.contains(name.as_str()) => {
-                    // Builtin: első op 2-operandusú, majd RHS inorder lánc opjai 1-operandusúak
-                    let first_name = name.clone();
-                    let (left, right) = if args.len() == 2 {
-                        (args[0].clone(), args[1].clone())
-                    } else if args.len() == 1 {
-                        (Term::Atom("".into()), args[0].clone())
-                    } else {
-                        (Term::Atom("".into()), Term::Atom("".into()))
-                    };
-                    this_list.push(ChildPred { name: first_name.clone(), arity: 2 });
-
-                    // első op bal arg
-                    flatten_arg_collect(&left,  l, 0, 0, &mut var_pos, &mut atom_pos);
-
-                    // ha a right nem op-fa, közvetlenül az első op jobb argjához
-                    let mut right_is_chain_root = false;
-                    if let Term::Predicate { name: rname, args: rargs } = &right {
-                        if rargs.len() == 2 && chainable_op(rname) {
-                            right_is_chain_root = true;
+                    let goal = to_term(t).expect("unreadable builtin goal");
+                    match builtin_layout(&goal) {
+                        // Kiértékelt reláció: rel(lhs, rhs), majd kifejezésfa-csúcsonként op(bal, jobb, eredmény)
+                        Some(layout) if builtin_relation(layout.rel) == Some(true) => {
+                            this_list.push(ChildPred { name: layout.rel.to_string(), arity: 2 });
+                            let operands = [(0, 0, layout.lhs), (0, 1, layout.rhs)].into_iter().chain(
+                                layout.nodes.iter().enumerate().flat_map(|(j, n)| [(1 + j, 0, n.left), (1 + j, 1, n.right)]),
+                            );
+                            for node in &layout.nodes {
+                                this_list.push(ChildPred { name: node.op.to_string(), arity: 3 });
+                            }
+                            for (p, a, operand) in operands {
+                                let o = Occ4 { l, p, a, li: 0 };
+                                match operand {
+                                    Operand::Leaf(leaf) => leaf_collect(leaf, o, &mut var_pos, &mut atom_pos),
+                                    // a j. csúcs eredménye (l, 1+j, 2, 0) = ez az operandus
+                                    Operand::Node(j) => node_links.push((o, Occ4 { l, p: 1 + j, a: 2, li: 0 })),
+                                }
+                            }
                         }
-                    }
-                    if !right_is_chain_root {
-                        flatten_arg_collect(&right, l, 0, 1, &mut var_pos, &mut atom_pos);
-                    } else {
-                        // <<< ÚJ >>> Ha op-fa, akkor a BAL SZÉLSŐ LEVÉL is legyen felvéve az 'is' jobb arg (l,0,1) helyére,
-                        // hogy a head-beli azonos nevű változóval equality jöjjön létre.
-                        if let Some(leftmost) = leftmost_leaf(&right) {
-                            flatten_arg_collect(&leftmost, l, 0, 1, &mut var_pos, &mut atom_pos);
+                        // =, \=, ==, \==: a két oldal értéke, listák a szokásos bontással
+                        Some(layout) => {
+                            this_list.push(ChildPred { name: layout.rel.to_string(), arity: 2 });
+                            flatten_arg_collect(&args[0], l, 0, 0, &mut var_pos, &mut atom_pos);
+                            flatten_arg_collect(&args[1], l, 0, 1, &mut var_pos, &mut atom_pos);
+                        }
+                        // egyéb builtin (\+, @<, ...): mint egy normál predikátum
+                        None => {
+                            this_list.push(ChildPred { name: name.clone(), arity: args.len() });
+                            for (ai, a) in args.iter().enumerate() {
+                                flatten_arg_collect(a, l, 0, ai, &mut var_pos, &mut atom_pos);
+                            }
                         }
-                    }
-
-                    // Jobb oldal lánc opjai balról jobbra (mind arity=1), és CSAK a jobboldali operandust gyűjtjük (arg=0)
-                    let mut chain: Vec<(String, Term)> = vec![];
-                    collect_right_args_inorder(&right, &mut chain);
-                    for (k, (opn, rterm)) in chain.into_iter().enumerate() {
-                        let pred_idx = 1 + k; // ebben a listában az első op után
-                        this_list.push(ChildPred { name: opn, arity: 1 });
-                        flatten_arg_collect(&rterm, l, pred_idx, 0, &mut var_pos, &mut atom_pos);
                     }
                 }
 
@@ -576,7 +347,8 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
         ) {
             let a = NodeArgRef4 { children_node_list: l.l, predicate: l.p, arg: l.a, list_index: l.li };
             if seen_atoms.insert((a, lit.clone())) {
-                equalities.push(Equality { left: a, right: RightSide::Atom(lit) });
+                let value = typed::Term::read(&lit).unwrap_or_else(|e| panic!("bad literal {lit}: {e}"));
+                equalities.push(Equality { left: a, right: RightSide::Value(value) });
             }
         }
 
@@ -589,6 +361,10 @@ fn to_output(clauses: Vec<Clause>) -> OutRoot {
                 }
             }
         }
+        // Kifejezésfa élei
+        for (operand, result) in node_links {
+            add_ref_eq(&mut equalities, &mut seen_refs, operand, result);
+        }
         // Literál kötés
         for (o, lit) in atom_pos {
             if !lit.starts_with("_") {
@@ -621,20 +397,11 @@ fn main() {
     // After tests/testX X = the test we want
     //let input = std::fs::read_to_string("./tests/test1/policy.pl").expect("read failed");
 
-    // ANTLR: lex + parse
-    let input_stream = InputStream::new(input.as_str());
-    let lexer = prologLexer::new(input_stream);
-    let token_stream = CommonTokenStream::new(lexer);
-    let mut parser = prologParser::new(token_stream);
-
-    let tree = parser.p_text().expect("parse");
-
-    // AST építés
-    let mut builder = AstBuilder::new();
-    builder.visit_p_text(&tree);
+    // ANTLR: lex + parse, AST építés
+    let clauses = parse_clauses(&input);
 
     // JSON-összerakás
-    let out = to_output(builder.clauses);
+    let out = to_output(clauses);
     let json = serde_json::to_string_pretty(&out).expect("json serialize");
 
     let dir = Path::new("input");
@@ -0,0 +1,58 @@
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{BatchVerifier, VerifyingKey},
+    poly::commitment::Params,
+};
+
+/// Instances of one proof, in the `Vec` form `BatchVerifier::add_proof` takes.
+pub type ProofInstances = Vec<Vec<Vec<Fp>>>;
+
+/// Verifies `proofs` with one `BatchVerifier` (a single random linear combination of the MSMs).
+/// If the batch fails it is bisected until the offending proofs are found.
+/// Returns the indices of the invalid proofs, empty if everything verified.
+pub fn batch_verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proofs: &[(ProofInstances, &[u8])],
+) -> Vec<usize> {
+    let indices: Vec<usize> = (0..proofs.len()).collect();
+    let mut bad = find_invalid(params, vk, proofs, &indices);
+    bad.sort_unstable();
+    bad
+}
+
+fn batch_ok(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proofs: &[(ProofInstances, &[u8])],
+    indices: &[usize],
+) -> bool {
+    let mut batch = BatchVerifier::new();
+    for &i in indices {
+        let (instances, proof) = &proofs[i];
+        batch.add_proof(instances.clone(), proof.to_vec());
+    }
+    batch.finalize(params, vk)
+}
+
+fn find_invalid(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proofs: &[(ProofInstances, &[u8])],
+    indices: &[usize],
+) -> Vec<usize> {
+    if indices.is_empty() || batch_ok(params, vk, proofs, indices) {
+        return Vec::new();
+    }
+    if indices.len() == 1 {
+        return indices.to_vec();
+    }
+
+    let (left, right) = indices.split_at(indices.len() / 2);
+    let (mut bad, bad_right) = rayon::join(
+        || find_invalid(params, vk, proofs, left),
+        || find_invalid(params, vk, proofs, right),
+    );
+    bad.extend(bad_right);
+    bad
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use common::{
+    circuit_shape::CircuitShape,
+    io::fp_decimal::fp_to_dec_string,
+    term::Term,
+    utils_2::{
+        off_circuit_poseidon::{goal_commitment_native, leaf_goal_commitments},
+        term_encoding::{encode_builtin, encode_predicate, is_builtin_goal},
+    },
+};
+use halo2_proofs::pasta::Fp;
+
+/// Checks that the goal commitments of the proofs form a single proof tree: every child slot
+/// is a leaf or the head goal of exactly one other proof, every proof except the root is the
+/// child of exactly one proof and everything is reachable from the root.
+/// Returns the index of the root proof.
+pub fn check_proof_tree(goal_commitments: &[Vec<Fp>], shape: &CircuitShape) -> Result<usize> {
+    let leaves: Vec<String> = leaf_goal_commitments(shape).iter().map(fp_to_dec_string).collect();
+
+    let mut heads: HashMap<String, usize> = HashMap::new();
+    for (i, c) in goal_commitments.iter().enumerate() {
+        let head = c.first().ok_or_else(|| anyhow::anyhow!("Proof {i} has no goal commitment"))?;
+        if heads.insert(fp_to_dec_string(head), i).is_some() {
+            anyhow::bail!("Proof {i}: head goal commitment is used by another proof too");
+        }
+    }
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); goal_commitments.len()];
+    let mut parents = vec![0usize; goal_commitments.len()];
+    for (i, c) in goal_commitments.iter().enumerate() {
+        for (k, child) in c.iter().enumerate().skip(1) {
+            let key = fp_to_dec_string(child);
+            if leaves.contains(&key) {
+                continue;
+            }
+            let j = *heads
+                .get(&key)
+                .ok_or_else(|| anyhow::anyhow!("Proof {i}: child {} has no proof", k - 1))?;
+            children[i].push(j);
+            parents[j] += 1;
+        }
+    }
+
+    if let Some(j) = parents.iter().position(|p| *p > 1) {
+        anyhow::bail!("Proof {j} is the child of more than one goal");
+    }
+    let roots: Vec<usize> = (0..parents.len()).filter(|j| parents[*j] == 0).collect();
+    let root = match roots.as_slice() {
+        [root] => *root,
+        _ => anyhow::bail!("Expected a single root goal, found {}", roots.len()),
+    };
+
+    // one root and one parent per node: the proofs form a tree iff all are reachable
+    let mut seen = vec![false; goal_commitments.len()];
+    let mut stack = vec![root];
+    while let Some(i) = stack.pop() {
+        if !seen[i] {
+            seen[i] = true;
+            stack.extend(children[i].iter().copied());
+        }
+    }
+    if let Some(j) = seen.iter().position(|s| !s) {
+        anyhow::bail!("Proof {j} is not reachable from the root goal");
+    }
+
+    Ok(root)
+}
+
+/// Head goal commitment of the proof of `goal` as a root: the prover commits root goals with salt 0
+/// and encodes them like any goal (the fact salt of a term is not committed).
+pub fn root_goal_commitment(goal: &str, shape: &CircuitShape) -> Result<Fp> {
+    let term = Term::read(goal).with_context(|| format!("Bad root goal {goal}"))?;
+    let terms = if is_builtin_goal(&term) {
+        encode_builtin(&term, shape)
+    } else {
+        vec![encode_predicate(&term, Fp::zero(), shape)]
+    };
+    Ok(goal_commitment_native(&terms, Fp::zero(), shape))
+}
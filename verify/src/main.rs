@@ -1,74 +1,188 @@
+mod batch;
+mod chain;
 mod reader;
+mod report;
 
 use common::data::{RuleTemplateFile, RuleTemplateFileFp, UnificationInputFp};
+use common::chips::fact_check::compare_chip::i128_to_fp;
 use common::unification_checker_circuit::UnificationCircuit;
-use common::io::read_fact_hashes::read_fact_hashes;
-//use common::utils_2::off_circuit_poseidon::poseidon_hash_list_native;
-use reader::read_proofs_bytes;
+use common::io::{fp_decimal::fp_to_dec_string, issuer::{read_issuer_pk, read_root, FACT_ROOT_PATH, ISSUER_PK_PATH, REVOCATION_ROOT_PATH}, keys::keygen_checked_vk};
+use common::circuit_shape::{CircuitShape, CIRCUIT_SHAPE_PATH};
+use common::utils_2::off_circuit_poseidon::poseidon_hash_list_native;
+use batch::{batch_verify, ProofInstances};
+use chain::{check_proof_tree, root_goal_commitment};
+use reader::read_proofs;
+use report::{ProofReport, VerifyReport};
 
 use std::{fs, path::Path};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use rayon::prelude::*;
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use halo2_proofs::{
     pasta::{EqAffine, Fp},
-    plonk::{keygen_vk, verify_proof, SingleVerifier, VerifyingKey},
-    poly::commitment::Params,
+    plonk::{verify_proof, SingleVerifier},
     transcript::{Blake2bRead, Challenge255},
 };
 
 fn main() -> Result<()> {
+    // --batch: one BatchVerifier for all proofs, bisecting to the bad ones if it fails
+    let batch_mode = std::env::args().any(|a| a == "--batch");
+    // --max-age <seconds>: how old the verification time of a proof may be (default: one day)
+    let max_age = max_age_arg()?;
+    // --goal <goal>: the root goal the proofs have to prove, e.g. --goal "endPrice(931220)"
+    let goal = goal_arg()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
     // Load proofs
-    let proofs: Vec<Vec<u8>> = read_proofs_bytes("unif")?;
-    // Load public hashes
-    let path = Path::new("input/fact_hashes.json");
-    let public_hashes = read_fact_hashes(path)?;
+    let proofs = read_proofs("unif")?;
+    // The facts are trusted through the issuer's public key, the root of its fact registry and the
+    // current root of its revocation tree
+    let issuer_pk = read_issuer_pk(Path::new(ISSUER_PK_PATH))?;
+    let fact_root = read_root(Path::new(FACT_ROOT_PATH))?;
+    let revocation_root = read_root(Path::new(REVOCATION_ROOT_PATH))?;
 
     // Debug (12 bytes/proof)
     println!("Verifying {} unification proofs", proofs.len());
 
+    // Same circuit dimensions as the prover
+    let circuit_shape = CircuitShape::load_or_default(Path::new(CIRCUIT_SHAPE_PATH))?;
+
     // Load Rules
     let rules_text = fs::read_to_string("input/rules.json")?;
     let rules: RuleTemplateFile = serde_json::from_str(&rules_text)?;
 
     let rules_fp = RuleTemplateFileFp::from(&rules);
-    //let flatten_rules_fp = RuleTemplateFileFp::to_flat_vec(&rules_fp);
-    //let public_rules_hashes = poseidon_hash_list_native(&flatten_rules_fp);
-    // Same params + vkgen
-    let params: Params<EqAffine> = Params::new(16);
+    let flatten_rules_fp = RuleTemplateFileFp::to_flat_vec(&rules_fp);
+    let public_rules_hashes = poseidon_hash_list_native(&flatten_rules_fp);
+    // Same params + vkgen (only the params are cached in keys/, the keys are generated every run)
     let shape = UnificationCircuit {
         rules: rules_fp,
-        unif: UnificationInputFp::default(),
+        unif: UnificationInputFp::empty(&circuit_shape),
+        shape: circuit_shape,
     };
-
-    let vk: VerifyingKey<EqAffine> = keygen_vk(&params, &shape)?;
+    let (params, vk) = keygen_checked_vk(Path::new("keys"), &shape, &public_rules_hashes)?;
     let params = Arc::new(params);
     let vk = Arc::new(vk);
 
     // Constructing the public inputs
-    let instance_columns: &[&[Fp]] = &[
-        &public_hashes/*,   // first instance column
-        std::slice::from_ref(&public_rules_hashes), // second instance column*/
-    ];
-
-    // Wrap into &[&[&[Fp]]] for create_proof
-    let public_inputs: &[&[&[Fp]]] = &[instance_columns];
-
-    // Parallel verification
-    let ok = proofs
-        .par_iter()
-        .all(|proof| {
-            let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&proof[..]);
-            let strategy = SingleVerifier::new(params.as_ref());
-            verify_proof(params.as_ref(), vk.as_ref(), strategy, &public_inputs, &mut transcript).is_ok()
-        });
-
-    if ok {
-        println!("All proofs verified successfully!");
+    let instances: Vec<ProofInstances> = proofs
+        .iter()
+        .map(|stored| vec![vec![
+            issuer_pk.to_vec(),                     // first instance column
+            vec![fact_root],                        // second instance column
+            vec![revocation_root],                  // third instance column
+            vec![i128_to_fp(stored.verification_time.into())], // fourth instance column
+            vec![public_rules_hashes],              // fifth instance column
+            stored.goal_commitments.clone(),        // sixth instance column
+        ]])
+        .collect();
+
+    // The facts were checked at the proof's verification time, it has to be recent
+    let check_time = |i: usize| -> Result<(), String> {
+        let t = proofs[i].verification_time;
+        if t > now {
+            return Err(format!("verification time {t} is in the future"));
+        }
+        if now - t > max_age {
+            return Err(format!("verification time {t} is older than {max_age} seconds"));
+        }
+        Ok(())
+    };
+
+    // Single verification of one proof, Err carries the halo2 error
+    let verify_one = |i: usize| -> Result<(), String> {
+        check_time(i)?;
+        let columns: Vec<&[Fp]> = instances[i][0].iter().map(|c| c.as_slice()).collect();
+        let public_inputs: &[&[&[Fp]]] = &[columns.as_slice()];
+
+        let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(&proofs[i].proof[..]);
+        let strategy = SingleVerifier::new(params.as_ref());
+        verify_proof(params.as_ref(), vk.as_ref(), strategy, public_inputs, &mut transcript)
+            .map(|_| ())
+            .map_err(|e| format!("{e:?}"))
+    };
+
+    let results: Vec<Result<(), String>> = if batch_mode {
+        let batch: Vec<(ProofInstances, &[u8])> = instances
+            .iter()
+            .zip(proofs.iter())
+            .map(|(inst, stored)| (inst.clone(), stored.proof.as_slice()))
+            .collect();
+        let bad = batch_verify(params.as_ref(), vk.as_ref(), &batch);
+
+        // only the proofs found by the bisection are re-run, to get their error
+        (0..proofs.len())
+            .into_par_iter()
+            .map(|i| if bad.contains(&i) { verify_one(i).and(Err("rejected by the batch verifier".to_string())) } else { check_time(i) })
+            .collect()
     } else {
-        println!("Some proofs failed verification!");
+        // Parallel verification
+        (0..proofs.len()).into_par_iter().map(verify_one).collect()
+    };
+
+    // The proofs have to chain up to a single root goal, the expected one if it is given
+    let commitments: Vec<Vec<Fp>> = proofs.iter().map(|p| p.goal_commitments.clone()).collect();
+    let tree = check_proof_tree(&commitments, &circuit_shape).and_then(|root| {
+        if let Some(goal) = &goal {
+            if commitments[root][0] != root_goal_commitment(goal, &circuit_shape)? {
+                anyhow::bail!("The root goal of the proofs is not {goal}");
+            }
+        }
+        Ok(root)
+    });
+    if goal.is_none() {
+        println!("No --goal given, the root goal is not checked");
+    }
+
+    // The commitments are salted, only the root goal can be named, once it matched --goal
+    let root_goal = tree.as_ref().ok().and(goal);
+    let report = VerifyReport {
+        proofs: results
+            .into_iter()
+            .enumerate()
+            .map(|(index, r)| ProofReport {
+                index,
+                goal: root_goal.clone().filter(|_| tree.as_ref().is_ok_and(|root| *root == index)),
+                head_goal_commitment: commitments[index].first().map(fp_to_dec_string).unwrap_or_default(),
+                verified: r.is_ok(),
+                error: r.err(),
+            })
+            .collect(),
+        proof_tree_ok: tree.is_ok(),
+        root_goal,
+        root_goal_commitment: tree.as_ref().ok().map(|root| fp_to_dec_string(&commitments[*root][0])),
+        proof_tree_error: tree.as_ref().err().map(|e| e.to_string()),
+    };
+
+    report.print();
+    report.write_json(Path::new("output/verify_report.json"))?;
+
+    if !report.all_ok() {
+        std::process::exit(1);
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// Value of `--max-age <seconds>`, one day without it
+fn max_age_arg() -> Result<i64> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|a| a == "--max-age") {
+        Some(i) => {
+            let value = args.get(i + 1).context("--max-age needs a number of seconds")?;
+            value.parse().with_context(|| format!("Bad maximum age {value}"))
+        }
+        None => Ok(24 * 60 * 60),
+    }
+}
+
+// Value of `--goal <goal>`, the root goal is not checked without it
+fn goal_arg() -> Result<Option<String>> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|a| a == "--goal") {
+        Some(i) => Ok(Some(args.get(i + 1).context("--goal needs a goal")?.clone())),
+        None => Ok(None),
+    }
+}
@@ -3,13 +3,25 @@ use std::path::Path;
 use serde::{Serialize, Deserialize};
 use base64::{engine::general_purpose, Engine as _};
 use anyhow::Result;
+use common::io::fp_decimal::fp_from_dec_str;
+use halo2_proofs::pasta::Fp;
 
 #[derive(Serialize, Deserialize)]
 pub struct ProofEntry {
     pub proof_b64: String,
+    pub goal_commitments: Vec<String>,
+    pub verification_time: i64,
 }
 
-pub fn read_proofs_bytes(name: &str) -> Result<Vec<Vec<u8>>> {
+pub struct StoredProof {
+    pub proof: Vec<u8>,
+    // public_goal_commitments: head goal, then the child slots
+    pub goal_commitments: Vec<Fp>,
+    // unix seconds, the facts of the proof are valid at this time
+    pub verification_time: i64,
+}
+
+pub fn read_proofs(name: &str) -> Result<Vec<StoredProof>> {
     let file_path = Path::new("output").join(format!("{}_proofs.json", name));
     if !file_path.exists() {
         anyhow::bail!("File not found: {}", file_path.display());
@@ -18,10 +30,18 @@ pub fn read_proofs_bytes(name: &str) -> Result<Vec<Vec<u8>>> {
     let content = fs::read_to_string(&file_path)?;
     let entries: Vec<ProofEntry> = serde_json::from_str(&content)?;
 
-    let proofs_bytes: Vec<Vec<u8>> = entries
+    entries
         .into_iter()
-        .map(|entry| general_purpose::STANDARD.decode(&entry.proof_b64).unwrap())
-        .collect();
-
-    Ok(proofs_bytes)
-}
\ No newline at end of file
+        .map(|entry| {
+            Ok(StoredProof {
+                proof: general_purpose::STANDARD.decode(&entry.proof_b64)?,
+                goal_commitments: entry
+                    .goal_commitments
+                    .iter()
+                    .map(|s| fp_from_dec_str(s))
+                    .collect::<Result<_>>()?,
+                verification_time: entry.verification_time,
+            })
+        })
+        .collect()
+}
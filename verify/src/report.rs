@@ -0,0 +1,68 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ProofReport {
+    pub index: usize,
+    // the head goal, only known for the root proof when --goal matched it
+    pub goal: Option<String>,
+    // salted commitment (decimal) of the head goal, the verifier can not open it
+    pub head_goal_commitment: String,
+    pub verified: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct VerifyReport {
+    pub proofs: Vec<ProofReport>,
+    pub proof_tree_ok: bool,
+    // the --goal the root proof was checked against
+    pub root_goal: Option<String>,
+    pub root_goal_commitment: Option<String>,
+    pub proof_tree_error: Option<String>,
+}
+
+impl VerifyReport {
+    pub fn all_ok(&self) -> bool {
+        self.proof_tree_ok && self.proofs.iter().all(|p| p.verified)
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn print(&self) {
+        for p in &self.proofs {
+            let goal = match &p.goal {
+                Some(goal) => format!("goal {goal}"),
+                None => format!("goal commitment {}", p.head_goal_commitment),
+            };
+            match &p.error {
+                None => println!("[ OK ] proof {} {goal}", p.index),
+                Some(e) => println!("[FAIL] proof {} {goal}: {e}", p.index),
+            }
+        }
+
+        match (&self.root_goal, &self.root_goal_commitment, &self.proof_tree_error) {
+            (Some(goal), _, _) => println!("Proof tree OK, root goal: {goal}"),
+            (None, Some(root), _) => println!("Proof tree OK, root goal commitment: {root}"),
+            (None, None, Some(e)) => println!("Proofs do not form a proof tree: {e}"),
+            (None, None, None) => {}
+        }
+
+        let failed = self.proofs.iter().filter(|p| !p.verified).count();
+        println!("{} / {} proofs verified", self.proofs.len() - failed, self.proofs.len());
+
+        if self.all_ok() {
+            println!("All proofs verified successfully!");
+        } else {
+            println!("Some proofs failed verification!");
+        }
+    }
+}